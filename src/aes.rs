@@ -0,0 +1,373 @@
+use set2::aes_cbc::{openssl_ecb_decrypt_block, openssl_ecb_encrypt_block};
+
+// AES (Rijndael with 128-bit blocks) as specified in FIPS-197.
+//
+// The state is a 4x4 matrix of bytes stored column by column, so byte i is row i % 4 of column
+// i / 4. Each round substitutes every byte through the S-box, rotates row r left by r, mixes each
+// column as a polynomial over GF(2^8) and XORs in a round key. The last round skips MixColumns.
+//
+// This is a straightforward table-based implementation that's slow and leaks through cache timing.
+// It's here so attacks can get at the internals, e.g. to inject faults between rounds. OpenSSL
+// stays the default backend.
+//
+// https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.197.pdf
+
+pub const BLOCK_SIZE: usize = 16;
+
+pub type Block = [u8; BLOCK_SIZE];
+
+/// S-box: multiplicative inverse in GF(2^8) followed by an affine map
+pub const SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+/// Inverse of the S-box, used when decrypting
+pub const INV_SBOX: [u8; 256] = [
+    0x52, 0x09, 0x6a, 0xd5, 0x30, 0x36, 0xa5, 0x38, 0xbf, 0x40, 0xa3, 0x9e, 0x81, 0xf3, 0xd7, 0xfb,
+    0x7c, 0xe3, 0x39, 0x82, 0x9b, 0x2f, 0xff, 0x87, 0x34, 0x8e, 0x43, 0x44, 0xc4, 0xde, 0xe9, 0xcb,
+    0x54, 0x7b, 0x94, 0x32, 0xa6, 0xc2, 0x23, 0x3d, 0xee, 0x4c, 0x95, 0x0b, 0x42, 0xfa, 0xc3, 0x4e,
+    0x08, 0x2e, 0xa1, 0x66, 0x28, 0xd9, 0x24, 0xb2, 0x76, 0x5b, 0xa2, 0x49, 0x6d, 0x8b, 0xd1, 0x25,
+    0x72, 0xf8, 0xf6, 0x64, 0x86, 0x68, 0x98, 0x16, 0xd4, 0xa4, 0x5c, 0xcc, 0x5d, 0x65, 0xb6, 0x92,
+    0x6c, 0x70, 0x48, 0x50, 0xfd, 0xed, 0xb9, 0xda, 0x5e, 0x15, 0x46, 0x57, 0xa7, 0x8d, 0x9d, 0x84,
+    0x90, 0xd8, 0xab, 0x00, 0x8c, 0xbc, 0xd3, 0x0a, 0xf7, 0xe4, 0x58, 0x05, 0xb8, 0xb3, 0x45, 0x06,
+    0xd0, 0x2c, 0x1e, 0x8f, 0xca, 0x3f, 0x0f, 0x02, 0xc1, 0xaf, 0xbd, 0x03, 0x01, 0x13, 0x8a, 0x6b,
+    0x3a, 0x91, 0x11, 0x41, 0x4f, 0x67, 0xdc, 0xea, 0x97, 0xf2, 0xcf, 0xce, 0xf0, 0xb4, 0xe6, 0x73,
+    0x96, 0xac, 0x74, 0x22, 0xe7, 0xad, 0x35, 0x85, 0xe2, 0xf9, 0x37, 0xe8, 0x1c, 0x75, 0xdf, 0x6e,
+    0x47, 0xf1, 0x1a, 0x71, 0x1d, 0x29, 0xc5, 0x89, 0x6f, 0xb7, 0x62, 0x0e, 0xaa, 0x18, 0xbe, 0x1b,
+    0xfc, 0x56, 0x3e, 0x4b, 0xc6, 0xd2, 0x79, 0x20, 0x9a, 0xdb, 0xc0, 0xfe, 0x78, 0xcd, 0x5a, 0xf4,
+    0x1f, 0xdd, 0xa8, 0x33, 0x88, 0x07, 0xc7, 0x31, 0xb1, 0x12, 0x10, 0x59, 0x27, 0x80, 0xec, 0x5f,
+    0x60, 0x51, 0x7f, 0xa9, 0x19, 0xb5, 0x4a, 0x0d, 0x2d, 0xe5, 0x7a, 0x9f, 0x93, 0xc9, 0x9c, 0xef,
+    0xa0, 0xe0, 0x3b, 0x4d, 0xae, 0x2a, 0xf5, 0xb0, 0xc8, 0xeb, 0xbb, 0x3c, 0x83, 0x53, 0x99, 0x61,
+    0x17, 0x2b, 0x04, 0x7e, 0xba, 0x77, 0xd6, 0x26, 0xe1, 0x69, 0x14, 0x63, 0x55, 0x21, 0x0c, 0x7d,
+];
+
+// Round constants for the key schedule: x^(i - 1) in GF(2^8)
+const RCON: [u8; 10] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36];
+
+/// AES with an expanded key. The key length picks AES-128, AES-192 or AES-256.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aes {
+    round_keys: Vec<Block>,
+}
+
+impl Aes {
+    pub fn new(key: &[u8]) -> Result<Self, String> {
+        Ok(Aes { round_keys: expand_key(key)? })
+    }
+
+    /// Number of rounds: 10, 12 or 14
+    pub fn rounds(&self) -> usize {
+        self.round_keys.len() - 1
+    }
+
+    pub fn round_keys_ref(&self) -> &[Block] {
+        &self.round_keys
+    }
+
+    pub fn encrypt_block(&self, block: &Block) -> Block {
+        self.encrypt_block_with(block, |_, _| {})
+    }
+
+    /// Encrypt, calling hook with the state at the start of each round 1..=rounds. The hook can
+    /// record the state or change it to simulate a fault.
+    pub fn encrypt_block_with<F: FnMut(usize, &mut Block)>(&self, block: &Block, mut hook: F) -> Block {
+        let rounds = self.rounds();
+        let mut state = *block;
+        add_round_key(&mut state, &self.round_keys[0]);
+
+        for round in 1..=rounds {
+            hook(round, &mut state);
+            sub_bytes(&mut state);
+            shift_rows(&mut state);
+            if round != rounds {
+                mix_columns(&mut state);
+            }
+            add_round_key(&mut state, &self.round_keys[round]);
+        }
+
+        state
+    }
+
+    pub fn decrypt_block(&self, block: &Block) -> Block {
+        let rounds = self.rounds();
+        let mut state = *block;
+
+        for round in (1..=rounds).rev() {
+            add_round_key(&mut state, &self.round_keys[round]);
+            if round != rounds {
+                inv_mix_columns(&mut state);
+            }
+            inv_shift_rows(&mut state);
+            inv_sub_bytes(&mut state);
+        }
+        add_round_key(&mut state, &self.round_keys[0]);
+
+        state
+    }
+}
+
+/// Which implementation performs block operations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backend {
+    #[default]
+    OpenSsl,
+    Native,
+}
+
+impl Backend {
+    /// Encrypt each 16-byte block of data independently, i.e. ECB without padding
+    pub fn encrypt_block(self, data: &[u8], key: &[u8]) -> Result<Vec<u8>, String> {
+        check_lengths(data, key)?;
+        match self {
+            Backend::OpenSsl => openssl_ecb_encrypt_block(data, key).map_err(|e| e.to_string()),
+            Backend::Native => {
+                let aes = Aes::new(key)?;
+                Ok(map_blocks(data, |block| aes.encrypt_block(block)))
+            }
+        }
+    }
+
+    pub fn decrypt_block(self, data: &[u8], key: &[u8]) -> Result<Vec<u8>, String> {
+        check_lengths(data, key)?;
+        match self {
+            Backend::OpenSsl => openssl_ecb_decrypt_block(data, key).map_err(|e| e.to_string()),
+            Backend::Native => {
+                let aes = Aes::new(key)?;
+                Ok(map_blocks(data, |block| aes.decrypt_block(block)))
+            }
+        }
+    }
+}
+
+/// Key schedule: expand a 16, 24 or 32 byte key into rounds + 1 round keys
+pub fn expand_key(key: &[u8]) -> Result<Vec<Block>, String> {
+    let nk = match key.len() {
+        16 | 24 | 32 => key.len() / 4,
+        len => return Err(format!("Invalid AES key length: {}", len)),
+    };
+    let rounds = nk + 6;
+
+    let mut words: Vec<[u8; 4]> = key.chunks(4).map(|w| [w[0], w[1], w[2], w[3]]).collect();
+    for i in nk..4 * (rounds + 1) {
+        let mut word = words[i - 1];
+        if i % nk == 0 {
+            // RotWord, SubWord and the round constant
+            word = [SBOX[word[1] as usize], SBOX[word[2] as usize], SBOX[word[3] as usize], SBOX[word[0] as usize]];
+            word[0] ^= RCON[i / nk - 1];
+        } else if nk > 6 && i % nk == 4 {
+            word = [SBOX[word[0] as usize], SBOX[word[1] as usize], SBOX[word[2] as usize], SBOX[word[3] as usize]];
+        }
+        let prev = words[i - nk];
+        words.push([prev[0] ^ word[0], prev[1] ^ word[1], prev[2] ^ word[2], prev[3] ^ word[3]]);
+    }
+
+    Ok(words.chunks(4).map(|w| {
+        let mut round_key = [0; BLOCK_SIZE];
+        for (dst, src) in round_key.chunks_mut(4).zip(w) {
+            dst.copy_from_slice(src);
+        }
+        round_key
+    }).collect())
+}
+
+pub fn sub_bytes(state: &mut Block) {
+    for byte in state.iter_mut() {
+        *byte = SBOX[*byte as usize];
+    }
+}
+
+pub fn inv_sub_bytes(state: &mut Block) {
+    for byte in state.iter_mut() {
+        *byte = INV_SBOX[*byte as usize];
+    }
+}
+
+/// Rotate row r left by r columns
+pub fn shift_rows(state: &mut Block) {
+    let old = *state;
+    for (i, byte) in state.iter_mut().enumerate() {
+        let (row, col) = (i % 4, i / 4);
+        *byte = old[row + 4 * ((col + row) % 4)];
+    }
+}
+
+pub fn inv_shift_rows(state: &mut Block) {
+    let old = *state;
+    for (i, byte) in state.iter_mut().enumerate() {
+        let (row, col) = (i % 4, i / 4);
+        *byte = old[row + 4 * ((col + 4 - row) % 4)];
+    }
+}
+
+/// Multiply each column by 3x^3 + x^2 + x + 2 modulo x^4 + 1
+pub fn mix_columns(state: &mut Block) {
+    for col in state.chunks_mut(4) {
+        let c = [col[0], col[1], col[2], col[3]];
+        for (row, byte) in col.iter_mut().enumerate() {
+            *byte = gmul(c[row], 2) ^ gmul(c[(row + 1) % 4], 3) ^ c[(row + 2) % 4] ^ c[(row + 3) % 4];
+        }
+    }
+}
+
+/// Multiply each column by the inverse polynomial 11x^3 + 13x^2 + 9x + 14
+pub fn inv_mix_columns(state: &mut Block) {
+    for col in state.chunks_mut(4) {
+        let c = [col[0], col[1], col[2], col[3]];
+        for (row, byte) in col.iter_mut().enumerate() {
+            *byte = gmul(c[row], 14) ^ gmul(c[(row + 1) % 4], 11) ^ gmul(c[(row + 2) % 4], 13) ^ gmul(c[(row + 3) % 4], 9);
+        }
+    }
+}
+
+pub fn add_round_key(state: &mut Block, round_key: &Block) {
+    for (byte, k) in state.iter_mut().zip(round_key.iter()) {
+        *byte ^= k;
+    }
+}
+
+// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x + 1
+fn gmul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 == 1 {
+            product ^= a;
+        }
+        let carry = a & 0x80 != 0;
+        a <<= 1;
+        if carry {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+fn check_lengths(data: &[u8], key: &[u8]) -> Result<(), String> {
+    if ![16, 24, 32].contains(&key.len()) {
+        return Err(format!("Invalid AES key length: {}", key.len()));
+    }
+    if data.len() % BLOCK_SIZE != 0 {
+        return Err(format!("Data isn't a whole number of blocks: {} bytes", data.len()));
+    }
+    Ok(())
+}
+
+fn map_blocks<F: Fn(&Block) -> Block>(data: &[u8], f: F) -> Vec<u8> {
+    data.chunks(BLOCK_SIZE).flat_map(|chunk| {
+        let mut block = [0; BLOCK_SIZE];
+        block.copy_from_slice(chunk);
+        f(&block).to_vec()
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use aes::*;
+    use set1::{hex_decode, hex_encode};
+    use util::{rand_bytes};
+
+    fn block(hex: &str) -> Block {
+        let mut block = [0; BLOCK_SIZE];
+        block.copy_from_slice(&hex_decode(hex));
+        block
+    }
+
+    #[test]
+    fn aes_fips_197_vectors() {
+        // Appendix C
+        let plaintext = block("00112233445566778899aabbccddeeff");
+        let vectors = [
+            ("000102030405060708090a0b0c0d0e0f", 10, "69c4e0d86a7b0430d8cdb78070b4c55a"),
+            ("000102030405060708090a0b0c0d0e0f1011121314151617", 12, "dda97ca4864cdfe06eaf70a0ec0d7191"),
+            ("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f", 14, "8ea2b7ca516745bfeafc49904b496089"),
+        ];
+        for (key, rounds, ciphertext) in vectors.iter() {
+            let aes = Aes::new(&hex_decode(key)).unwrap();
+            assert_eq!(aes.rounds(), *rounds);
+            assert_eq!(aes.encrypt_block(&plaintext), block(ciphertext), "key {}", key);
+            assert_eq!(aes.decrypt_block(&block(ciphertext)), plaintext, "key {}", key);
+        }
+    }
+
+    #[test]
+    fn aes_key_expansion() {
+        // Appendix A: the last word of each expanded key
+        let vectors = [
+            ("2b7e151628aed2a6abf7158809cf4f3c", "b6630ca6"),
+            ("8e73b0f7da0e6452c810f32b809079e562f8ead2522c6b7b", "01002202"),
+            ("603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4", "706c631e"),
+        ];
+        for (key, last_word) in vectors.iter() {
+            let round_keys = expand_key(&hex_decode(key)).unwrap();
+            assert_eq!(hex_encode(&round_keys.last().unwrap()[12..]), *last_word);
+        }
+        assert!(expand_key(&[0; 20]).is_err());
+    }
+
+    #[test]
+    fn aes_round_functions() {
+        // Appendix B, round 1
+        let mut state = block("193de3bea0f4e22b9ac68d2ae9f84808");
+        sub_bytes(&mut state);
+        assert_eq!(state, block("d42711aee0bf98f1b8b45de51e415230"));
+        shift_rows(&mut state);
+        assert_eq!(state, block("d4bf5d30e0b452aeb84111f11e2798e5"));
+        mix_columns(&mut state);
+        assert_eq!(state, block("046681e5e0cb199a48f8d37a2806264c"));
+
+        inv_mix_columns(&mut state);
+        inv_shift_rows(&mut state);
+        inv_sub_bytes(&mut state);
+        assert_eq!(state, block("193de3bea0f4e22b9ac68d2ae9f84808"));
+    }
+
+    #[test]
+    fn aes_round_hook() {
+        let aes = Aes::new(&hex_decode("2b7e151628aed2a6abf7158809cf4f3c")).unwrap();
+        let input = block("3243f6a8885a308d313198a2e0370734");
+
+        let mut states = vec![];
+        let output = aes.encrypt_block_with(&input, |_, state| states.push(*state));
+        assert_eq!(output, block("3925841d02dc09fbdc118597196a0b32"));
+        assert_eq!(states.len(), 10);
+        assert_eq!(states[0], block("193de3bea0f4e22b9ac68d2ae9f84808"));
+        assert_eq!(states[1], block("a49c7ff2689f352b6b5bea43026a5049"));
+
+        // A fault going into the last round has no MixColumns to spread it, so it changes exactly
+        // one byte of the output
+        let faulty = aes.encrypt_block_with(&input, |round, state| if round == 10 { state[0] ^= 1 });
+        assert_eq!(faulty.iter().zip(output.iter()).filter(|(a, b)| a != b).count(), 1);
+    }
+
+    #[test]
+    fn aes_backends_agree() {
+        for key_len in &[16, 24, 32] {
+            let key = rand_bytes(*key_len);
+            let data = rand_bytes(4 * BLOCK_SIZE);
+            let ciphertext = Backend::OpenSsl.encrypt_block(&data, &key).unwrap();
+            assert_eq!(Backend::Native.encrypt_block(&data, &key).unwrap(), ciphertext);
+            assert_eq!(Backend::Native.decrypt_block(&ciphertext, &key).unwrap(), data);
+            assert_eq!(Backend::OpenSsl.decrypt_block(&ciphertext, &key).unwrap(), data);
+        }
+
+        for backend in &[Backend::OpenSsl, Backend::Native] {
+            assert!(backend.encrypt_block(&[0; 15], &[0; 16]).is_err());
+            assert!(backend.encrypt_block(&[0; 16], &[0; 17]).is_err());
+        }
+    }
+}
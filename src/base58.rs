@@ -7,7 +7,7 @@ use util::hash256;
 static BASE58_ALPHABET: &'static [u8] =
     b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
 
-fn base58_encode(bytes: &[u8]) -> Vec<u8> {
+pub fn base58_encode(bytes: &[u8]) -> Vec<u8> {
     let mut zero_bytes_num = 0;
     for byte in bytes {
         if *byte == 0 {
@@ -43,6 +43,42 @@ pub fn base58check_encode(bytes: &[u8]) -> Vec<u8> {
     base58_encode(&bytes_with_checksum)
 }
 
+/// Decode base58 encoded bytes. Leading `1`s are decoded as leading zero bytes.
+pub fn base58_decode(encoded: &[u8]) -> Result<Vec<u8>, String> {
+    let zero_bytes_num = encoded.iter().take_while(|c| **c == b'1').count();
+
+    let fifty_eight = BigInt::from(58);
+    let mut num = BigInt::zero();
+    for c in encoded {
+        let digit = match BASE58_ALPHABET.iter().position(|a| a == c) {
+            Some(digit) => digit,
+            None => return Err(format!("Invalid base58 character: {}", *c as char)),
+        };
+        num = num * &fifty_eight + BigInt::from(digit);
+    }
+
+    let mut result = vec![0u8; zero_bytes_num];
+    if !num.is_zero() {
+        let (_, mut bytes) = num.to_bytes_be();
+        result.append(&mut bytes);
+    }
+    Ok(result)
+}
+
+/// Decode base58check encoded bytes, verifying and removing the 4 byte checksum.
+pub fn base58check_decode(encoded: &[u8]) -> Result<Vec<u8>, String> {
+    let mut bytes = base58_decode(encoded)?;
+    if bytes.len() < 4 {
+        return Err(String::from("Not enough bytes. Expected at least 4 for the checksum"));
+    }
+
+    let checksum = bytes.split_off(bytes.len() - 4);
+    if checksum[..] != hash256(&bytes)[0..4] {
+        return Err(String::from("Invalid checksum"));
+    }
+    Ok(bytes)
+}
+
 #[cfg(test)]
 mod tests {
     use base58::*;
@@ -82,6 +118,28 @@ mod tests {
             let bytes = hex_decode(&hex_input);
             let output = base58_encode(&bytes);
             assert_eq!(output, expected_bytes.to_vec());
+            assert_eq!(base58_decode(&output), Ok(bytes));
         }
     }
+
+    #[test]
+    fn base58_decoding() {
+        // Leading zero bytes round trip as leading 1s
+        assert_eq!(base58_decode(b"11"), Ok(vec![0, 0]));
+        assert_eq!(base58_decode(b"1112"), Ok(vec![0, 0, 0, 1]));
+        assert!(base58_decode(b"0OIl").is_err());
+    }
+
+    #[test]
+    fn base58check_round_trip() {
+        let bytes = hex_decode("6f3dcd3fd1e3ab0a35b3e51cb3ba0d3ba27a9cb26b");
+        let encoded = base58check_encode(&bytes);
+
+        assert_eq!(base58check_decode(&encoded), Ok(bytes));
+
+        // Changing a character invalidates the checksum
+        let mut tampered = encoded.clone();
+        tampered[5] = if tampered[5] == b'a' { b'b' } else { b'a' };
+        assert!(base58check_decode(&tampered).is_err());
+    }
 }
@@ -3,7 +3,7 @@ use elliptic_curve::{Sec};
 use secp256k1::{Point};
 use util::{hash160};
 
-fn derive_address(public_key: &Point, compressed: bool, testnet: bool) -> Vec<u8> {
+pub fn derive_address(public_key: &Point, compressed: bool, testnet: bool) -> Vec<u8> {
     let prefix = if testnet { 0x6f } else { 0x00 };
     let sec = if compressed { public_key.as_sec_compressed() } else { public_key.as_sec() };
     let hashed_pubkey = hash160(&sec);
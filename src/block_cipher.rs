@@ -0,0 +1,106 @@
+use aes::{Aes, Backend, Block, BLOCK_SIZE};
+use set2::aes_cbc::{openssl_ecb_decrypt_block, openssl_ecb_encrypt_block};
+
+// Block ciphers as keyed permutations on fixed-size blocks, so modes of operation in `modes` can be
+// written once and run over AES from either backend or any toy cipher.
+
+pub trait BlockCipher {
+    fn block_size(&self) -> usize;
+
+    /// Encrypt exactly one block
+    fn encrypt_block(&self, block: &[u8]) -> Vec<u8>;
+
+    /// Decrypt exactly one block
+    fn decrypt_block(&self, block: &[u8]) -> Vec<u8>;
+}
+
+impl<C: BlockCipher + ?Sized> BlockCipher for Box<C> {
+    fn block_size(&self) -> usize {
+        (**self).block_size()
+    }
+
+    fn encrypt_block(&self, block: &[u8]) -> Vec<u8> {
+        (**self).encrypt_block(block)
+    }
+
+    fn decrypt_block(&self, block: &[u8]) -> Vec<u8> {
+        (**self).decrypt_block(block)
+    }
+}
+
+impl BlockCipher for Aes {
+    fn block_size(&self) -> usize {
+        BLOCK_SIZE
+    }
+
+    fn encrypt_block(&self, block: &[u8]) -> Vec<u8> {
+        Aes::encrypt_block(self, &to_block(block)).to_vec()
+    }
+
+    fn decrypt_block(&self, block: &[u8]) -> Vec<u8> {
+        Aes::decrypt_block(self, &to_block(block)).to_vec()
+    }
+}
+
+/// AES through OpenSSL
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenSslAes {
+    key: Vec<u8>,
+}
+
+impl OpenSslAes {
+    pub fn new(key: &[u8]) -> Result<Self, String> {
+        match key.len() {
+            16 | 24 | 32 => Ok(OpenSslAes { key: key.to_vec() }),
+            len => Err(format!("Invalid AES key length: {}", len)),
+        }
+    }
+}
+
+impl BlockCipher for OpenSslAes {
+    fn block_size(&self) -> usize {
+        BLOCK_SIZE
+    }
+
+    fn encrypt_block(&self, block: &[u8]) -> Vec<u8> {
+        openssl_ecb_encrypt_block(&to_block(block), &self.key).expect("key length checked in new")
+    }
+
+    fn decrypt_block(&self, block: &[u8]) -> Vec<u8> {
+        openssl_ecb_decrypt_block(&to_block(block), &self.key).expect("key length checked in new")
+    }
+}
+
+/// AES keyed with key on the given backend
+pub fn aes(backend: Backend, key: &[u8]) -> Result<Box<dyn BlockCipher>, String> {
+    Ok(match backend {
+        Backend::OpenSsl => Box::new(OpenSslAes::new(key)?),
+        Backend::Native => Box::new(Aes::new(key)?),
+    })
+}
+
+fn to_block(block: &[u8]) -> Block {
+    assert_eq!(block.len(), BLOCK_SIZE, "AES works on 16-byte blocks");
+    let mut array = [0; BLOCK_SIZE];
+    array.copy_from_slice(block);
+    array
+}
+
+#[cfg(test)]
+mod tests {
+    use block_cipher::*;
+    use util::{rand_bytes};
+
+    #[test]
+    fn block_cipher_aes_backends() {
+        let key = rand_bytes(16);
+        let block = rand_bytes(BLOCK_SIZE);
+        let openssl = aes(Backend::OpenSsl, &key).unwrap();
+        let native = aes(Backend::Native, &key).unwrap();
+        assert_eq!(openssl.block_size(), 16);
+        assert_eq!(openssl.encrypt_block(&block), native.encrypt_block(&block));
+        assert_eq!(native.decrypt_block(&openssl.encrypt_block(&block)), block);
+        assert!(aes(Backend::OpenSsl, &key[..15]).is_err());
+        assert!(aes(Backend::Native, &key[..15]).is_err());
+    }
+}
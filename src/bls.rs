@@ -0,0 +1,225 @@
+use std::slice;
+use num_bigint::{BigInt, RandBigInt};
+use num_traits::*;
+use rand::{thread_rng};
+use elliptic_curve::{FiniteCurvy, Point as ECPoint};
+use bn254::{Bn254, ExtensionPoint};
+use util::{bigint_to_bytes32_be, sha256_bigint};
+
+// BLS signatures over BN254 using the proof of possession scheme.
+//
+// Signatures live in G1 and public keys in G2, which keeps signatures small and hashing cheap
+// since G1 has a cofactor of 1. A signature on m is sk * H(m) and verifies when
+//
+//   e(pk, H(m)) = e(g2, sig)
+//
+// Signatures on different messages add up to a single aggregate signature. Aggregating signatures
+// on the same message is only safe when every public key has come with a proof of possession,
+// which is a signature over the public key itself. Without it a rogue key pk' = pk_a - pk_b lets
+// someone forge an aggregate including pk_b.
+//
+// https://datatracker.ietf.org/doc/html/draft-irtf-cfrg-bls-signature-05
+
+/// Domain separation tag used when hashing messages to sign
+pub const SIG_DST: &[u8] = b"BLS_SIG_BN254G1_XMD:SHA-256_TAI_POP_";
+/// Domain separation tag used when hashing public keys for proofs of possession
+pub const POP_DST: &[u8] = b"BLS_POP_BN254G1_XMD:SHA-256_TAI_POP_";
+
+pub struct Bls {
+    curve: Bn254,
+}
+
+impl Bls {
+    pub fn new() -> Self {
+        Bls { curve: Bn254::new() }
+    }
+
+    pub fn curve_ref(&self) -> &Bn254 {
+        &self.curve
+    }
+
+    /// Generate a random private key and its public key
+    pub fn keygen(&self) -> (BigInt, ExtensionPoint) {
+        let private_key = thread_rng().gen_bigint_range(&BigInt::one(), &Bn254::n());
+        let public_key = self.pubkey(&private_key);
+        (private_key, public_key)
+    }
+
+    /// Produce the public key from a provided private key
+    pub fn pubkey(&self, private_key: &BigInt) -> ExtensionPoint {
+        self.curve.g2_mul(&self.curve.g2(), private_key)
+    }
+
+    pub fn sign(&self, message: &[u8], private_key: &BigInt) -> ECPoint {
+        self.curve.g1_mul(&self.hash_to_g1(message, SIG_DST), private_key)
+    }
+
+    pub fn verify(&self, message: &[u8], signature: &ECPoint, public_key: &ExtensionPoint) -> bool {
+        self.aggregate_verify(&[message], signature, slice::from_ref(public_key))
+    }
+
+    /// Sum signatures into one aggregate signature
+    pub fn aggregate(&self, signatures: &[ECPoint]) -> ECPoint {
+        signatures.iter().fold(ECPoint::Infinity, |acc, sig| self.curve.g1_add(&acc, sig))
+    }
+
+    /// Sum public keys into one aggregate public key. Only do this with keys whose proofs of
+    /// possession have been verified.
+    pub fn aggregate_pubkeys(&self, public_keys: &[ExtensionPoint]) -> ExtensionPoint {
+        public_keys.iter().fold(ExtensionPoint::Infinity, |acc, pk| self.curve.g2_add(&acc, pk))
+    }
+
+    /// Verify an aggregate signature where the ith public key signed the ith message by checking
+    ///
+    ///   e(pk_1, H(m_1)) * ... * e(pk_k, H(m_k)) * e(-g2, sig) = 1
+    ///
+    /// The Miller loops are multiplied together so there's only a single final exponentiation.
+    pub fn aggregate_verify(&self, messages: &[&[u8]], signature: &ECPoint, public_keys: &[ExtensionPoint]) -> bool {
+        if messages.is_empty() || messages.len() != public_keys.len() {
+            return false;
+        }
+        if public_keys.iter().any(|pk| pk.is_infinity() || !self.curve.g2_curve_ref().is_valid_point(pk)) {
+            return false;
+        }
+        if signature.is_infinity() || !self.curve.g1_curve_ref().is_valid_point(signature) {
+            return false;
+        }
+
+        let neg_g2 = self.curve.g2().inverse();
+        let f = messages.iter().zip(public_keys.iter()).fold(
+            self.curve.miller_loop(&neg_g2, signature),
+            |f, (message, pk)| &f * &self.curve.miller_loop(pk, &self.hash_to_g1(message, SIG_DST)),
+        );
+
+        self.curve.final_exponentiation(&f).is_one()
+    }
+
+    /// Verify an aggregate signature where every public key signed the same message. Each public
+    /// key must have a verified proof of possession.
+    pub fn fast_aggregate_verify(&self, message: &[u8], signature: &ECPoint, public_keys: &[ExtensionPoint]) -> bool {
+        if public_keys.is_empty() {
+            return false;
+        }
+
+        self.verify(message, signature, &self.aggregate_pubkeys(public_keys))
+    }
+
+    /// Sign the public key to prove knowledge of the private key
+    pub fn prove_possession(&self, private_key: &BigInt) -> ECPoint {
+        let public_key = self.pubkey(private_key);
+        self.curve.g1_mul(&self.hash_to_g1(&g2_to_bytes(&public_key), POP_DST), private_key)
+    }
+
+    /// Check a proof of possession. This also checks that the public key is in the order n
+    /// subgroup, which the twist G2 lives on isn't by itself.
+    pub fn verify_possession(&self, public_key: &ExtensionPoint, proof: &ECPoint) -> bool {
+        if public_key.is_infinity() || !self.curve.g2_curve_ref().is_valid_point(public_key) {
+            return false;
+        }
+        if !public_key.mul(&Bn254::n(), self.curve.g2_curve_ref()).is_infinity() {
+            return false;
+        }
+        if proof.is_infinity() || !self.curve.g1_curve_ref().is_valid_point(proof) {
+            return false;
+        }
+
+        let message = self.hash_to_g1(&g2_to_bytes(public_key), POP_DST);
+        let f = &self.curve.miller_loop(public_key, &message) * &self.curve.miller_loop(&self.curve.g2().inverse(), proof);
+        self.curve.final_exponentiation(&f).is_one()
+    }
+
+    /// Hash onto G1 with try-and-increment. The counter is hashed in with the message rather than
+    /// added to x so every attempt is an independent hash.
+    pub fn hash_to_g1(&self, message: &[u8], dst: &[u8]) -> ECPoint {
+        let g1_curve = self.curve.g1_curve_ref();
+        for counter in 0u8..=255 {
+            let mut input = dst.to_vec();
+            input.push(counter);
+            input.extend_from_slice(message);
+
+            let x = g1_curve.field_elem(sha256_bigint(&input));
+            let rhs = &(&x * &x) * &x + g1_curve.b_ref();
+            if let Some(y) = rhs.sqrt() {
+                return ECPoint::coord(x, y);
+            }
+        }
+
+        // Each attempt succeeds with probability 1/2
+        panic!("failed to hash onto G1 after 256 attempts");
+    }
+}
+
+impl Default for Bls {
+    fn default() -> Self {
+        Bls::new()
+    }
+}
+
+// Serialize a G2 point as x_1 || x_0 || y_1 || y_0, each as 32 big-endian bytes
+fn g2_to_bytes(point: &ExtensionPoint) -> Vec<u8> {
+    match point {
+        ExtensionPoint::Infinity => vec![0; 128],
+        ExtensionPoint::Coordinate { x, y } => {
+            let mut bytes = vec![];
+            for coeff in &[&x.coeffs[1], &x.coeffs[0], &y.coeffs[1], &y.coeffs[0]] {
+                bytes.extend(bigint_to_bytes32_be(coeff, true));
+            }
+            bytes
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bls::*;
+
+    #[test]
+    fn bls_sign_and_verify() {
+        let bls = Bls::new();
+        let (private_key, public_key) = bls.keygen();
+
+        let sig = bls.sign(b"attestation", &private_key);
+        assert!(bls.verify(b"attestation", &sig, &public_key), "it verifies the signature");
+        assert!(!bls.verify(b"attestation!", &sig, &public_key), "it rejects a different message");
+    }
+
+    #[test]
+    fn bls_aggregate_verify() {
+        let bls = Bls::new();
+        let keys: Vec<BigInt> = (1..4).map(|i| BigInt::from(i * 1000 + 7)).collect();
+        let public_keys: Vec<ExtensionPoint> = keys.iter().map(|k| bls.pubkey(k)).collect();
+        let messages: Vec<&[u8]> = vec![b"block 1", b"block 2", b"block 3"];
+
+        let sigs: Vec<ECPoint> = keys.iter().zip(messages.iter()).map(|(k, m)| bls.sign(m, k)).collect();
+        let aggregate = bls.aggregate(&sigs);
+        assert!(bls.aggregate_verify(&messages, &aggregate, &public_keys));
+
+        let swapped: Vec<&[u8]> = vec![b"block 2", b"block 1", b"block 3"];
+        assert!(!bls.aggregate_verify(&swapped, &aggregate, &public_keys));
+        assert!(!bls.aggregate_verify(&messages[..2], &aggregate, &public_keys[..2]));
+    }
+
+    #[test]
+    fn bls_proof_of_possession() {
+        let bls = Bls::new();
+        let curve = bls.curve_ref();
+        let (alice_key, bob_key) = (BigInt::from(31337), BigInt::from(424242));
+        let (alice, bob) = (bls.pubkey(&alice_key), bls.pubkey(&bob_key));
+
+        assert!(bls.verify_possession(&alice, &bls.prove_possession(&alice_key)));
+        assert!(!bls.verify_possession(&bob, &bls.prove_possession(&alice_key)));
+
+        let message = b"same message";
+        let aggregate = bls.aggregate(&[bls.sign(message, &alice_key), bls.sign(message, &bob_key)]);
+        assert!(bls.fast_aggregate_verify(message, &aggregate, &[alice.clone(), bob.clone()]));
+
+        // Mallory publishes m * g2 - bob as a key and alone forges a signature from both of them
+        let mallory_key = BigInt::from(666);
+        let rogue = curve.g2_add(&bls.pubkey(&mallory_key), &bob.inverse());
+        let forgery = bls.sign(message, &mallory_key);
+        assert!(bls.fast_aggregate_verify(message, &forgery, &[bob.clone(), rogue.clone()]));
+
+        // But can't prove possession of the rogue key without knowing bob's private key
+        assert!(!bls.verify_possession(&rogue, &bls.prove_possession(&mallory_key)));
+    }
+}
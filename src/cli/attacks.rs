@@ -0,0 +1,220 @@
+use std::fs::File;
+use std::io::Read;
+use base64::decode as base64decode;
+use pals::set1::{break_repeating_xor, decrypt_single_byte_xor_with_score_bytes, hex_encode};
+use pals::set2::{pkcs_7_unpad};
+use pals::set2::byte_decryption::{ecb_decrypt_using_oracle, ecb_decrypt_w_prefix_using_oracle};
+use pals::util::{rand_bytes, rand_in_range};
+use pals::set2::padding_oracle;
+use pals::set3::aes_ctr_nonce_reuse::{recover_keystream};
+use cli::{Args, Json, decode_hex};
+
+/// Crack single-byte XOR. With `--lines` every line is a candidate ciphertext and the one that
+/// decrypts to the most english looking plaintext is returned.
+pub fn xor_single(args: &Args) -> Result<String, String> {
+    let path = args.positional(0, "file")?;
+    let ciphertexts = if args.flag("--lines") {
+        read_ciphertext_lines(args, path)?
+    } else {
+        vec![read_ciphertext(args, path)?]
+    };
+
+    let (line, (_, key, plaintext)) = ciphertexts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, ct)| decrypt_single_byte_xor_with_score_bytes(ct).map(|res| (i, res)))
+        .max_by_key(|(_, res)| res.0)
+        .ok_or("No ciphertext to decrypt")?;
+
+    Ok(Json::new()
+        .string("key", &hex_encode(&[key as u8]))
+        .number("line", line)
+        .string("plaintext", &String::from_utf8_lossy(&plaintext))
+        .render())
+}
+
+/// Crack repeating-key XOR by guessing the key size and solving each key byte independently.
+pub fn xor_repeating(args: &Args) -> Result<String, String> {
+    let ciphertext = read_ciphertext(args, args.positional(0, "file")?)?;
+    let (key, plaintext) = break_repeating_xor(&ciphertext);
+
+    Ok(Json::new()
+        .string("key", &hex_encode(&key))
+        .string("key_text", &String::from_utf8_lossy(&key))
+        .string("plaintext", &String::from_utf8_lossy(&plaintext))
+        .render())
+}
+
+/// Recover the secret appended by an ECB encryption oracle one byte at a time. The oracle uses a
+/// random key and the secret is read from the file. With `--random-prefix` the oracle also
+/// prepends between 0 and 32 random bytes, which the attack has to find the length of.
+pub fn ecb_byte_at_a_time(args: &Args) -> Result<String, String> {
+    let secret = read_ciphertext(args, args.positional(0, "file")?)?;
+    let key = rand_bytes(16);
+
+    let plaintext = if args.flag("--random-prefix") {
+        let prefix = rand_bytes(rand_in_range(0, 32));
+        ecb_decrypt_w_prefix_using_oracle(&key, &secret, &prefix)?
+    } else {
+        ecb_decrypt_using_oracle(&key, &secret)?
+    };
+
+    Ok(Json::new()
+        .string("plaintext", &String::from_utf8_lossy(&plaintext))
+        .render())
+}
+
+/// Decrypt AES-CBC ciphertext using only a padding oracle. The key and IV are needed to build
+/// the oracle but the attack itself only learns whether padding is valid.
+pub fn padding_oracle(args: &Args) -> Result<String, String> {
+    let ciphertext = read_ciphertext(args, args.positional(0, "file")?)?;
+    let key = decode_hex(args.option("--key").ok_or("Missing option: --key")?)?;
+    let iv = decode_hex(args.option("--iv").ok_or("Missing option: --iv")?)?;
+    if key.len() != 16 || iv.len() != 16 {
+        return Err(String::from("Key and IV must be 16 bytes"));
+    }
+    if ciphertext.is_empty() || ciphertext.len() % 16 != 0 {
+        return Err(String::from("Ciphertext must be a non-empty multiple of 16 bytes"));
+    }
+
+    let padded = padding_oracle::attack(&ciphertext, &key, &iv);
+    let plaintext = pkcs_7_unpad(&padded, 16).map_err(|_| "Recovered plaintext has invalid padding")?;
+
+    Ok(Json::new()
+        .string("plaintext", &String::from_utf8_lossy(&plaintext))
+        .render())
+}
+
+/// Recover the keystream from CTR ciphertexts, one per line, encrypted with a fixed nonce.
+pub fn ctr_nonce_reuse(args: &Args) -> Result<String, String> {
+    let ciphertexts = read_ciphertext_lines(args, args.positional(0, "file")?)?;
+    let keystream = recover_keystream(&ciphertexts)?;
+
+    let plaintexts: Vec<String> = ciphertexts
+        .iter()
+        .map(|ct| {
+            let pt: Vec<u8> = ct.iter().zip(keystream.iter()).map(|(c, k)| c ^ k).collect();
+            String::from_utf8_lossy(&pt).into_owned()
+        })
+        .collect();
+
+    Ok(Json::new()
+        .string("keystream", &hex_encode(&keystream))
+        .strings("plaintexts", &plaintexts)
+        .render())
+}
+
+fn read_file(path: &str) -> Result<String, String> {
+    let mut contents = String::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut contents))
+        .map_err(|e| format!("Unable to read {}: {}", path, e))?;
+
+    Ok(contents)
+}
+
+// Decode ciphertext as base64 unless `--hex` is given
+fn decode_ciphertext(args: &Args, input: &str) -> Result<Vec<u8>, String> {
+    if args.flag("--hex") {
+        decode_hex(input)
+    } else {
+        base64decode(input).map_err(|e| format!("Invalid base64: {}", e))
+    }
+}
+
+// Read the whole file as a single ciphertext, ignoring line breaks
+fn read_ciphertext(args: &Args, path: &str) -> Result<Vec<u8>, String> {
+    let contents: String = read_file(path)?.split_whitespace().collect();
+    decode_ciphertext(args, &contents)
+}
+
+// Read each non-empty line of the file as a separate ciphertext
+fn read_ciphertext_lines(args: &Args, path: &str) -> Result<Vec<Vec<u8>>, String> {
+    read_file(path)?
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| decode_ciphertext(args, line))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::File;
+    use std::io::Write;
+    use base64::{encode as base64encode, decode as base64decode};
+    use pals::set2::padding_oracle;
+    use pals::set3::aes_ctr;
+    use pals::set1::{hex_encode};
+    use cli::run;
+
+    fn run_args(args: &[&str]) -> Result<String, String> {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        run(&args)
+    }
+
+    // Write contents to a file in the temp dir and return its path
+    fn write_temp_file(name: &str, contents: &str) -> String {
+        let path = env::temp_dir().join(name);
+        File::create(&path).and_then(|mut f| f.write_all(contents.as_bytes())).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn cli_xor_single() {
+        let output = run_args(&["xor-single", "src/data/challenge4.txt", "--hex", "--lines"]).unwrap();
+
+        assert!(output.contains("\"plaintext\": \"Now that the party is jumping\\n\""));
+        assert!(output.contains("\"line\": 170,"));
+    }
+
+    #[test]
+    fn cli_xor_repeating() {
+        let output = run_args(&["xor-repeating", "src/data/challenge6.txt"]).unwrap();
+
+        assert!(output.contains("\"key_text\": \"Terminator X: Bring the noise\""));
+    }
+
+    #[test]
+    fn cli_ecb_byte_at_a_time() {
+        let path = write_temp_file("pals_ecb_secret.txt", &base64encode(b"Rollin' in my 5.0"));
+
+        let output = run_args(&["ecb-byte-at-a-time", &path]).unwrap();
+        assert_eq!(output, "{\n  \"plaintext\": \"Rollin' in my 5.0\"\n}");
+
+        let output = run_args(&["ecb-byte-at-a-time", &path, "--random-prefix"]).unwrap();
+        assert_eq!(output, "{\n  \"plaintext\": \"Rollin' in my 5.0\"\n}");
+    }
+
+    #[test]
+    fn cli_padding_oracle() {
+        let key = b"YELLOW SUBMARINE";
+        let iv = [0u8; 16];
+        let ciphertext = padding_oracle::encrypt(b"Cooking MC's like a pound of bacon", key, &iv);
+        let path = write_temp_file("pals_padding_oracle.txt", &base64encode(&ciphertext));
+
+        let output = run_args(&[
+            "padding-oracle", &path, "--key", &hex_encode(key), "--iv", &hex_encode(&iv)
+        ]).unwrap();
+
+        assert!(output.contains("\"plaintext\": \"Cooking MC's like a pound of bacon\""));
+    }
+
+    #[test]
+    fn cli_ctr_nonce_reuse() {
+        let key = b"YELLOW SUBMARINE";
+        let mut contents = String::new();
+        for line in include_str!("../data/challenge20.txt").lines() {
+            let ct = aes_ctr::encrypt(&base64decode(line).unwrap(), key, 0);
+            contents.push_str(&base64encode(&ct));
+            contents.push('\n');
+        }
+        let path = write_temp_file("pals_ctr_nonce_reuse.txt", &contents);
+
+        let output = run_args(&["ctr-nonce-reuse", &path]).unwrap();
+
+        assert!(output.contains("\"keystream\""));
+        assert!(output.contains("this is a warning, ya better void"));
+    }
+}
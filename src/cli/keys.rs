@@ -0,0 +1,235 @@
+use num_bigint::{BigInt, RandBigInt, Sign};
+use num_traits::*;
+use rand::{thread_rng};
+use pals::{Der, Point, Sec, Secp256k1, Signer, derive_address};
+use pals::ecdsa;
+use pals::set1::{hex_encode};
+use pals::util::{bigint_to_bytes32_be, hash256_bigint};
+use cli::{Args, Json};
+
+/// Generate a new random private key along with its public key and address.
+pub fn keygen(args: &Args) -> Result<String, String> {
+    let mut rng = thread_rng();
+    let private_key = rng.gen_bigint_range(&BigInt::one(), &Secp256k1::n());
+
+    let curve = Secp256k1::new();
+    let public_key = curve.pubkey(&private_key);
+    let compressed = args.flag("--compressed");
+
+    Ok(Json::new()
+        .string("private_key", &hex_encode(&bigint_to_bytes32_be(&private_key, true)))
+        .string("public_key", &hex_encode(&encode_sec(&public_key, compressed)))
+        .string("address", &address_string(&public_key, compressed, args.flag("--testnet")))
+        .render())
+}
+
+/// Derive the public key for a private key.
+pub fn pubkey(args: &Args) -> Result<String, String> {
+    let private_key = parse_private_key(args, 0)?;
+    let public_key = Secp256k1::new().pubkey(&private_key);
+    let (x, y) = public_key.as_coord().ok_or("Public key is the point at infinity")?;
+
+    Ok(Json::new()
+        .string("public_key", &hex_encode(&encode_sec(&public_key, args.flag("--compressed"))))
+        .string("x", &hex_encode(&bigint_to_bytes32_be(&x.value, true)))
+        .string("y", &hex_encode(&bigint_to_bytes32_be(&y.value, true)))
+        .render())
+}
+
+/// Derive the address for either a private key or a SEC encoded public key.
+pub fn address(args: &Args) -> Result<String, String> {
+    let curve = Secp256k1::new();
+    let bytes = args.decode(args.positional(0, "key")?)?;
+    let public_key = if is_sec(&bytes) {
+        Point::from_sec(&bytes, &curve)?
+    } else {
+        curve.pubkey(&parse_scalar(&bytes)?)
+    };
+    let compressed = args.flag("--compressed");
+    let testnet = args.flag("--testnet");
+
+    Ok(Json::new()
+        .string("address", &address_string(&public_key, compressed, testnet))
+        .bool("compressed", compressed)
+        .bool("testnet", testnet)
+        .render())
+}
+
+/// Sign the Hash256 of a message. A random nonce is used unless one is provided with `--k`.
+pub fn sign(args: &Args) -> Result<String, String> {
+    let private_key = parse_private_key(args, 0)?;
+    let message = args.positional(1, "message")?;
+    let k = match args.option("--k") {
+        Some(k) => parse_scalar(&args.decode(k)?)?,
+        None => thread_rng().gen_bigint_range(&BigInt::one(), &Secp256k1::n()),
+    };
+
+    let sig = Signer::new().sign_message(message.as_bytes(), &k, &private_key);
+
+    Ok(Json::new()
+        .string("z", &hex_encode(&bigint_to_bytes32_be(&sig.z.value, true)))
+        .string("r", &hex_encode(&bigint_to_bytes32_be(&sig.r.value, true)))
+        .string("s", &hex_encode(&bigint_to_bytes32_be(&sig.s.value, true)))
+        .string("der", &hex_encode(&sig.as_der()))
+        .render())
+}
+
+/// Verify a DER encoded signature over the Hash256 of a message.
+pub fn verify(args: &Args) -> Result<String, String> {
+    let curve = Secp256k1::new();
+    let public_key = Point::from_sec(&args.decode(args.positional(0, "public key")?)?, &curve)?;
+    let message = args.positional(1, "message")?;
+    let der = args.decode(args.positional(2, "signature")?)?;
+
+    let signer = Signer::new();
+    let sig = signer.sig_from_der(&der, &hash256_bigint(message.as_bytes()))?;
+
+    Ok(Json::new()
+        .bool("valid", signer.verify(&sig, &public_key))
+        .render())
+}
+
+/// Decode a SEC encoded public key into its coordinates and both SEC encodings.
+pub fn sec_decode(args: &Args) -> Result<String, String> {
+    let bytes = args.decode(args.positional(0, "public key")?)?;
+    let public_key = Point::from_sec(&bytes, &Secp256k1::new())?;
+    let (x, y) = public_key.as_coord().ok_or("Public key is the point at infinity")?;
+
+    Ok(Json::new()
+        .string("x", &hex_encode(&bigint_to_bytes32_be(&x.value, true)))
+        .string("y", &hex_encode(&bigint_to_bytes32_be(&y.value, true)))
+        .bool("compressed", bytes.len() == 33)
+        .string("sec", &hex_encode(&public_key.as_sec()))
+        .string("sec_compressed", &hex_encode(&public_key.as_sec_compressed()))
+        .render())
+}
+
+/// Decode a DER encoded signature into its r and s values.
+pub fn der_decode(args: &Args) -> Result<String, String> {
+    let bytes = args.decode(args.positional(0, "signature")?)?;
+    let (r, s) = ecdsa::der_decode(&bytes)?;
+    if r.bits() > 256 || s.bits() > 256 {
+        return Err(String::from("Signature values must fit in 32 bytes"));
+    }
+
+    Ok(Json::new()
+        .string("r", &hex_encode(&bigint_to_bytes32_be(&r, true)))
+        .string("s", &hex_encode(&bigint_to_bytes32_be(&s, true)))
+        .render())
+}
+
+fn parse_private_key(args: &Args, idx: usize) -> Result<BigInt, String> {
+    let bytes = args.decode(args.positional(idx, "private key")?)?;
+    parse_scalar(&bytes)
+}
+
+// Parse a big-endian scalar, making sure it's within [1, n) for the secp256k1 subgroup
+fn parse_scalar(bytes: &[u8]) -> Result<BigInt, String> {
+    let n = BigInt::from_bytes_be(Sign::Plus, bytes);
+    if n.is_zero() || n >= Secp256k1::n() {
+        return Err(String::from("Scalar must be between 1 and the curve order"));
+    }
+    Ok(n)
+}
+
+// SEC public keys are 33 bytes (compressed) or 65 bytes (uncompressed) with a known prefix
+fn is_sec(bytes: &[u8]) -> bool {
+    match bytes.len() {
+        33 => bytes[0] == 2 || bytes[0] == 3,
+        65 => bytes[0] == 4,
+        _ => false,
+    }
+}
+
+fn encode_sec(public_key: &Point, compressed: bool) -> Vec<u8> {
+    if compressed { public_key.as_sec_compressed() } else { public_key.as_sec() }
+}
+
+fn address_string(public_key: &Point, compressed: bool, testnet: bool) -> String {
+    // base58 output is always ascii
+    String::from_utf8(derive_address(public_key, compressed, testnet)).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use cli::run;
+
+    fn run_args(args: &[&str]) -> Result<String, String> {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        run(&args)
+    }
+
+    // Private key 5002 from the bitcoin address tests
+    const PRIVATE_KEY: &str = "000000000000000000000000000000000000000000000000000000000000138a";
+
+    #[test]
+    fn cli_keygen() {
+        let output = run_args(&["keygen", "--compressed"]).unwrap();
+
+        assert!(output.contains("\"private_key\""));
+        assert!(output.contains("\"address\": \"1"));
+    }
+
+    #[test]
+    fn cli_address() {
+        let output = run_args(&["address", PRIVATE_KEY, "--testnet"]).unwrap();
+        assert!(output.contains("\"address\": \"mmTPbXQFxboEtNRkwfh6K51jvdtHLxGeMA\""));
+
+        // Same address derived from the public key
+        let pubkey = run_args(&["pubkey", PRIVATE_KEY]).unwrap();
+        let sec = json_value(&pubkey, "public_key");
+        let output = run_args(&["address", &sec, "--testnet"]).unwrap();
+        assert!(output.contains("\"address\": \"mmTPbXQFxboEtNRkwfh6K51jvdtHLxGeMA\""));
+
+        assert!(run_args(&["address", "00"]).is_err());
+    }
+
+    #[test]
+    fn cli_sign_and_verify() {
+        // Example from ecdsa_sign_message_and_verify_examples
+        let output = run_args(&["sign", "3039", "Programming Bitcoin!", "--k", "499602d2"]).unwrap();
+        assert!(output.contains("\"r\": \"2b698a0f0a4041b77e63488ad48c23e8e8838dd1fb7520408b121697b782ef22\""));
+        assert!(output.contains("\"s\": \"1dbc63bfef4416705e602a7b564161167076d8b20990a0f26f316cff2cb0bc1a\""));
+
+        let der = json_value(&output, "der");
+        let pubkey = json_value(&run_args(&["pubkey", "3039", "--compressed"]).unwrap(), "public_key");
+        let valid = run_args(&["verify", &pubkey, "Programming Bitcoin!", &der]).unwrap();
+        assert!(valid.contains("\"valid\": true"));
+        let invalid = run_args(&["verify", &pubkey, "Programming Ethereum!", &der]).unwrap();
+        assert!(invalid.contains("\"valid\": false"));
+
+        let decoded = run_args(&["der-decode", &der]).unwrap();
+        assert!(decoded.contains("\"r\": \"2b698a0f0a4041b77e63488ad48c23e8e8838dd1fb7520408b121697b782ef22\""));
+
+        // The same signature with r + n is rejected rather than reduced back to r
+        assert_eq!(&der[4..8], "0220");
+        let len = u8::from_str_radix(&der[2..4], 16).unwrap() + 1;
+        let r_plus_n = format!("30{:02x}0221{}{}", len, "012b698a0f0a4041b77e63488ad48c23e7a3326ab8aabdc07c4ae4752487b93063", &der[72..]);
+        assert!(run_args(&["der-decode", &r_plus_n]).is_err());
+        assert!(run_args(&["verify", &pubkey, "Programming Bitcoin!", &r_plus_n]).is_err());
+    }
+
+    #[test]
+    fn cli_sec_decode() {
+        let pubkey = run_args(&["pubkey", PRIVATE_KEY, "--compressed"]).unwrap();
+        let sec = json_value(&pubkey, "public_key");
+
+        let output = run_args(&["sec-decode", &sec]).unwrap();
+        assert!(output.contains("\"compressed\": true"));
+        assert_eq!(json_value(&output, "x"), json_value(&pubkey, "x"));
+
+        // Malformed keys are errors rather than panics
+        assert!(run_args(&["sec-decode", "0x"]).is_err());
+        assert!(run_args(&["sec-decode", &format!("04{}", "01".repeat(64))]).is_err());
+        assert!(run_args(&["sec-decode", &format!("02{}01", "0".repeat(78))]).is_err());
+        assert!(run_args(&["address", "0x"]).is_err());
+    }
+
+    // Pull a string value out of the rendered JSON output
+    fn json_value(json: &str, key: &str) -> String {
+        let marker = format!("\"{}\": \"", key);
+        let start = json.find(&marker).expect("key in output") + marker.len();
+        let end = start + json[start..].find('"').unwrap();
+        json[start..end].to_string()
+    }
+}
@@ -0,0 +1,226 @@
+mod attacks;
+mod keys;
+
+use std::collections::HashMap;
+use pals::{base58_decode, base58check_decode};
+use pals::set1::{hex_decode};
+
+const USAGE: &str = "usage: pals <command> [args]
+
+Keys, addresses and signatures (secp256k1):
+    keygen [--testnet] [--compressed]
+    pubkey <private key> [--compressed]
+    address <private key | sec public key> [--testnet] [--compressed]
+    sign <private key> <message> [--k <nonce>]
+    verify <sec public key> <message> <der signature>
+    sec-decode <sec public key>
+    der-decode <der signature>
+
+Cryptopals attacks:
+    xor-single <file> [--lines]
+    xor-repeating <file>
+    ecb-byte-at-a-time <secret file> [--random-prefix]
+    padding-oracle <file> --key <key> --iv <iv>
+    ctr-nonce-reuse <file>
+
+Keys, public keys and signatures are read as hex unless --base58 or --base58check is given.
+Ciphertext files are read as base64 unless --hex is given. Output is JSON.";
+
+// Options which take a value. Everything else starting with `--` is treated as a boolean flag.
+const VALUE_OPTIONS: &[&str] = &["--k", "--key", "--iv"];
+
+/// Arguments to a command split into positional arguments, flags and options with values.
+pub struct Args {
+    positional: Vec<String>,
+    flags: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Args, String> {
+        let mut positional = vec![];
+        let mut flags = vec![];
+        let mut options = HashMap::new();
+
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if VALUE_OPTIONS.contains(&arg.as_str()) {
+                let value = iter.next().ok_or_else(|| format!("Missing value for {}", arg))?;
+                options.insert(arg.clone(), value.clone());
+            } else if arg.starts_with("--") {
+                flags.push(arg.clone());
+            } else {
+                positional.push(arg.clone());
+            }
+        }
+
+        Ok(Args { positional, flags, options })
+    }
+
+    /// Return the positional argument at idx, naming it in the error if it's missing.
+    pub fn positional(&self, idx: usize, name: &str) -> Result<&str, String> {
+        self.positional.get(idx).map(|s| s.as_str()).ok_or_else(|| format!("Missing argument: <{}>", name))
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(|s| s.as_str())
+    }
+
+    /// Decode binary input using the encoding selected by the flags, defaulting to hex.
+    pub fn decode(&self, input: &str) -> Result<Vec<u8>, String> {
+        if self.flag("--base58check") {
+            base58check_decode(input.as_bytes())
+        } else if self.flag("--base58") {
+            base58_decode(input.as_bytes())
+        } else {
+            decode_hex(input)
+        }
+    }
+}
+
+/// Run the command described by args and return its output.
+pub fn run(args: &[String]) -> Result<String, String> {
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => return Err(USAGE.to_string()),
+    };
+    let args = Args::parse(rest)?;
+
+    match command {
+        "keygen" => keys::keygen(&args),
+        "pubkey" => keys::pubkey(&args),
+        "address" => keys::address(&args),
+        "sign" => keys::sign(&args),
+        "verify" => keys::verify(&args),
+        "sec-decode" => keys::sec_decode(&args),
+        "der-decode" => keys::der_decode(&args),
+        "xor-single" => attacks::xor_single(&args),
+        "xor-repeating" => attacks::xor_repeating(&args),
+        "ecb-byte-at-a-time" => attacks::ecb_byte_at_a_time(&args),
+        "padding-oracle" => attacks::padding_oracle(&args),
+        "ctr-nonce-reuse" => attacks::ctr_nonce_reuse(&args),
+        "help" | "--help" | "-h" => Ok(USAGE.to_string()),
+        _ => Err(format!("Unknown command: {}\n\n{}", command, USAGE)),
+    }
+}
+
+// The hex decoding in set1 assumes well formed input so we validate it here first.
+fn decode_hex(input: &str) -> Result<Vec<u8>, String> {
+    let input = input.trim_start_matches("0x").to_lowercase();
+    if input.len() % 2 != 0 {
+        return Err(String::from("Hex input must have an even number of characters"));
+    }
+    if let Some(c) = input.chars().find(|c| !c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid hex character: {}", c));
+    }
+
+    Ok(hex_decode(&input))
+}
+
+/// Minimal JSON object builder for command output. Values are written in insertion order.
+pub struct Json {
+    fields: Vec<(String, String)>,
+}
+
+impl Json {
+    pub fn new() -> Json {
+        Json { fields: vec![] }
+    }
+
+    pub fn string(mut self, key: &str, value: &str) -> Json {
+        self.fields.push((key.to_string(), escape_string(value)));
+        self
+    }
+
+    pub fn strings(mut self, key: &str, values: &[String]) -> Json {
+        let values: Vec<String> = values.iter().map(|v| escape_string(v)).collect();
+        self.fields.push((key.to_string(), format!("[{}]", values.join(", "))));
+        self
+    }
+
+    pub fn number(mut self, key: &str, value: usize) -> Json {
+        self.fields.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn bool(mut self, key: &str, value: bool) -> Json {
+        self.fields.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn render(&self) -> String {
+        let fields: Vec<String> = self.fields
+            .iter()
+            .map(|(key, value)| format!("  {}: {}", escape_string(key), value))
+            .collect();
+
+        format!("{{\n{}\n}}", fields.join(",\n"))
+    }
+}
+
+fn escape_string(value: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use cli::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn cli_args_parse() {
+        let parsed = Args::parse(&args(&["abc", "--testnet", "--k", "05", "def"])).unwrap();
+
+        assert_eq!(parsed.positional(0, "first"), Ok("abc"));
+        assert_eq!(parsed.positional(1, "second"), Ok("def"));
+        assert!(parsed.positional(2, "third").is_err());
+        assert!(parsed.flag("--testnet"));
+        assert!(!parsed.flag("--compressed"));
+        assert_eq!(parsed.option("--k"), Some("05"));
+
+        assert!(Args::parse(&args(&["--k"])).is_err());
+    }
+
+    #[test]
+    fn cli_decode_hex() {
+        assert_eq!(decode_hex("0aFF"), Ok(vec![10, 255]));
+        assert_eq!(decode_hex("0x0a"), Ok(vec![10]));
+        assert!(decode_hex("abc").is_err());
+        assert!(decode_hex("zz").is_err());
+    }
+
+    #[test]
+    fn cli_json_render() {
+        let json = Json::new().string("a", "say \"hi\"\n").bool("b", true).render();
+        assert_eq!(json, "{\n  \"a\": \"say \\\"hi\\\"\\n\",\n  \"b\": true\n}");
+
+        let json = Json::new().strings("c", &["x".to_string(), "y".to_string()]).render();
+        assert_eq!(json, "{\n  \"c\": [\"x\", \"y\"]\n}");
+    }
+
+    #[test]
+    fn cli_unknown_command() {
+        assert!(run(&args(&["nope"])).is_err());
+        assert!(run(&[]).is_err());
+    }
+}
@@ -0,0 +1,207 @@
+use num_bigint::{BigInt};
+use finite_field::{Field, FieldElement};
+use elliptic_curve::{CurveGroup, FiniteCurve, FiniteCurvy, Point as ECPoint};
+use secp256k1::{Point, Secp256k1};
+
+/// Names accepted by `NamedCurve::from_name`, one per curve.
+pub const CURVE_NAMES: &[&str] = &["secp256k1", "secp256r1", "secp384r1", "brainpoolP256r1"];
+
+/// Standard curve in short Weierstrass form along with the generator of its prime order subgroup.
+#[derive(Debug, Clone)]
+pub struct NamedCurve {
+    name: &'static str,
+    curve: FiniteCurve,
+    g: ECPoint,
+    n: BigInt,
+    cofactor: BigInt,
+}
+
+impl NamedCurve {
+    pub fn secp256k1() -> Self {
+        let secp = Secp256k1::new();
+        NamedCurve {
+            name: "secp256k1",
+            curve: secp.curve_ref().clone(),
+            g: secp.g,
+            n: Secp256k1::n(),
+            cofactor: BigInt::from(1),
+        }
+    }
+
+    /// NIST P-256, also known as prime256v1
+    pub fn secp256r1() -> Self {
+        NamedCurve::from_hex(
+            "secp256r1",
+            "ffffffff00000001000000000000000000000000ffffffffffffffffffffffff",
+            "ffffffff00000001000000000000000000000000fffffffffffffffffffffffc",
+            "5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b",
+            ("6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296",
+             "4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5"),
+            "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551",
+            1,
+        )
+    }
+
+    /// NIST P-384
+    pub fn secp384r1() -> Self {
+        NamedCurve::from_hex(
+            "secp384r1",
+            "fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffeffffffff0000000000000000ffffffff",
+            "fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffeffffffff0000000000000000fffffffc",
+            "b3312fa7e23ee7e4988e056be3f82d19181d9c6efe8141120314088f5013875ac656398d8a2ed19d2a85c8edd3ec2aef",
+            ("aa87ca22be8b05378eb1c71ef320ad746e1d3b628ba79b9859f741e082542a385502f25dbf55296c3a545e3872760ab7",
+             "3617de4a96262c6f5d9e98bf9292dc29f8f41dbd289a147ce9da3113b5f0b8c00a60b1ce1d7e819d7a431d7c90ea0e5f"),
+            "ffffffffffffffffffffffffffffffffffffffffffffffffc7634d81f4372ddf581a0db248b0a77aecec196accc52973",
+            1,
+        )
+    }
+
+    /// Brainpool curve from RFC 5639 with a pseudo-randomly generated prime
+    pub fn brainpool_p256r1() -> Self {
+        NamedCurve::from_hex(
+            "brainpoolP256r1",
+            "a9fb57dba1eea9bc3e660a909d838d726e3bf623d52620282013481d1f6e5377",
+            "7d5a0975fc2c3057eef67530417affe7fb8055c126dc5c6ce94a4b44f330b5d9",
+            "26dc5c6ce94a4b44f330b5d9bbd77cbf958416295cf7e1ce6bccdc18ff8c07b6",
+            ("8bd2aeb9cb7e57cb2c4b482ffc81b7afb9de27e1e3bd23c23a4453bd9ace3262",
+             "547ef835c3dac4fd97f8461a14611dc9c27745132ded8e545c1d54c72f046997"),
+            "a9fb57dba1eea9bc3e660a909d838d718c397aa3b561a6f7901e0e82974856a7",
+            1,
+        )
+    }
+
+    /// Look up a curve by name. NIST names and the OpenSSL alias for P-256 are accepted too.
+    pub fn from_name(name: &str) -> Result<NamedCurve, String> {
+        match name {
+            "secp256k1" => Ok(NamedCurve::secp256k1()),
+            "secp256r1" | "prime256v1" | "P-256" => Ok(NamedCurve::secp256r1()),
+            "secp384r1" | "P-384" => Ok(NamedCurve::secp384r1()),
+            "brainpoolP256r1" => Ok(NamedCurve::brainpool_p256r1()),
+            _ => Err(format!("Unknown curve: {}", name)),
+        }
+    }
+
+    fn from_hex(name: &'static str, p: &str, a: &str, b: &str, g: (&str, &str), n: &str, cofactor: u32) -> Self {
+        let parse = |hex: &str| BigInt::parse_bytes(hex.as_bytes(), 16).unwrap();
+
+        let curve = FiniteCurve::new(parse(a), parse(b), parse(p));
+        let g = curve.point(parse(g.0), parse(g.1));
+
+        NamedCurve { name, curve, g, n: parse(n), cofactor: BigInt::from(cofactor) }
+    }
+
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    /// Number of points on the curve divided by the order of the subgroup generated by g
+    pub fn cofactor_ref(&self) -> &BigInt {
+        &self.cofactor
+    }
+
+    /// Return the base generator point for the curve
+    pub fn g(&self) -> Point<NamedCurve> {
+        Point::new(self.g.clone(), self.clone())
+    }
+
+    /// Produce the public key from a provided private key
+    pub fn pubkey(&self, private_key: &BigInt) -> Point<NamedCurve> {
+        self.g() * private_key.clone()
+    }
+}
+
+impl FiniteCurvy for NamedCurve {
+    fn field_ref(&self) -> &Field {
+        self.curve.field_ref()
+    }
+
+    fn a_ref(&self) -> &FieldElement {
+        self.curve.a_ref()
+    }
+
+    fn b_ref(&self) -> &FieldElement {
+        self.curve.b_ref()
+    }
+}
+
+impl CurveGroup for NamedCurve {
+    fn curve_ref(&self) -> &FiniteCurve {
+        &self.curve
+    }
+
+    fn g_ref(&self) -> &ECPoint {
+        &self.g
+    }
+
+    fn n_ref(&self) -> &BigInt {
+        &self.n
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_traits::Num;
+    use curves::*;
+    use ecdsa::{Signer};
+    use elliptic_curve::{Sec};
+
+    #[test]
+    fn curves_generator_order() {
+        for name in CURVE_NAMES {
+            let curve = NamedCurve::from_name(name).unwrap();
+            assert_eq!(curve.name(), *name);
+            assert_eq!(curve.g() * curve.n_ref().clone(), ECPoint::Infinity, "n * g on {}", name);
+            assert_eq!(curve.g() * (curve.n_ref() + 1), curve.g(), "(n + 1) * g on {}", name);
+        }
+
+        assert!(NamedCurve::from_name("P-256").is_ok());
+        assert!(NamedCurve::from_name("secp192r1").is_err());
+    }
+
+    #[test]
+    fn curves_secp256k1_matches() {
+        let curve = NamedCurve::secp256k1();
+        let secp = Secp256k1::new();
+        let k = BigInt::from(0xdeadbeefu32);
+
+        assert_eq!(curve.pubkey(&k).as_sec(), secp.pubkey(&k).as_sec());
+    }
+
+    #[test]
+    fn curves_p256_pubkey() {
+        // Key pair from RFC 6979 A.2.5
+        let curve = NamedCurve::secp256r1();
+        let x = BigInt::from_str_radix("c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721", 16).unwrap();
+
+        let pubkey = curve.pubkey(&x);
+        let (u_x, u_y) = pubkey.as_coord().unwrap();
+        assert_eq!(u_x.value.to_str_radix(16), "60fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6");
+        assert_eq!(u_y.value.to_str_radix(16), "7903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299");
+    }
+
+    #[test]
+    fn curves_sec_encoding() {
+        let curve = NamedCurve::secp384r1();
+        let pubkey = curve.pubkey(&BigInt::from(12345));
+
+        let sec = pubkey.as_sec();
+        assert_eq!(sec.len(), 97);
+        assert_eq!(Point::from_sec(&sec, &curve), Ok(pubkey.clone()));
+    }
+
+    #[test]
+    fn curves_ecdsa_sign_and_verify() {
+        for name in CURVE_NAMES {
+            let curve = NamedCurve::from_name(name).unwrap();
+            let privkey = BigInt::from(12345);
+            let pubkey = curve.pubkey(&privkey);
+
+            let signer = Signer::with_curve(curve.clone());
+            let sig = signer.sign_message(b"Programming Bitcoin!", &BigInt::from(1234567890), &privkey);
+            assert!(signer.verify(&sig, &pubkey), "signature on {}", name);
+
+            let wrong_key = curve.pubkey(&BigInt::from(54321));
+            assert!(!signer.verify(&sig, &wrong_key), "wrong key on {}", name);
+        }
+    }
+}
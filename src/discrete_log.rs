@@ -0,0 +1,217 @@
+use std::collections::{HashMap};
+use num_bigint::{BigInt, RandBigInt};
+use num_integer::{Integer};
+use num_traits::*;
+use rand::{thread_rng};
+use finite_field::{mod_inverse};
+use elliptic_curve::{FiniteCurvy, Point};
+
+// Solvers for the elliptic curve discrete log problem: given g and h = xg find x.
+//
+// Baby-step giant-step and Pollard's rho both take about sqrt(n) group operations for a subgroup
+// of order n, so they only work on small groups. Pohlig-Hellman reduces a group of composite order
+// to its prime power subgroups, which is why a group whose order only has small factors is weak
+// no matter how large it is.
+
+/// Find x in [0, bound) with xg = h using baby-step giant-step. The bound is usually the order of
+/// g, but a smaller one works when x is known to be small. Uses O(sqrt(bound)) memory.
+pub fn baby_step_giant_step(g: &Point, h: &Point, bound: &BigInt, curve: &dyn FiniteCurvy) -> Option<BigInt> {
+    let m = bound.sqrt() + BigInt::one();
+
+    // Baby steps: jg for j in [0, m)
+    let mut table = HashMap::new();
+    let mut baby = Point::Infinity;
+    let mut j = BigInt::zero();
+    while j < m {
+        table.entry(point_key(&baby)).or_insert_with(|| j.clone());
+        baby = baby.add(g, curve);
+        j += 1;
+    }
+
+    // Giant steps: h - i(mg) for i in [0, m)
+    let giant = g.mul(&m, curve).inverse();
+    let mut current = h.clone();
+    let mut i = BigInt::zero();
+    while i < m {
+        if let Some(j) = table.get(&point_key(&current)) {
+            let x = &i * &m + j;
+            if &x < bound {
+                return Some(x);
+            }
+        }
+        current = current.add(&giant, curve);
+        i += 1;
+    }
+
+    None
+}
+
+/// Find x with xg = h using Pollard's rho, where order is the prime order of g. Walks a
+/// pseudo-random sequence of points ag + bh until Floyd's cycle detection finds a collision, then
+/// solves for x. Uses constant memory.
+pub fn pollard_rho(g: &Point, h: &Point, order: &BigInt, curve: &dyn FiniteCurvy) -> Option<BigInt> {
+    if h.is_infinity() {
+        return Some(BigInt::zero());
+    }
+
+    let mut rng = thread_rng();
+    // A collision with matching b coefficients tells us nothing, so start over from elsewhere
+    for _ in 0..10 {
+        let a = rng.gen_bigint_range(&BigInt::zero(), order);
+        let b = rng.gen_bigint_range(&BigInt::zero(), order);
+        let start = g.mul(&a, curve).add(&h.mul(&b, curve), curve);
+
+        let mut tortoise = (start.clone(), a.clone(), b.clone());
+        let mut hare = (start, a, b);
+        loop {
+            tortoise = rho_step(tortoise, g, h, order, curve);
+            hare = rho_step(rho_step(hare, g, h, order, curve), g, h, order, curve);
+            if tortoise.0 == hare.0 {
+                break;
+            }
+        }
+
+        // a_1 g + b_1 h = a_2 g + b_2 h  =>  x = (a_1 - a_2) / (b_2 - b_1)
+        let (_, a_1, b_1) = tortoise;
+        let (_, a_2, b_2) = hare;
+        if let Some(inv) = mod_inverse(&(b_2 - b_1), order) {
+            let x = ((a_1 - a_2) * inv).mod_floor(order);
+            if &g.mul(&x, curve) == h {
+                return Some(x);
+            }
+        }
+    }
+
+    None
+}
+
+/// Find x with xg = h given the factorization of the order of g as (prime, exponent) pairs. Solves
+/// x mod q^e in each subgroup one base q digit at a time and combines them with the CRT, so the
+/// work depends on the largest prime factor rather than the order.
+pub fn pohlig_hellman(g: &Point, h: &Point, factors: &[(BigInt, u32)], curve: &dyn FiniteCurvy) -> Option<BigInt> {
+    let order = factors.iter().fold(BigInt::one(), |acc, (q, e)| acc * q.pow(*e as usize));
+
+    let mut residues = vec![];
+    for (q, e) in factors {
+        // gamma generates the subgroup of order q
+        let gamma = g.mul(&(&order / q), curve);
+        let mut x_q = BigInt::zero();
+        let mut q_k = BigInt::one();
+
+        for k in 0..*e {
+            // Strip off the digits found so far and project into the order q subgroup
+            let cofactor = &order / (&q_k * q);
+            let h_k = h.add(&g.mul(&x_q, curve).inverse(), curve).mul(&cofactor, curve);
+            let d = baby_step_giant_step(&gamma, &h_k, q, curve)?;
+
+            x_q += d * &q_k;
+            if k + 1 < *e {
+                q_k *= q;
+            }
+        }
+
+        residues.push((x_q, q.pow(*e as usize)));
+    }
+
+    crt(&residues).map(|(x, _)| x)
+}
+
+/// Chinese remainder theorem. Given pairwise coprime moduli returns the unique x mod M with
+/// x = r_i mod m_i for each (r_i, m_i), along with M.
+pub fn crt(residues: &[(BigInt, BigInt)]) -> Option<(BigInt, BigInt)> {
+    let modulus = residues.iter().fold(BigInt::one(), |acc, (_, m)| acc * m);
+
+    let mut x = BigInt::zero();
+    for (r, m) in residues {
+        let rest = &modulus / m;
+        x += r * &rest * mod_inverse(&rest, m)?;
+    }
+
+    Some((x.mod_floor(&modulus), modulus))
+}
+
+// Points aren't hashable so key the baby step table on the coordinates
+fn point_key(point: &Point) -> Option<(BigInt, BigInt)> {
+    match point {
+        Point::Infinity => None,
+        Point::Coordinate { x, y } => Some((x.value.clone(), y.value.clone())),
+    }
+}
+
+// Take one step of the walk, choosing the move by x mod 3
+fn rho_step(state: (Point, BigInt, BigInt), g: &Point, h: &Point, order: &BigInt, curve: &dyn FiniteCurvy) -> (Point, BigInt, BigInt) {
+    let (point, a, b) = state;
+    let partition = match &point {
+        Point::Infinity => BigInt::zero(),
+        Point::Coordinate { x, .. } => &x.value % 3,
+    };
+
+    if partition.is_zero() {
+        (point.add(g, curve), (a + BigInt::one()).mod_floor(order), b)
+    } else if partition.is_one() {
+        (point.add(&point, curve), (&a + &a).mod_floor(order), (&b + &b).mod_floor(order))
+    } else {
+        (point.add(h, curve), a, (b + BigInt::one()).mod_floor(order))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use discrete_log::*;
+    use elliptic_curve::{CurveGroup, FiniteCurve};
+    use secp256k1::{Secp256k1};
+
+    #[test]
+    fn discrete_log_baby_step_giant_step() {
+        // y^2 = x^3 + 2x + 5 over F_100003 has prime order 99907
+        let curve = FiniteCurve::new(2, 5, 100003);
+        let g = curve.point(2, 40359);
+        let order = BigInt::from(99907);
+
+        for x in &[0, 1, 2, 316, 54321, 99906] {
+            let h = g.mul(&BigInt::from(*x), &curve);
+            assert_eq!(baby_step_giant_step(&g, &h, &order, &curve), Some(BigInt::from(*x)));
+        }
+
+        // Small private keys fall quickly even on a real curve
+        let secp = Secp256k1::new();
+        let h = secp.g.mul(&BigInt::from(123456), secp.curve_ref());
+        let x = baby_step_giant_step(&secp.g, &h, &BigInt::from(1 << 20), secp.curve_ref());
+        assert_eq!(x, Some(BigInt::from(123456)));
+    }
+
+    #[test]
+    fn discrete_log_pollard_rho() {
+        let curve = FiniteCurve::new(2, 5, 100003);
+        let g = curve.point(2, 40359);
+        let order = BigInt::from(99907);
+
+        for x in &[1, 4242, 99906] {
+            let h = g.mul(&BigInt::from(*x), &curve);
+            assert_eq!(pollard_rho(&g, &h, &order, &curve), Some(BigInt::from(*x)));
+        }
+    }
+
+    #[test]
+    fn discrete_log_pohlig_hellman() {
+        // y^2 = x^3 + x + 7 over F_10007 has a cyclic group of order 9936 = 2^4 * 3^3 * 23
+        let curve = FiniteCurve::new(1, 7, 10007);
+        let g = curve.point(7, 1188);
+        let factors = vec![(BigInt::from(2), 4), (BigInt::from(3), 3), (BigInt::from(23), 1)];
+        assert!(g.mul(&BigInt::from(9936), &curve).is_infinity());
+
+        for x in &[0, 1, 23, 4999, 9935] {
+            let h = g.mul(&BigInt::from(*x), &curve);
+            assert_eq!(pohlig_hellman(&g, &h, &factors, &curve), Some(BigInt::from(*x)));
+        }
+    }
+
+    #[test]
+    fn discrete_log_crt() {
+        let residues = vec![(BigInt::from(2), BigInt::from(3)), (BigInt::from(3), BigInt::from(5)), (BigInt::from(2), BigInt::from(7))];
+        assert_eq!(crt(&residues), Some((BigInt::from(23), BigInt::from(105))));
+
+        let not_coprime = vec![(BigInt::from(1), BigInt::from(4)), (BigInt::from(3), BigInt::from(6))];
+        assert_eq!(crt(&not_coprime), None);
+    }
+}
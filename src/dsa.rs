@@ -0,0 +1,176 @@
+use num_bigint::{BigInt, RandBigInt};
+use num_traits::*;
+use rand::{thread_rng};
+use finite_field::{Field, FieldElement};
+use util::{sha1_bigint};
+
+// DSA over the order q subgroup of Z_p*, as in FIPS 186-2.
+//
+// It's the same scheme as ECDSA with g^k mod p in place of kG, so it fails the same ways: anyone
+// who learns a single nonce k can solve s = k^-1 (z + xr) for the private key x.
+
+/// Domain parameters: q divides p - 1 and g generates the subgroup of order q
+#[derive(Debug, Clone, PartialEq)]
+pub struct Params {
+    pub p: BigInt,
+    pub q: BigInt,
+    pub g: BigInt,
+}
+
+impl Params {
+    pub fn new(p: BigInt, q: BigInt, g: BigInt) -> Self {
+        Params { p, q, g }
+    }
+
+    /// 1024-bit p with a 160-bit q, from cryptopals set 6
+    pub fn cryptopals() -> Self {
+        let hex = |s: &str| BigInt::parse_bytes(s.as_bytes(), 16).unwrap();
+        Params::new(
+            hex("800000000000000089e1855218a0e7dac38136ffafa72eda7859f2171e25e65eac698c1702578b07dc2a1076da241c76c62d374d8389ea5aeffd3226a0530cc565f3bf6b50929139ebeac04f48c3c84afb796d61e5a4f9a8fda812ab59494232c7d2b4deb50aa18ee9e132bfa85ac4374d7f9091abc3d015efc871a584471bb1"),
+            hex("f4f47f05794b256174bba6e9b396a7707e563c5b"),
+            hex("5958c9d3898b224b12672c0b98e06c60df923cb8bc999d119458fef538b8fa4046c8db53039db620c094c9fa077ef389b5322a559946a71903f990f1f7e0e025e2d7f7cf494aff1a0470f5b64c36b625a097f1651fe775323556fe00b3608c887892878480e99041be601a62166ca6894bdd41a7054ec89f756ba9fc95302291"),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sig {
+    pub z: FieldElement, // content hash
+    pub r: FieldElement, // rand
+    pub s: FieldElement, // sig
+}
+
+impl Sig {
+    pub fn new(r: FieldElement, s: FieldElement, z: FieldElement) -> Sig {
+        Sig { r, s, z }
+    }
+}
+
+/// DSA signer. Uses the cryptopals parameters unless created with `with_params`.
+pub struct Signer {
+    params: Params,
+    subgroup_field: Field,
+}
+
+impl Signer {
+    pub fn new() -> Self {
+        Signer::with_params(Params::cryptopals())
+    }
+
+    pub fn with_params(params: Params) -> Self {
+        let subgroup_field = Field::new(params.q.clone());
+        Signer { params, subgroup_field }
+    }
+
+    pub fn params_ref(&self) -> &Params {
+        &self.params
+    }
+
+    /// Random private key x in [1, q) and its public key y = g^x mod p
+    pub fn keypair(&self) -> (BigInt, BigInt) {
+        let privkey = thread_rng().gen_bigint_range(&BigInt::one(), &self.params.q);
+        let pubkey = self.pubkey(&privkey);
+        (privkey, pubkey)
+    }
+
+    pub fn pubkey(&self, privkey: &BigInt) -> BigInt {
+        self.params.g.modpow(privkey, &self.params.p)
+    }
+
+    /// Sign the SHA-1 hash of message
+    pub fn sign_message(&self, message: &[u8], k: &BigInt, privkey: &BigInt) -> Sig {
+        self.sign(&sha1_bigint(message), k, privkey)
+    }
+
+    pub fn sign(&self, z: &BigInt, k: &BigInt, privkey: &BigInt) -> Sig {
+        let r = &self.compute_r(k);
+        let k = &self.elem(k);
+        let z = &self.elem(z);
+        let privkey = &self.elem(privkey);
+
+        if *r == 0 {
+            panic!("r was 0. Choose another k.")
+        }
+        let s = k.inverse() * (z + (r * privkey));
+        if s == 0 {
+            panic!("s was 0. Choose another k.")
+        }
+
+        Sig { z: z.clone(), r: r.clone(), s }
+    }
+
+    pub fn verify(&self, sig: &Sig, pubkey: &BigInt) -> bool {
+        if sig.r == 0 || sig.s == 0 {
+            return false;
+        }
+
+        self.verify_without_range_check(sig, pubkey)
+    }
+
+    /// Verify without checking that 0 < r, s < q, as broken implementations do. Paired with g = 0
+    /// a signature with r = 0 verifies for any message.
+    pub fn verify_without_range_check(&self, sig: &Sig, pubkey: &BigInt) -> bool {
+        if sig.s == 0 {
+            return false;
+        }
+
+        let p = &self.params.p;
+        let s_inv = &sig.s.inverse();
+        let u_1 = s_inv * &sig.z;
+        let u_2 = s_inv * &sig.r;
+        let v = (self.params.g.modpow(&u_1.value, p) * pubkey.modpow(&u_2.value, p)) % p;
+
+        sig.r == self.elem(&v)
+    }
+
+    /// r = (g^k mod p) mod q
+    pub fn compute_r(&self, k: &BigInt) -> FieldElement {
+        self.elem(&self.params.g.modpow(k, &self.params.p))
+    }
+
+    /// Element of the order q field that signatures live in
+    pub fn elem(&self, n: &BigInt) -> FieldElement {
+        self.subgroup_field.elem(n.clone())
+    }
+}
+
+impl Default for Signer {
+    fn default() -> Self {
+        Signer::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dsa::*;
+
+    #[test]
+    fn dsa_sign_and_verify() {
+        let signer = Signer::new();
+        let params = signer.params_ref();
+        assert!(params.g.modpow(&params.q, &params.p).is_one(), "g has order q");
+
+        let (x, y) = signer.keypair();
+        let k = thread_rng().gen_bigint_range(&BigInt::one(), &params.q);
+        let sig = signer.sign_message(b"Programming Bitcoin!", &k, &x);
+        assert!(signer.verify(&sig, &y));
+
+        let other = signer.sign_message(b"Programming Ethereum!", &k, &x);
+        assert!(!signer.verify(&Sig::new(sig.r.clone(), sig.s.clone(), other.z.clone()), &y));
+        assert!(!signer.verify(&sig, &signer.pubkey(&(x + 1))));
+    }
+
+    #[test]
+    fn dsa_small_params() {
+        // q = 11 divides p - 1 = 22 and g = 4 has order 11
+        let signer = Signer::with_params(Params::new(BigInt::from(23), BigInt::from(11), BigInt::from(4)));
+        let (x, k, z) = (BigInt::from(7), BigInt::from(3), BigInt::from(5));
+        let y = signer.pubkey(&x);
+        assert_eq!(y, BigInt::from(8));
+
+        // r = (4^3 mod 23) mod 11 = 18 mod 11 = 7, s = 3^-1 (5 + 7 * 7) mod 11 = 4 * 54 mod 11 = 7
+        let sig = signer.sign(&z, &k, &x);
+        assert_eq!((sig.r.value.clone(), sig.s.value.clone()), (BigInt::from(7), BigInt::from(7)));
+        assert!(signer.verify(&sig, &y));
+    }
+}
@@ -1,29 +1,31 @@
-use finite_field::FieldElement;
-use num_bigint::BigInt;
+use finite_field::{Field, FieldElement};
+use num_bigint::{BigInt, Sign};
+use num_traits::{Zero};
+use elliptic_curve::{CurveGroup};
 use secp256k1::{Point, Secp256k1};
-use util::{bigint_to_bytes32_be, hash256_bigint};
+use util::{hash256_bigint};
 
 #[derive(Debug)]
-struct Sig {
-    z: FieldElement, // content hash
-    r: FieldElement, // rand
-    s: FieldElement, // sig
+pub struct Sig {
+    pub z: FieldElement, // content hash
+    pub r: FieldElement, // rand
+    pub s: FieldElement, // sig
 }
 
 impl Sig {
-    fn new(r: FieldElement, s: FieldElement, z: FieldElement) -> Sig {
+    pub fn new(r: FieldElement, s: FieldElement, z: FieldElement) -> Sig {
         Sig { r, s, z }
     }
 }
 
 // Distinguished Encoding Rules (DER) serialization
-trait Der {
+pub trait Der {
     fn as_der(&self) -> Vec<u8>;
 }
 
 // Helper function to encode a value into a marked format for der encoding
 fn der_encode_value(v: &BigInt) -> Vec<u8> {
-    let mut bytes = bigint_to_bytes32_be(v, false);
+    let (_, mut bytes) = v.to_bytes_be();
 
     // Since DER is built to work with signed numbers but we're only dealing with unsigned, we must
     // explicitly add a 0 byte if it's already set so that it considers it positive.
@@ -41,6 +43,59 @@ fn der_encode_value(v: &BigInt) -> Vec<u8> {
     res
 }
 
+// Helper function to decode a marked value from der encoding. Returns the value along with the
+// remaining bytes after it.
+fn der_decode_value(bytes: &[u8]) -> Result<(BigInt, &[u8]), String> {
+    if bytes.len() < 2 {
+        return Err(String::from("Not enough bytes for a DER value"));
+    }
+    if bytes[0] != 0x2 {
+        return Err(format!("Invalid value marker: {}", bytes[0]));
+    }
+
+    let len = bytes[1] as usize;
+    if bytes.len() < 2 + len {
+        return Err(format!("Not enough bytes. Expected value of length {}", len));
+    }
+
+    // Integers are signed and must use as few bytes as possible, so a leading 0 is only allowed
+    // when it's needed to keep the next byte's top bit from making the value negative
+    let value = &bytes[2..2 + len];
+    if value.is_empty() {
+        return Err(String::from("Empty DER value"));
+    }
+    if value[0] & 0x80 != 0 {
+        return Err(String::from("Negative DER value"));
+    }
+    if value.len() > 1 && value[0] == 0 && value[1] & 0x80 == 0 {
+        return Err(String::from("DER value has a redundant leading zero"));
+    }
+
+    let value = BigInt::from_bytes_be(Sign::Plus, &bytes[2..2 + len]);
+    Ok((value, &bytes[2 + len..]))
+}
+
+/// Decode a DER encoded signature into its (r, s) values.
+pub fn der_decode(bytes: &[u8]) -> Result<(BigInt, BigInt), String> {
+    if bytes.len() < 2 {
+        return Err(String::from("Not enough bytes for a DER signature"));
+    }
+    if bytes[0] != 0x30 {
+        return Err(format!("Invalid signature marker: {}", bytes[0]));
+    }
+    if bytes[1] as usize != bytes.len() - 2 {
+        return Err(format!("Invalid signature length: {}", bytes[1]));
+    }
+
+    let (r, rest) = der_decode_value(&bytes[2..])?;
+    let (s, rest) = der_decode_value(rest)?;
+    if !rest.is_empty() {
+        return Err(String::from("Unexpected bytes after signature"));
+    }
+
+    Ok((r, s))
+}
+
 impl Der for Sig {
     // marker + sig len + r (marker, length, value) + s (marker, length, value)
     fn as_der(&self) -> Vec<u8> {
@@ -55,24 +110,31 @@ impl Der for Sig {
     }
 }
 
-struct Signer {
-    curve: Secp256k1,
+/// ECDSA signer over a curve. Uses Secp256k1 unless created with `with_curve`.
+pub struct Signer<C = Secp256k1> {
+    curve: C,
+    subgroup_field: Field,
 }
 
-impl Signer {
-    fn new() -> Self {
-        Signer {
-            curve: Secp256k1::new(),
-        }
+impl Signer<Secp256k1> {
+    pub fn new() -> Self {
+        Signer::with_curve(Secp256k1::new())
+    }
+}
+
+impl<C: CurveGroup + Clone> Signer<C> {
+    pub fn with_curve(curve: C) -> Self {
+        let subgroup_field = Field::new(curve.n_ref().clone());
+        Signer { curve, subgroup_field }
     }
 
-    fn sign_message(&self, message: &[u8], k: &BigInt, privkey: &BigInt) -> Sig {
+    pub fn sign_message(&self, message: &[u8], k: &BigInt, privkey: &BigInt) -> Sig {
         let z = &hash256_bigint(message);
         self.sign(z, k, privkey)
     }
 
-    fn sign(&self, z: &BigInt, k: &BigInt, privkey: &BigInt) -> Sig {
-        let p = self.curve.g() * k.clone(); // hax
+    pub fn sign(&self, z: &BigInt, k: &BigInt, privkey: &BigInt) -> Sig {
+        let p = self.g() * k.clone(); // hax
         let r = &self.compute_r(&p);
         let k = &self.elem(k);
         let z = &self.elem(z);
@@ -91,17 +153,40 @@ impl Signer {
         }
     }
 
-    fn verify(&self, sig: &Sig, pubkey: &Point) -> bool {
+    pub fn verify(&self, sig: &Sig, pubkey: &Point<C>) -> bool {
+        // r and s are reduced mod n, so either being zero means it was out of range
+        if sig.r == 0 || sig.s == 0 {
+            return false;
+        }
+
         let s_inv = &sig.s.inverse();
         let u_1 = s_inv * &sig.z;
         let u_2 = s_inv * &sig.r;
-        let p = (self.curve.g() * u_1.value) + (pubkey * u_2.value);
+        let p = (self.g() * u_1.value) + (pubkey * u_2.value);
+        if p.as_coord().is_none() {
+            return false;
+        }
         let computed_r = self.compute_r(&p);
 
         sig.r == computed_r
     }
 
-    fn compute_r(&self, p: &Point) -> FieldElement {
+    /// Build a signature from its DER encoding and the hash of the content that was signed. r and
+    /// s must both be in [1, n).
+    pub fn sig_from_der(&self, der: &[u8], z: &BigInt) -> Result<Sig, String> {
+        let (r, s) = der_decode(der)?;
+        let n = self.curve.n_ref();
+        if r.is_zero() || &r >= n || s.is_zero() || &s >= n {
+            return Err(String::from("Signature values must be between 1 and n - 1"));
+        }
+        Ok(Sig::new(self.elem(&r), self.elem(&s), self.elem(z)))
+    }
+
+    fn g(&self) -> Point<C> {
+        Point::new(self.curve.g_ref().clone(), self.curve.clone())
+    }
+
+    fn compute_r(&self, p: &Point<C>) -> FieldElement {
         let (xp, _) = p.as_coord().expect("non-infinite point");
         let r = self.elem(&xp.value);
         if r == 0 {
@@ -112,7 +197,13 @@ impl Signer {
     }
 
     fn elem(&self, n: &BigInt) -> FieldElement {
-        self.curve.subgroup_field_elem(n.clone())
+        self.subgroup_field.elem(n.clone())
+    }
+}
+
+impl Default for Signer {
+    fn default() -> Self {
+        Signer::new()
     }
 }
 
@@ -120,6 +211,10 @@ impl Signer {
 mod tests {
     use ecdsa::*;
     use num_traits::Num;
+    use curves::{NamedCurve};
+    use elliptic_curve::{Sec};
+    use set1::{hex_decode};
+    use util::{sha256_bigint};
 
     #[test]
     fn ecdsa_sign_and_verify() {
@@ -178,4 +273,198 @@ mod tests {
             assert_eq!(sig.as_der(), &sig_bytes[..]);
         }
     }
+
+    #[test]
+    fn ecdsa_der_decoding() {
+        let curve = Secp256k1::new();
+        let privk = BigInt::from(12345);
+        let pubk = curve.pubkey(&privk);
+        let signer = Signer::new();
+        let sig = signer.sign_message(b"Programming Bitcoin!", &BigInt::from(1234567890), &privk);
+
+        let der = sig.as_der();
+        assert_eq!(der_decode(&der), Ok((sig.r.value.clone(), sig.s.value.clone())));
+
+        let decoded = signer.sig_from_der(&der, &sig.z.value).unwrap();
+        assert!(signer.verify(&decoded, &pubk));
+
+        assert!(der_decode(&der[..der.len() - 1]).is_err()); // truncated
+        assert!(der_decode(&[0x31, 0]).is_err()); // bad marker
+        assert!(der_decode(&[0x30, 5, 2, 0, 2, 1, 1]).is_err()); // empty r
+        assert!(der_decode(&[0x30, 6, 2, 1, 0x80, 2, 1, 1]).is_err()); // negative r
+        assert!(der_decode(&[0x30, 7, 2, 2, 0, 1, 2, 1, 1]).is_err()); // redundant leading zero
+        assert_eq!(der_decode(&[0x30, 7, 2, 2, 0, 0x80, 2, 1, 1]), Ok((BigInt::from(0x80), BigInt::from(1))));
+
+        // r and s are checked against n before they're reduced by it
+        let encode = |r: &BigInt, s: &BigInt| {
+            let mut values = der_encode_value(r);
+            values.extend(der_encode_value(s));
+            [vec![0x30, values.len() as u8], values].concat()
+        };
+        let n = Secp256k1::n();
+        for (r, s) in vec![(&sig.r.value + &n, sig.s.value.clone()), (sig.r.value.clone(), &sig.s.value + &n), (n.clone(), sig.s.value.clone()), (sig.r.value.clone(), n.clone())] {
+            assert!(signer.sig_from_der(&encode(&r, &s), &sig.z.value).is_err());
+        }
+
+        // Out of range values that were reduced to zero don't verify
+        let zero = Sig::new(signer.elem(&n), sig.s.clone(), sig.z.clone());
+        assert!(!signer.verify(&zero, &pubk));
+    }
+
+    #[test]
+    fn ecdsa_p256_rfc6979_vectors() {
+        // RFC 6979 A.2.5, ECDSA over P-256 with SHA-256. The nonces are the deterministic ones from
+        // the RFC which we pass in directly.
+        let curve = NamedCurve::secp256r1();
+        let privk = BigInt::from_str_radix("c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721", 16).unwrap();
+        let pubk = curve.pubkey(&privk);
+        let signer = Signer::with_curve(curve);
+
+        let vectors = vec![
+            (
+                "sample",
+                "a6e3c57dd01abe90086538398355dd4c3b17aa873382b0f24d6129493d8aad60",
+                "efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716",
+                "f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8",
+            ),
+            (
+                "test",
+                "d16b6ae827f17175e040871a1c7ec3500192c4c92677336ec2537acaee0008e0",
+                "f1abb023518351cd71d881567b1ea663ed3efcf6c5132b354f28d3b0b7d38367",
+                "19f4113742a2b14bd25926b49c649155f267e60d3814b4c0cc84250e46f0083",
+            ),
+        ];
+
+        for (message, k, r, s) in vectors {
+            let z = sha256_bigint(message.as_bytes());
+            let k = BigInt::from_str_radix(k, 16).unwrap();
+
+            let sig = signer.sign(&z, &k, &privk);
+            assert_eq!(sig.r.value.to_str_radix(16), r);
+            assert_eq!(sig.s.value.to_str_radix(16), s);
+            assert!(signer.verify(&sig, &pubk));
+        }
+    }
+
+    #[test]
+    fn ecdsa_p256_verify_openssl_signature() {
+        // Generated with `openssl dgst -sha256 -sign` using a fresh prime256v1 key
+        let curve = NamedCurve::secp256r1();
+        let sec = hex_decode("04c68a2eb7c3b810b62d8efec55b5bde6cd8c67457db1d699313c74e76194ea5f5ea233731672042a80561a9eee3124477ddb3ccc7cf6b3122ee4d0ba2e0d6bfd9");
+        let der = hex_decode("304402200dac145c9e102e4ce8686937f61f65b98a4f78e5377dc117c9ef1f9b4137ee1c022074442bd04b8a8e751609794ee37dc01514f5a952ecddfc62ccc0c2703250993b");
+        let message = b"Programming Bitcoin!";
+
+        let pubk = Point::from_sec(&sec, &curve).unwrap();
+        let compressed = pubk.as_sec_compressed();
+        assert_eq!(compressed[0], 3);
+        assert_eq!(Point::from_sec(&compressed, &curve), Ok(pubk.clone()));
+
+        let signer = Signer::with_curve(curve);
+        let sig = signer.sig_from_der(&der, &sha256_bigint(message)).unwrap();
+        assert!(signer.verify(&sig, &pubk));
+
+        let sig = signer.sig_from_der(&der, &sha256_bigint(b"Programming Ethereum!")).unwrap();
+        assert!(!signer.verify(&sig, &pubk));
+    }
+
+    #[test]
+    fn ecdsa_p256_cavp_siggen_vectors() {
+        // NIST CAVP FIPS 186-3 SigGen.rsp, [P-256,SHA-256]. Signing with the listed nonce must give
+        // exactly R and S.
+        let hex = |s: &str| BigInt::from_str_radix(s, 16).unwrap();
+        let curve = NamedCurve::secp256r1();
+        let signer = Signer::with_curve(curve.clone());
+
+        let vectors = vec![
+            (
+                "5905238877c77421f73e43ee3da6f2d9e2ccad5fc942dcec0cbd25482935faaf416983fe165b1a045ee2bcd2e6dca3bdf46c4310a7461f9a37960ca672d3feb5473e253605fb1ddfd28065b53cb5858a8ad28175bf9bd386a5e471ea7a65c17cc934a9d791e91491eb3754d03799790fe2d308d16146d5c9b0d0debd97d79ce8",
+                "519b423d715f8b581f4fa8ee59f4771a5b44c8130b4e3eacca54a56dda72b464",
+                "1ccbe91c075fc7f4f033bfa248db8fccd3565de94bbfb12f3c59ff46c271bf83",
+                "ce4014c68811f9a21a1fdb2c0e6113e06db7ca93b7404e78dc7ccd5ca89a4ca9",
+                "94a1bbb14b906a61a280f245f9e93c7f3b4a6247824f5d33b9670787642a68de",
+                "f3ac8061b514795b8843e3d6629527ed2afd6b1f6a555a7acabb5e6f79c8c2ac",
+                "8bf77819ca05a6b2786c76262bf7371cef97b218e96f175a3ccdda2acc058903",
+            ),
+            (
+                "c35e2f092553c55772926bdbe87c9796827d17024dbb9233a545366e2e5987dd344deb72df987144b8c6c43bc41b654b94cc856e16b96d7a821c8ec039b503e3d86728c494a967d83011a0e090b5d54cd47f4e366c0912bc808fbb2ea96efac88fb3ebec9342738e225f7c7c2b011ce375b56621a20642b4d36e060db4524af1",
+                "0f56db78ca460b055c500064824bed999a25aaf48ebb519ac201537b85479813",
+                "e266ddfdc12668db30d4ca3e8f7749432c416044f2d2b8c10bf3d4012aeffa8a",
+                "bfa86404a2e9ffe67d47c587ef7a97a7f456b863b4d02cfc6928973ab5b1cb39",
+                "6d3e71882c3b83b156bb14e0ab184aa9fb728068d3ae9fac421187ae0b2f34c6",
+                "976d3a4e9d23326dc0baa9fa560b7c4e53f42864f508483a6473b6a11079b2db",
+                "1b766e9ceb71ba6c01dcd46e0af462cd4cfa652ae5017d4555b8eeefe36e1932",
+            ),
+            (
+                "3c054e333a94259c36af09ab5b4ff9beb3492f8d5b4282d16801daccb29f70fe61a0b37ffef5c04cd1b70e85b1f549a1c4dc672985e50f43ea037efa9964f096b5f62f7ffdf8d6bfb2cc859558f5a393cb949dbd48f269343b5263dcdb9c556eca074f2e98e6d94c2c29a677afaf806edf79b15a3fcd46e7067b7669f83188ee",
+                "e283871239837e13b95f789e6e1af63bf61c918c992e62bca040d64cad1fc2ef",
+                "74ccd8a62fba0e667c50929a53f78c21b8ff0c3c737b0b40b1750b2302b0bde8",
+                "29074e21f3a0ef88b9efdf10d06aa4c295cc1671f758ca0e4cd108803d0f2614",
+                "ad5e887eb2b380b8d8280ad6e5ff8a60f4d26243e0124c2f31a297b5d0835de2",
+                "35fb60f5ca0f3ca08542fb3cc641c8263a2cab7a90ee6a5e1583fac2bb6f6bd1",
+                "ee59d81bc9db1055cc0ed97b159d8784af04e98511d0a9a407b99bb292572e96",
+            ),
+        ];
+
+        for (msg, d, qx, qy, k, r, s) in vectors {
+            let d = hex(d);
+            let pubk = curve.pubkey(&d);
+            let (x, y) = pubk.as_coord().unwrap();
+            assert_eq!((&x.value, &y.value), (&hex(qx), &hex(qy)));
+
+            let sig = signer.sign(&sha256_bigint(&hex_decode(msg)), &hex(k), &d);
+            assert_eq!(sig.r.value, hex(r));
+            assert_eq!(sig.s.value, hex(s));
+            assert!(signer.verify(&sig, &pubk));
+        }
+    }
+
+    #[test]
+    fn ecdsa_p256_cavp_sigver_vectors() {
+        // NIST CAVP FIPS 186-3 SigVer.rsp, [P-256,SHA-256]. Each failing case has one of the
+        // message, public key, R or S changed.
+        let hex = |s: &str| BigInt::from_str_radix(s, 16).unwrap();
+        let curve = NamedCurve::secp256r1();
+        let signer = Signer::with_curve(curve.clone());
+
+        let vectors = vec![
+            (
+                "e4796db5f785f207aa30d311693b3702821dff1168fd2e04c0836825aefd850d9aa60326d88cde1a23c7745351392ca2288d632c264f197d05cd424a30336c19fd09bb229654f0222fcb881a4b35c290a093ac159ce13409111ff0358411133c24f5b8e2090d6db6558afc36f06ca1f6ef779785adba68db27a409859fc4c4a0",
+                "87f8f2b218f49845f6f10eec3877136269f5c1a54736dbdf69f89940cad41555",
+                "e15f369036f49842fac7a86c8a2b0557609776814448b8f5e84aa9f4395205e9",
+                "d19ff48b324915576416097d2544f7cbdf8768b1454ad20e0baac50e211f23b0",
+                "a3e81e59311cdfff2d4784949f7a2cb50ba6c3a91fa54710568e61aca3e847c6",
+                false, // Result = F (3 - S changed)
+            ),
+            (
+                "069a6e6b93dfee6df6ef6997cd80dd2182c36653cef10c655d524585655462d683877f95ecc6d6c81623d8fac4e900ed0019964094e7de91f1481989ae1873004565789cbf5dc56c62aedc63f62f3b894c9c6f7788c8ecaadc9bd0e81ad91b2b3569ea12260e93924fdddd3972af5273198f5efda0746219475017557616170e",
+                "5cf02a00d205bdfee2016f7421807fc38ae69e6b7ccd064ee689fc1a94a9f7d2",
+                "ec530ce3cc5c9d1af463f264d685afe2b4db4b5828d7e61b748930f3ce622a85",
+                "dc23d130c6117fb5751201455e99f36f59aba1a6a21cf2d0e7481a97451d6693",
+                "d6ce7708c18dbf35d4f8aa7240922dc6823f2e7058cbc1484fcad1599db5018c",
+                false, // Result = F (2 - R changed)
+            ),
+            (
+                "df04a346cf4d0e331a6db78cca2d456d31b0a000aa51441defdb97bbeb20b94d8d746429a393ba88840d661615e07def615a342abedfa4ce912e562af714959896858af817317a840dcff85a057bb91a3c2bf90105500362754a6dd321cdd86128cfc5f04667b57aa78c112411e42da304f1012d48cd6a7052d7de44ebcc01de",
+                "2ddfd145767883ffbb0ac003ab4a44346d08fa2570b3120dcce94562422244cb",
+                "5f70c7d11ac2b7a435ccfbbae02c3df1ea6b532cc0e9db74f93fffca7c6f9a64",
+                "9913111cff6f20c5bf453a99cd2c2019a4e749a49724a08774d14e4c113edda8",
+                "9467cd4cd21ecb56b0cab0a9a453b43386845459127a952421f5c6382866c5cc",
+                false, // Result = F (4 - Q changed)
+            ),
+            (
+                "e1130af6a38ccb412a9c8d13e15dbfc9e69a16385af3c3f1e5da954fd5e7c45fd75e2b8c36699228e92840c0562fbf3772f07e17f1add56588dd45f7450e1217ad239922dd9c32695dc71ff2424ca0dec1321aa47064a044b7fe3c2b97d03ce470a592304c5ef21eed9f93da56bb232d1eeb0035f9bf0dfafdcc4606272b20a3",
+                "e424dc61d4bb3cb7ef4344a7f8957a0c5134e16f7a67c074f82e6e12f49abf3c",
+                "970eed7aa2bc48651545949de1dddaf0127e5965ac85d1243d6f60e7dfaee927",
+                "bf96b99aa49c705c910be33142017c642ff540c76349b9dab72f981fd9347f4f",
+                "17c55095819089c2e03b9cd415abdf12444e323075d98f31920b9e0f57ec871c",
+                true, // Result = P
+            ),
+        ];
+
+        for (msg, qx, qy, r, s, valid) in vectors {
+            let pubk = Point::from_sec(&hex_decode(&format!("04{}{}", qx, qy)), &curve).unwrap();
+            let sig = Sig::new(signer.elem(&hex(r)), signer.elem(&hex(s)), signer.elem(&sha256_bigint(&hex_decode(msg))));
+            assert_eq!(signer.verify(&sig, &pubk), valid, "R = {}", r);
+        }
+    }
 }
@@ -0,0 +1,123 @@
+use num_bigint::{BigInt, RandBigInt};
+use num_traits::*;
+use rand::{thread_rng};
+use elliptic_curve::{Sec};
+use secp256k1::{Point, Secp256k1};
+use set3::aes_ctr;
+use util::{constant_time_eq, hmac_sha256};
+
+// Elliptic Curve Integrated Encryption Scheme over secp256k1.
+//
+// The sender generates an ephemeral key pair and does ECDH with the recipient's public key. HKDF
+// turns the shared secret into an AES-128 key for CTR mode and a key for an HMAC-SHA-256 tag over
+// the ciphertext. The output is:
+//
+//   ephemeral public key (33 byte compressed SEC) || ciphertext || tag (32 bytes)
+//
+// Each message gets a fresh key so a fixed CTR nonce of 0 is safe.
+
+const SEC_LEN: usize = 33;
+const TAG_LEN: usize = 32;
+const ENC_KEY_LEN: usize = 16;
+const MAC_KEY_LEN: usize = 32;
+const HKDF_INFO: &[u8] = b"pals ecies secp256k1 aes-128-ctr hmac-sha256";
+
+/// Encrypt plaintext to the holder of the private key for public_key.
+pub fn encrypt(public_key: &Point, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let ephemeral_key = thread_rng().gen_bigint_range(&BigInt::one(), &Secp256k1::n());
+    encrypt_with_ephemeral_key(public_key, plaintext, &ephemeral_key)
+}
+
+/// Encrypt using the provided ephemeral private key. Reusing an ephemeral key reuses the CTR
+/// keystream so this should only be used to get deterministic output in tests.
+pub fn encrypt_with_ephemeral_key(public_key: &Point, plaintext: &[u8], ephemeral_key: &BigInt) -> Result<Vec<u8>, String> {
+    let ephemeral_public = Secp256k1::new().pubkey(ephemeral_key).as_sec_compressed();
+    let (enc_key, mac_key) = derive_keys(public_key, ephemeral_key, &ephemeral_public)?;
+
+    let ciphertext = aes_ctr::encrypt(plaintext, &enc_key, 0);
+    let mut tag = hmac_sha256(&mac_key, &ciphertext);
+
+    let mut output = ephemeral_public;
+    output.extend_from_slice(&ciphertext);
+    output.append(&mut tag);
+    Ok(output)
+}
+
+/// Decrypt a message encrypted to the public key for private_key. Fails if the message was
+/// tampered with.
+pub fn decrypt(private_key: &BigInt, message: &[u8]) -> Result<Vec<u8>, String> {
+    if message.len() < SEC_LEN + TAG_LEN {
+        return Err(String::from("Message is too short"));
+    }
+
+    let (ephemeral_public, rest) = message.split_at(SEC_LEN);
+    let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LEN);
+    let ephemeral_point = Point::from_sec(ephemeral_public, &Secp256k1::new())?;
+    let (enc_key, mac_key) = derive_keys(&ephemeral_point, private_key, ephemeral_public)?;
+
+    if !constant_time_eq(&hmac_sha256(&mac_key, ciphertext), tag) {
+        return Err(String::from("Invalid tag"));
+    }
+
+    Ok(aes_ctr::decrypt(ciphertext, &enc_key, 0))
+}
+
+// Derive the encryption and MAC keys from ECDH between point and private_key. The ephemeral public
+// key is used as the HKDF salt to bind the keys to it.
+fn derive_keys(point: &Point, private_key: &BigInt, ephemeral_public: &[u8]) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let mut enc_key = point.ecdh(private_key, ephemeral_public, HKDF_INFO, ENC_KEY_LEN + MAC_KEY_LEN)?;
+    let mac_key = enc_key.split_off(ENC_KEY_LEN);
+    Ok((enc_key, mac_key))
+}
+
+#[cfg(test)]
+mod tests {
+    use ecies::*;
+
+    #[test]
+    fn ecies_encrypt_and_decrypt() {
+        let private_key = BigInt::from(12345);
+        let public_key = Secp256k1::new().pubkey(&private_key);
+        let plaintext = b"Cooking MC's like a pound of bacon";
+
+        let message = encrypt(&public_key, plaintext).unwrap();
+        assert_eq!(message.len(), SEC_LEN + plaintext.len() + TAG_LEN);
+        assert_eq!(decrypt(&private_key, &message), Ok(plaintext.to_vec()));
+
+        // Fresh ephemeral key each time
+        assert_ne!(encrypt(&public_key, plaintext).unwrap(), message);
+
+        // Only the recipient can decrypt
+        assert!(decrypt(&BigInt::from(54321), &message).is_err());
+    }
+
+    #[test]
+    fn ecies_deterministic() {
+        let private_key = BigInt::from(12345);
+        let public_key = Secp256k1::new().pubkey(&private_key);
+        let ephemeral_key = BigInt::from(67890);
+
+        let first = encrypt_with_ephemeral_key(&public_key, b"hello", &ephemeral_key).unwrap();
+        let second = encrypt_with_ephemeral_key(&public_key, b"hello", &ephemeral_key).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(&first[..SEC_LEN], &Secp256k1::new().pubkey(&ephemeral_key).as_sec_compressed()[..]);
+    }
+
+    #[test]
+    fn ecies_tampering() {
+        let private_key = BigInt::from(12345);
+        let public_key = Secp256k1::new().pubkey(&private_key);
+        let message = encrypt(&public_key, b"attack at dawn").unwrap();
+
+        let mut tampered = message.clone();
+        tampered[SEC_LEN] ^= 1;
+        assert_eq!(decrypt(&private_key, &tampered), Err(String::from("Invalid tag")));
+
+        let mut tampered = message.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert_eq!(decrypt(&private_key, &tampered), Err(String::from("Invalid tag")));
+
+        assert!(decrypt(&private_key, &message[..SEC_LEN + TAG_LEN - 1]).is_err());
+    }
+}
@@ -0,0 +1,198 @@
+use num_bigint::{BigInt, Sign};
+use num_integer::{Integer};
+use num_traits::*;
+use edwards::{EdwardsCurve, EdwardsPoint};
+use util::{sha512};
+
+// Ed25519 signatures as described in RFC 8032 section 5.1. This is the pure variant without a
+// context or prehashing.
+//
+// Keys are 32 byte seeds. Hashing the seed gives the secret scalar s (after clamping) and a prefix
+// used to derive nonces deterministically, so signing needs no randomness. A signature on M is
+// R || S where
+//
+//   r = H(prefix || M),  R = rB,  k = H(R || A || M),  S = r + k s mod L
+//
+// https://www.rfc-editor.org/rfc/rfc8032.html
+
+pub const SEED_LEN: usize = 32;
+pub const PUBLIC_KEY_LEN: usize = 32;
+pub const SIGNATURE_LEN: usize = 64;
+
+pub struct Ed25519 {
+    curve: EdwardsCurve,
+    b: EdwardsPoint,
+}
+
+impl Ed25519 {
+    pub fn new() -> Self {
+        let curve = EdwardsCurve::edwards25519();
+        let y = curve.field_elem(4) / curve.field_elem(5);
+        let x = curve.solve_x(&y, false).expect("base point is on the curve");
+        let b = EdwardsPoint { x, y };
+
+        Ed25519 { curve, b }
+    }
+
+    /// Order of the base point, 2^252 + 27742317777372353535851937790883648493
+    pub fn l() -> BigInt {
+        (BigInt::one() << 252) + BigInt::parse_bytes(b"27742317777372353535851937790883648493", 10).unwrap()
+    }
+
+    pub fn curve_ref(&self) -> &EdwardsCurve {
+        &self.curve
+    }
+
+    /// Return the base point B
+    pub fn b(&self) -> EdwardsPoint {
+        self.b.clone()
+    }
+
+    /// Derive the encoded public key A = sB from a 32 byte seed
+    pub fn pubkey(&self, seed: &[u8]) -> Result<Vec<u8>, String> {
+        let (s, _) = expand_seed(seed)?;
+        Ok(self.curve.encode_point(&self.b.mul(&s, &self.curve)))
+    }
+
+    pub fn sign(&self, message: &[u8], seed: &[u8]) -> Result<Vec<u8>, String> {
+        let (s, prefix) = expand_seed(seed)?;
+        let public_key = self.curve.encode_point(&self.b.mul(&s, &self.curve));
+
+        let r = hash_scalar(&[&prefix, message]);
+        let r_encoded = self.curve.encode_point(&self.b.mul(&r, &self.curve));
+
+        let k = hash_scalar(&[&r_encoded, &public_key, message]);
+        let s = (r + k * s).mod_floor(&Ed25519::l());
+
+        let mut signature = r_encoded;
+        signature.extend(scalar_to_bytes(&s));
+        Ok(signature)
+    }
+
+    /// Check that SB = R + kA. This is the cofactorless equation which is stricter than the
+    /// cofactored [8]SB = [8]R + [8]kA that RFC 8032 also allows.
+    pub fn verify(&self, message: &[u8], signature: &[u8], public_key: &[u8]) -> bool {
+        if signature.len() != SIGNATURE_LEN || public_key.len() != PUBLIC_KEY_LEN {
+            return false;
+        }
+
+        let a = match self.curve.decode_point(public_key) {
+            Ok(point) => point,
+            Err(_) => return false,
+        };
+        let (r_encoded, s_encoded) = signature.split_at(32);
+        let r = match self.curve.decode_point(r_encoded) {
+            Ok(point) => point,
+            Err(_) => return false,
+        };
+
+        // S must be fully reduced or signatures would be malleable
+        let s = BigInt::from_bytes_le(Sign::Plus, s_encoded);
+        if s >= Ed25519::l() {
+            return false;
+        }
+
+        let k = hash_scalar(&[r_encoded, public_key, message]);
+        let lhs = self.b.mul(&s, &self.curve);
+        let rhs = r.add(&a.mul(&k, &self.curve), &self.curve);
+        lhs == rhs
+    }
+}
+
+impl Default for Ed25519 {
+    fn default() -> Self {
+        Ed25519::new()
+    }
+}
+
+// Hash the seed and split it into the clamped secret scalar and the nonce prefix. Clamping clears
+// the low 3 bits so s is a multiple of the cofactor 8, and sets bit 254 for a fixed bit length.
+fn expand_seed(seed: &[u8]) -> Result<(BigInt, Vec<u8>), String> {
+    if seed.len() != SEED_LEN {
+        return Err(format!("Seed must be {} bytes", SEED_LEN));
+    }
+
+    let h = sha512(seed);
+    let mut scalar = h[..32].to_vec();
+    scalar[0] &= 0xf8;
+    scalar[31] &= 0x7f;
+    scalar[31] |= 0x40;
+
+    Ok((BigInt::from_bytes_le(Sign::Plus, &scalar), h[32..].to_vec()))
+}
+
+// SHA-512 of the concatenated parts as a little-endian integer reduced mod L
+fn hash_scalar(parts: &[&[u8]]) -> BigInt {
+    let h = sha512(&parts.concat());
+    BigInt::from_bytes_le(Sign::Plus, &h).mod_floor(&Ed25519::l())
+}
+
+fn scalar_to_bytes(n: &BigInt) -> Vec<u8> {
+    let mut bytes = n.to_bytes_le().1;
+    bytes.resize(32, 0);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519::*;
+    use set1::{hex_decode, hex_encode};
+
+    #[test]
+    fn ed25519_rfc8032_vectors() {
+        // RFC 8032 7.1 tests 1 to 3
+        let vectors = vec![
+            (
+                "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+                "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+                "",
+                "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+            ),
+            (
+                "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+                "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+                "72",
+                "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+            ),
+            (
+                "c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7",
+                "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+                "af82",
+                "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a",
+            ),
+        ];
+
+        let ed25519 = Ed25519::new();
+        for (seed, public_key, message, signature) in vectors {
+            let (seed, message) = (hex_decode(seed), hex_decode(message));
+
+            assert_eq!(hex_encode(&ed25519.pubkey(&seed).unwrap()), public_key);
+            let sig = ed25519.sign(&message, &seed).unwrap();
+            assert_eq!(hex_encode(&sig), signature);
+            assert!(ed25519.verify(&message, &sig, &hex_decode(public_key)));
+        }
+    }
+
+    #[test]
+    fn ed25519_rejects_bad_signatures() {
+        let ed25519 = Ed25519::new();
+        let seed = [7u8; 32];
+        let public_key = ed25519.pubkey(&seed).unwrap();
+        let sig = ed25519.sign(b"deploy v1.2.3", &seed).unwrap();
+
+        assert!(ed25519.verify(b"deploy v1.2.3", &sig, &public_key));
+        assert!(!ed25519.verify(b"deploy v1.2.4", &sig, &public_key), "it rejects a different message");
+
+        let mut tampered = sig.clone();
+        tampered[40] ^= 1;
+        assert!(!ed25519.verify(b"deploy v1.2.3", &tampered, &public_key), "it rejects a modified S");
+
+        // S + L is the same scalar but isn't accepted
+        let s = BigInt::from_bytes_le(Sign::Plus, &sig[32..]) + Ed25519::l();
+        let mut malleated = sig[..32].to_vec();
+        malleated.extend(scalar_to_bytes(&s));
+        assert!(!ed25519.verify(b"deploy v1.2.3", &malleated, &public_key), "it rejects S >= L");
+
+        assert!(ed25519.sign(b"", &seed[..31]).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+use std::fmt;
+use num_bigint::{BigInt, Sign};
+use num_integer::{Integer};
+use num_traits::*;
+use finite_field::{Field, FieldElement};
+
+/// Twisted Edwards curve ax^2 + y^2 = 1 + dx^2y^2 over F_p.
+///
+/// Unlike the Weierstrass curves in elliptic_curve.rs the addition law is the same for doubling
+/// and for the identity (0, 1), so there's no special point at infinity. It's complete when a is
+/// a square and d isn't.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdwardsCurve {
+    field: Field,
+    a: FieldElement,
+    d: FieldElement,
+}
+
+/// Point on a twisted Edwards curve in affine coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdwardsPoint {
+    pub x: FieldElement,
+    pub y: FieldElement,
+}
+
+impl EdwardsCurve {
+    pub fn new<T: Into<BigInt>>(a: T, d: T, p: T) -> Self {
+        let field = Field::new(p);
+        EdwardsCurve { a: field.elem(a), d: field.elem(d), field }
+    }
+
+    /// Curve25519 in the twisted Edwards form used by Ed25519: a = -1, d = -121665 / 121666 and
+    /// p = 2^255 - 19. This is birationally equivalent to the Montgomery curve used by X25519.
+    pub fn edwards25519() -> Self {
+        let p = (BigInt::one() << 255) - BigInt::from(19);
+        let field = Field::new(p.clone());
+        let d = -field.elem(121665) / field.elem(121666);
+        EdwardsCurve::new(BigInt::from(-1), d.value, p)
+    }
+
+    pub fn field_ref(&self) -> &Field {
+        &self.field
+    }
+
+    pub fn field_elem<T: Into<BigInt>>(&self, n: T) -> FieldElement {
+        self.field.elem(n)
+    }
+
+    /// Neutral element of the group
+    pub fn identity(&self) -> EdwardsPoint {
+        EdwardsPoint { x: self.field_elem(0), y: self.field_elem(1) }
+    }
+
+    /// Create point on the curve, panicking if it isn't on it
+    pub fn point<T: Into<BigInt>, P: Into<BigInt>>(&self, x: T, y: P) -> EdwardsPoint {
+        let point = EdwardsPoint { x: self.field_elem(x), y: self.field_elem(y) };
+        assert!(self.is_valid_point(&point), "new point is not on curve");
+        point
+    }
+
+    pub fn is_valid_point(&self, point: &EdwardsPoint) -> bool {
+        let x2 = &point.x * &point.x;
+        let y2 = &point.y * &point.y;
+        let lhs = &self.a * &x2 + y2.clone();
+        let rhs = &(&self.d * &x2) * &y2 + self.field_elem(1);
+        lhs == rhs
+    }
+
+    /// Find the x with the given parity such that (x, y) is on the curve, where
+    ///   x^2 = (y^2 - 1) / (dy^2 - a)
+    pub fn solve_x(&self, y: &FieldElement, is_odd: bool) -> Result<FieldElement, String> {
+        let y2 = y * y;
+        let x2 = (&y2 - &self.field_elem(1)) / (&self.d * &y2 - &self.a);
+        let x = x2.sqrt().ok_or_else(|| String::from("No point on the curve with this y coordinate"))?;
+
+        if x.value.is_zero() && is_odd {
+            return Err(String::from("x is 0 so it can't be odd"));
+        }
+        Ok(if x.is_even() == is_odd { -x } else { x })
+    }
+
+    // Bytes needed for the y coordinate plus a sign bit for x
+    fn encoded_len(&self) -> usize {
+        (self.field.p_ref().bits() + 1).div_ceil(8)
+    }
+
+    /// Encode a point as in RFC 8032: y in little-endian with the lowest bit of x in the top bit
+    /// of the last byte.
+    pub fn encode_point(&self, point: &EdwardsPoint) -> Vec<u8> {
+        let mut bytes = point.y.value.to_bytes_le().1;
+        bytes.resize(self.encoded_len(), 0);
+
+        if !point.x.is_even() {
+            let last = bytes.len() - 1;
+            bytes[last] |= 0x80;
+        }
+        bytes
+    }
+
+    /// Decode a point encoded with encode_point, checking it's on the curve.
+    pub fn decode_point(&self, bytes: &[u8]) -> Result<EdwardsPoint, String> {
+        if bytes.len() != self.encoded_len() {
+            return Err(format!("Encoded point must be {} bytes", self.encoded_len()));
+        }
+
+        let mut y_bytes = bytes.to_vec();
+        let is_odd = y_bytes[bytes.len() - 1] & 0x80 != 0;
+        y_bytes[bytes.len() - 1] &= 0x7f;
+
+        let y = BigInt::from_bytes_le(Sign::Plus, &y_bytes);
+        if &y >= self.field.p_ref() {
+            return Err(String::from("y coordinate is not reduced mod p"));
+        }
+
+        let y = self.field_elem(y);
+        let x = self.solve_x(&y, is_odd)?;
+        Ok(EdwardsPoint { x, y })
+    }
+}
+
+impl EdwardsPoint {
+    /// Return the additive inverse of the point
+    pub fn inverse(&self) -> EdwardsPoint {
+        EdwardsPoint { x: -self.x.clone(), y: self.y.clone() }
+    }
+
+    /// Unified addition law, also used for doubling:
+    ///   x_3 = (x_1 y_2 + y_1 x_2) / (1 + d x_1 x_2 y_1 y_2)
+    ///   y_3 = (y_1 y_2 - a x_1 x_2) / (1 - d x_1 x_2 y_1 y_2)
+    pub fn add(&self, q: &EdwardsPoint, curve: &EdwardsCurve) -> EdwardsPoint {
+        let one = curve.field_elem(1);
+        let x1x2 = &self.x * &q.x;
+        let y1y2 = &self.y * &q.y;
+        let dxy = &(&curve.d * &x1x2) * &y1y2;
+
+        let x = (&self.x * &q.y + &self.y * &q.x) / (&one + dxy.clone());
+        let y = (&y1y2 - &(&curve.a * &x1x2)) / (one - dxy);
+        EdwardsPoint { x, y }
+    }
+
+    /// Multiplication implemented using the double-and-add algorithm
+    pub fn mul(&self, n: &BigInt, curve: &EdwardsCurve) -> EdwardsPoint {
+        let mut coeff = n.clone();
+        let mut current = self.clone();
+        let mut result = curve.identity();
+
+        if coeff.is_negative() {
+            coeff = -coeff;
+            current = current.inverse();
+        }
+
+        while !coeff.is_zero() {
+            if coeff.is_odd() {
+                result = result.add(&current, curve);
+            }
+            current = current.add(&current, curve);
+            coeff >>= 1;
+        }
+
+        result
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.x.value.is_zero() && self.y.value.is_one()
+    }
+}
+
+impl fmt::Display for EdwardsPoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use edwards::*;
+
+    #[test]
+    fn edwards_toy_curve_math() {
+        // x^2 + y^2 = 1 + 2x^2y^2 over F_13 has 8 points. d = 2 isn't a square mod 13.
+        let curve = EdwardsCurve::new(1, 2, 13);
+        let g = curve.point(1, 0);
+
+        assert_eq!(g.add(&curve.identity(), &curve), g);
+        assert_eq!(g.add(&g.inverse(), &curve), curve.identity());
+        assert_eq!(g.mul(&BigInt::from(2), &curve), g.add(&g, &curve));
+        assert!(curve.is_valid_point(&g.mul(&BigInt::from(3), &curve)));
+        assert!(g.mul(&BigInt::from(4), &curve).is_identity(), "(1, 0) has order 4");
+        assert_eq!(g.mul(&BigInt::from(-1), &curve), g.inverse());
+    }
+
+    #[test]
+    fn edwards_encoding() {
+        let curve = EdwardsCurve::edwards25519();
+        let y = curve.field_elem(4) / curve.field_elem(5);
+        let base = EdwardsPoint { x: curve.solve_x(&y, false).unwrap(), y };
+        assert!(curve.is_valid_point(&base));
+
+        // RFC 8032 5.1: B is (x, 4/5) with x positive, so encodes as 0x58 followed by 0x66s
+        let encoded = curve.encode_point(&base);
+        assert_eq!(encoded[0], 0x58);
+        assert!(encoded[1..].iter().all(|b| *b == 0x66));
+
+        let point = base.mul(&BigInt::from(12345), &curve);
+        assert_eq!(curve.decode_point(&curve.encode_point(&point)), Ok(point.clone()));
+        assert_eq!(curve.decode_point(&curve.encode_point(&point.inverse())), Ok(point.inverse()));
+        assert!(curve.decode_point(&encoded[1..]).is_err());
+    }
+}
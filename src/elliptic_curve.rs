@@ -1,9 +1,9 @@
+use std::cmp;
 use std::fmt;
 use num_bigint::{BigInt, Sign};
-use num_integer::{Integer};
 use num_traits::*;
 use finite_field::{Field, FieldElement};
-use util::{bigint_to_bytes32_be};
+use util::{bigint_to_bytes_be};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Point {
@@ -59,12 +59,17 @@ impl Point {
     ///
     /// https://en.wikipedia.org/wiki/Elliptic_curve_point_multiplication#Double-and-add
     pub fn mul<T: Into<BigInt> + Clone>(&self, n: &T, curve: &FiniteCurvy) -> Point {
-        // mod allows us to handle negative numbers by:
-        //   g^(x) = g^(x+q), so.. g^(-x+q) = g^(q-x) which is the same as: x % q
-        let mut coeff = n.clone().into().mod_floor(curve.field_ref().p_ref());
+        // The group order generally isn't p so the scalar can't be reduced mod p. Negative scalars
+        // multiply the inverse point instead: (-n)P = n(-P)
+        let mut coeff = n.clone().into();
         let mut current = self.clone();
         let mut result = Point::Infinity;
 
+        if coeff.is_negative() {
+            coeff = -coeff;
+            current = current.inverse();
+        }
+
         while coeff > BigInt::zero() {
             if !(&coeff & BigInt::one()).is_zero() {
                 result = result.add(&current, curve); // add
@@ -90,6 +95,12 @@ impl fmt::Display for Point {
     }
 }
 
+// Number of bytes used for each coordinate in SEC encoding. This is the byte length of p, but
+// fields smaller than 256 bits are still encoded using 32 bytes.
+fn sec_coord_len(p: &BigInt) -> usize {
+    cmp::max(32, p.bits().div_ceil(8))
+}
+
 // Standards for Efficient Cryptography (SEC) encoding
 pub trait Sec<T, C> where T: Sized, C: Sized {
     fn as_sec(&self) -> Vec<u8>;
@@ -102,9 +113,10 @@ impl Sec<Point, FiniteCurve> for Point {
         match self {
             Point::Infinity => panic!("cannot encode infinity in sec"),
             Point::Coordinate { x, y } => {
+                let len = sec_coord_len(x.p_ref());
                 let mut result = vec![0x04];
-                result.append(&mut bigint_to_bytes32_be(&x.value, true));
-                result.append(&mut bigint_to_bytes32_be(&y.value, true));
+                result.append(&mut bigint_to_bytes_be(&x.value, len, true));
+                result.append(&mut bigint_to_bytes_be(&y.value, len, true));
                 result
             }
         }
@@ -117,7 +129,7 @@ impl Sec<Point, FiniteCurve> for Point {
                 let mut result = vec![];
                 let prefix = if y.is_even() { 2 } else { 3 };
                 result.push(prefix);
-                result.append(&mut bigint_to_bytes32_be(&x.value, false));
+                result.append(&mut bigint_to_bytes_be(&x.value, sec_coord_len(x.p_ref()), true));
                 result
             }
         }
@@ -125,28 +137,39 @@ impl Sec<Point, FiniteCurve> for Point {
 
     /// Decode sec encoded bytes into a Point. Supports compressed and uncompressed formats.
     fn from_sec<'a>(bytes: &'a [u8], curve: &'a FiniteCurve) -> Result<Point, String> {
-        match bytes[0] {
-            2 => { // y is even
-                let x = BigInt::from_bytes_be(Sign::Plus, &bytes[1..]);
-                let y = curve.solve_y(&x, true);
-                Ok(curve.point(x, y))
-            },
-            3 => { // y is odd
-                let x = BigInt::from_bytes_be(Sign::Plus, &bytes[1..]);
-                let y = curve.solve_y(&x, false);
-                Ok(curve.point(x, y))
-            },
-            4 => {
-                if bytes.len() < 65 {
-                    return Err(String::from("Not enough bytes. Expected at least 65"));
-                }
-
-                let x = BigInt::from_bytes_be(Sign::Plus, &bytes[1..33]);
-                let y = BigInt::from_bytes_be(Sign::Plus, &bytes[33..65]);
-                Ok(curve.point(x, y))
+        let (prefix, rest) = bytes.split_first().ok_or_else(|| String::from("Empty SEC encoding"))?;
+        let len = sec_coord_len(curve.field.p_ref());
+        let expected = match prefix {
+            2 | 3 => len,
+            4 => 2 * len,
+            prefix => return Err(format!("Invalid prefix: {}", prefix)),
+        };
+        if rest.len() != expected {
+            return Err(format!("Invalid SEC length. Expected {} bytes but got {}", 1 + expected, bytes.len()));
+        }
+
+        let coord = |bytes: &[u8]| {
+            let n = BigInt::from_bytes_be(Sign::Plus, bytes);
+            if &n >= curve.field.p_ref() {
+                return Err(format!("Coordinate {} isn't less than p", n));
+            }
+            Ok(n)
+        };
+        let (x, y) = match prefix {
+            // 2 means y is even, 3 that it's odd
+            2 | 3 => {
+                let x = coord(rest)?;
+                let y = curve.solve_y(&x, *prefix == 2)?;
+                (curve.field_elem(x), y)
             },
-            prefix => Err(format!("Invalid prefix: {}", prefix))
+            _ => (curve.field_elem(coord(&rest[..len])?), curve.field_elem(coord(&rest[len..])?)),
+        };
+
+        let point = Point::coord(x, y);
+        if !curve.is_valid_point(&point) {
+            return Err(String::from("Point is not on the curve"));
         }
+        Ok(point)
     }
 }
 
@@ -180,12 +203,12 @@ impl FiniteCurve {
     }
 
     /// Determine the y value for a given x coordinate and itendify if you want it to be even or
-    /// not. This is used for SEC parsing.
-    fn solve_y(&self, x: &BigInt, is_even: bool) -> FieldElement {
+    /// not. This is used for SEC parsing. Errors if there's no point on the curve with that x.
+    pub fn solve_y(&self, x: &BigInt, is_even: bool) -> Result<FieldElement, String> {
         // rhs of y^2 = x^3 + ax + 7
         let x_3 = self.field_elem(x.pow(3 as u8));
         let rhs = x_3 + &self.a*x + &self.b;
-        let y = rhs.sqrt();
+        let y = rhs.sqrt().ok_or_else(|| format!("No point on the curve with x = {}", x))?;
 
         let (even_beta, odd_beta) = if y.is_even() {
             (y.clone(), self.field_elem(self.field.p_ref() - &y.value))
@@ -194,9 +217,9 @@ impl FiniteCurve {
         };
 
         if is_even {
-            even_beta
+            Ok(even_beta)
         } else {
-            odd_beta
+            Ok(odd_beta)
         }
     }
 
@@ -220,6 +243,14 @@ pub trait FiniteCurvy {
     fn b_ref(&self) -> &FieldElement;
 }
 
+/// A curve together with a base point generating a subgroup of prime order n. This is everything
+/// needed to do ECDSA over the curve.
+pub trait CurveGroup: FiniteCurvy {
+    fn curve_ref(&self) -> &FiniteCurve;
+    fn g_ref(&self) -> &Point;
+    fn n_ref(&self) -> &BigInt;
+}
+
 impl FiniteCurvy for FiniteCurve {
     fn field_ref(&self) -> &Field {
         &self.field
@@ -257,6 +288,11 @@ mod tests {
         let exp = c.point(80, 10);
         println!("res: {}, exp: {}, add: {}", res, exp, c.point(3, 6).add(&c.point(3, 6), c));
         assert_eq!(res, exp);
+
+        // (3, 6) has order 5, which has nothing to do with p
+        assert_eq!(c.point(3, 6).mul(&5, c), Point::Infinity);
+        assert_eq!(c.point(3, 6).mul(&(97 + 2), c), c.point(3, 6).mul(&4, c));
+        assert_eq!(c.point(3, 6).mul(&-2, c), exp.inverse());
     }
 
     #[test]
@@ -287,8 +323,8 @@ mod tests {
     fn elliptic_curve_sec_compressed() {
         let c = &FiniteCurve::new(2, 3, 97);
 
-        assert_eq!(c.point(1, 2).as_sec_compressed(), vec![2, 1]);
-        assert_eq!(c.point(1, 3).as_sec_compressed(), vec![3, 1]);
+        assert_eq!(c.point(1, 2).as_sec_compressed(), [&[2][..], &[0; 31], &[1]].concat());
+        assert_eq!(c.point(1, 3).as_sec_compressed(), [&[3][..], &[0; 31], &[1]].concat());
     }
 
     #[test]
@@ -316,7 +352,35 @@ mod tests {
         ], &c).unwrap());
 
         // Compressed format
-        assert_eq!(c.point(1, 54), Point::from_sec(&[2, 1], &c).unwrap());
-        assert_eq!(c.point(1, 45), Point::from_sec(&[3, 1], &c).unwrap());
+        assert_eq!(c.point(1, 54), Point::from_sec(&[&[2][..], &[0; 31], &[1]].concat(), &c).unwrap());
+        assert_eq!(c.point(1, 45), Point::from_sec(&[&[3][..], &[0; 31], &[1]].concat(), &c).unwrap());
+    }
+
+    #[test]
+    fn elliptic_curve_from_sec_rejects_invalid() {
+        let c = &FiniteCurve::new(0, 7, 223);
+        let sec = c.point(47, 71).as_sec();
+        assert_eq!(Point::from_sec(&sec, c), Ok(c.point(47, 71)));
+        assert_eq!(Point::from_sec(&sec[..33], c), Err(String::from("Invalid SEC length. Expected 65 bytes but got 33")));
+        assert_eq!(Point::from_sec(&c.point(47, 71).as_sec_compressed(), c), Ok(c.point(47, 71)));
+
+        assert!(Point::from_sec(&[], c).is_err());
+        assert!(Point::from_sec(&[5; 65], c).is_err());
+        assert!(Point::from_sec(&[&sec[..], &[0]].concat(), c).is_err());
+        let mut long_compressed = vec![0; 41];
+        long_compressed[0] = 2;
+        long_compressed[40] = 1;
+        assert!(Point::from_sec(&long_compressed, c).is_err());
+
+        // Not on the curve
+        let mut off_curve = sec.clone();
+        off_curve[64] += 1;
+        assert_eq!(Point::from_sec(&off_curve, c), Err(String::from("Point is not on the curve")));
+
+        // x + p is the same point mod p, but isn't a valid encoding
+        let mut unreduced = sec.clone();
+        unreduced[31] = 1;
+        unreduced[32] = 14; // 270 = 0x010e
+        assert_eq!(Point::from_sec(&unreduced, c), Err(String::from("Coordinate 270 isn't less than p")));
     }
 }
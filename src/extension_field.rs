@@ -0,0 +1,409 @@
+use std::cell::{OnceCell};
+use std::fmt;
+use std::ops::{Add, Sub, Mul, Div, Neg};
+use std::rc::{Rc};
+use num_bigint::{BigInt};
+use num_integer::{Integer};
+use num_traits::*;
+use finite_field::{Field};
+
+/// Extension field F_p^k = F_p[x] / m(x) where m(x) is a monic irreducible polynomial of degree k.
+/// Elements are polynomials of degree < k with coefficients in F_p.
+///
+/// The fields used by BN254 are built directly on top of F_p rather than as a tower:
+///   F_p^2  = F_p[u] / (u^2 + 1)
+///   F_p^6  = F_p[v] / (v^6 - 18v^3 + 82)      where v^3 = 9 + u
+///   F_p^12 = F_p[w] / (w^12 - 18w^6 + 82)     where w^2 = v
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtensionField {
+    params: Rc<Params>,
+}
+
+#[derive(Debug)]
+struct Params {
+    field: Field,
+    // Coefficients of m(x) lowest degree first, without the leading 1
+    modulus: Vec<BigInt>,
+    // Coefficients of x^(ip) for i = 0..k, filled in the first time frobenius is called
+    frobenius: OnceCell<Vec<Vec<BigInt>>>,
+}
+
+// The frobenius cache is left out so that equality doesn't depend on whether it's been filled yet
+impl PartialEq for Params {
+    fn eq(&self, other: &Params) -> bool {
+        self.field == other.field && self.modulus == other.modulus
+    }
+}
+
+impl ExtensionField {
+    /// Create the extension of F_p by the monic polynomial whose lower coefficients, lowest degree
+    /// first, are given in modulus. Irreducibility isn't checked.
+    pub fn new<T: Into<BigInt>>(p: T, modulus: &[i64]) -> Self {
+        let field = Field::new(p);
+        let modulus = modulus.iter().map(|c| BigInt::from(*c).mod_floor(field.p_ref())).collect();
+        ExtensionField { params: Rc::new(Params { field, modulus, frobenius: OnceCell::new() }) }
+    }
+
+    /// F_p^2 with u^2 = -1
+    pub fn fp2<T: Into<BigInt>>(p: T) -> Self {
+        ExtensionField::new(p, &[1, 0])
+    }
+
+    /// F_p^6 with v^6 = 18v^3 - 82
+    pub fn fp6<T: Into<BigInt>>(p: T) -> Self {
+        ExtensionField::new(p, &[82, 0, 0, -18, 0, 0])
+    }
+
+    /// F_p^12 with w^12 = 18w^6 - 82
+    pub fn fp12<T: Into<BigInt>>(p: T) -> Self {
+        ExtensionField::new(p, &[82, 0, 0, 0, 0, 0, -18, 0, 0, 0, 0, 0])
+    }
+
+    pub fn degree(&self) -> usize {
+        self.params.modulus.len()
+    }
+
+    pub fn p_ref(&self) -> &BigInt {
+        self.params.field.p_ref()
+    }
+
+    /// Return an element from its coefficients, lowest degree first. Missing coefficients are 0.
+    pub fn elem<T: Into<BigInt> + Clone>(&self, coeffs: &[T]) -> ExtensionFieldElement {
+        assert!(coeffs.len() <= self.degree(), "too many coefficients for the field");
+        let mut values: Vec<BigInt> = coeffs.iter().map(|c| c.clone().into()).collect();
+        values.resize(self.degree(), BigInt::zero());
+        ExtensionFieldElement::new(values, self.clone())
+    }
+
+    pub fn zero(&self) -> ExtensionFieldElement {
+        self.elem::<BigInt>(&[])
+    }
+
+    pub fn one(&self) -> ExtensionFieldElement {
+        self.elem(&[1])
+    }
+}
+
+/// Value within an extension field
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtensionFieldElement {
+    pub coeffs: Vec<BigInt>,
+    field: ExtensionField,
+}
+
+impl ExtensionFieldElement {
+    fn new(coeffs: Vec<BigInt>, field: ExtensionField) -> Self {
+        let p = field.p_ref();
+        let coeffs = coeffs.iter().map(|c| c.mod_floor(p)).collect();
+        ExtensionFieldElement { coeffs, field }
+    }
+
+    pub fn field_ref(&self) -> &ExtensionField {
+        &self.field
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coeffs.iter().all(|c| c.is_zero())
+    }
+
+    pub fn is_one(&self) -> bool {
+        self.coeffs[0].is_one() && self.coeffs[1..].iter().all(|c| c.is_zero())
+    }
+
+    /// Multiply every coefficient by a value in F_p
+    pub fn scale(&self, n: &BigInt) -> Self {
+        ExtensionFieldElement::new(self.coeffs.iter().map(|c| c * n).collect(), self.field.clone())
+    }
+
+    pub fn pow(&self, n: &BigInt) -> Self {
+        // Square and multiply, from the most significant bit down
+        let mut result = self.field.one();
+        for bit in n.to_str_radix(2).chars() {
+            result = &result * &result;
+            if bit == '1' {
+                result = &result * self;
+            }
+        }
+
+        result
+    }
+
+    /// Raise to the power p. This is linear over F_p so with x^(ip) precomputed it's just a sum
+    /// of c_i x^(ip) rather than a full exponentiation.
+    pub fn frobenius(&self) -> Self {
+        let powers = self.field.params.frobenius.get_or_init(|| {
+            let x_p = self.field.elem(&[0, 1]).pow(self.field.p_ref());
+            let mut power = self.field.one();
+            (0..self.field.degree()).map(|_| {
+                let coeffs = power.coeffs.clone();
+                power = &power * &x_p;
+                coeffs
+            }).collect()
+        });
+
+        let mut coeffs = vec![BigInt::zero(); self.field.degree()];
+        for (c, power) in self.coeffs.iter().zip(powers.iter()) {
+            for (result, x) in coeffs.iter_mut().zip(power.iter()) {
+                *result += c * x;
+            }
+        }
+
+        ExtensionFieldElement::new(coeffs, self.field.clone())
+    }
+
+    /// Find the multiplicative inverse using the extended Euclidean algorithm over F_p[x]. We look
+    /// for s with s * a + t * m = 1 so that s * a = 1 mod m.
+    pub fn inverse(&self) -> Self {
+        if self.is_zero() {
+            panic!("0 has no multiplicative inverse");
+        }
+
+        let p = self.field.p_ref();
+        let mut modulus = self.field.params.modulus.clone();
+        modulus.push(BigInt::one());
+
+        let (mut old_r, mut r) = (modulus, poly_trim(self.coeffs.clone()));
+        let (mut old_s, mut s) = (vec![], vec![BigInt::one()]);
+        while !r.is_empty() {
+            let (quotient, remainder) = poly_divmod(&old_r, &r, &self.field.params.field);
+            old_r = r;
+            r = remainder;
+
+            let next_s = poly_sub(&old_s, &poly_mul(&quotient, &s, p), p);
+            old_s = s;
+            s = next_s;
+        }
+
+        // old_r is now a non-zero constant since m is irreducible. Divide it out to get 1.
+        let scale = self.field.params.field.elem(old_r[0].clone()).inverse().value;
+        let mut coeffs: Vec<BigInt> = old_s.iter().map(|c| c * &scale).collect();
+        coeffs.resize(self.field.degree(), BigInt::zero());
+        ExtensionFieldElement::new(coeffs, self.field.clone())
+    }
+}
+
+// Polynomials here are Vec<BigInt> with coefficients mod p, lowest degree first and no trailing
+// zeros so the zero polynomial is empty.
+
+pub(crate) fn poly_trim(mut a: Vec<BigInt>) -> Vec<BigInt> {
+    while a.last().is_some_and(|c| c.is_zero()) {
+        a.pop();
+    }
+    a
+}
+
+pub(crate) fn poly_sub(a: &[BigInt], b: &[BigInt], p: &BigInt) -> Vec<BigInt> {
+    let len = a.len().max(b.len());
+    let result = (0..len).map(|i| {
+        let left = a.get(i).cloned().unwrap_or_else(BigInt::zero);
+        let right = b.get(i).cloned().unwrap_or_else(BigInt::zero);
+        (left - right).mod_floor(p)
+    }).collect();
+    poly_trim(result)
+}
+
+pub(crate) fn poly_mul(a: &[BigInt], b: &[BigInt], p: &BigInt) -> Vec<BigInt> {
+    if a.is_empty() || b.is_empty() {
+        return vec![];
+    }
+
+    let mut result = vec![BigInt::zero(); a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            result[i + j] += x * y;
+        }
+    }
+    poly_trim(result.iter().map(|c| c.mod_floor(p)).collect())
+}
+
+// Long division returning (quotient, remainder). b must be non-zero.
+pub(crate) fn poly_divmod(a: &[BigInt], b: &[BigInt], field: &Field) -> (Vec<BigInt>, Vec<BigInt>) {
+    let p = field.p_ref();
+    let mut remainder = a.to_vec();
+    if a.len() < b.len() {
+        return (vec![], remainder);
+    }
+
+    let lead_inv = field.elem(b[b.len() - 1].clone()).inverse().value;
+    let mut quotient = vec![BigInt::zero(); a.len() - b.len() + 1];
+    for i in (0..quotient.len()).rev() {
+        let q = (&remainder[i + b.len() - 1] * &lead_inv).mod_floor(p);
+        for (j, c) in b.iter().enumerate() {
+            remainder[i + j] = (&remainder[i + j] - &q * c).mod_floor(p);
+        }
+        quotient[i] = q;
+    }
+
+    (poly_trim(quotient), poly_trim(remainder))
+}
+
+impl fmt::Display for ExtensionFieldElement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let coeffs: Vec<String> = self.coeffs.iter().map(|c| c.to_str_radix(16)).collect();
+        write!(f, "[{}]", coeffs.join(", "))
+    }
+}
+
+impl Add<&ExtensionFieldElement> for &ExtensionFieldElement {
+    type Output = ExtensionFieldElement;
+
+    fn add(self, rhs: &ExtensionFieldElement) -> ExtensionFieldElement {
+        let coeffs = self.coeffs.iter().zip(rhs.coeffs.iter()).map(|(a, b)| a + b).collect();
+        ExtensionFieldElement::new(coeffs, self.field.clone())
+    }
+}
+
+impl Add for ExtensionFieldElement {
+    type Output = ExtensionFieldElement;
+
+    fn add(self, rhs: ExtensionFieldElement) -> ExtensionFieldElement {
+        &self + &rhs
+    }
+}
+
+impl Sub<&ExtensionFieldElement> for &ExtensionFieldElement {
+    type Output = ExtensionFieldElement;
+
+    fn sub(self, rhs: &ExtensionFieldElement) -> ExtensionFieldElement {
+        let coeffs = self.coeffs.iter().zip(rhs.coeffs.iter()).map(|(a, b)| a - b).collect();
+        ExtensionFieldElement::new(coeffs, self.field.clone())
+    }
+}
+
+impl Sub for ExtensionFieldElement {
+    type Output = ExtensionFieldElement;
+
+    fn sub(self, rhs: ExtensionFieldElement) -> ExtensionFieldElement {
+        &self - &rhs
+    }
+}
+
+impl Neg for &ExtensionFieldElement {
+    type Output = ExtensionFieldElement;
+
+    fn neg(self) -> ExtensionFieldElement {
+        let coeffs = self.coeffs.iter().map(|c| -c).collect();
+        ExtensionFieldElement::new(coeffs, self.field.clone())
+    }
+}
+
+impl Neg for ExtensionFieldElement {
+    type Output = ExtensionFieldElement;
+
+    fn neg(self) -> ExtensionFieldElement {
+        -&self
+    }
+}
+
+impl Mul<&ExtensionFieldElement> for &ExtensionFieldElement {
+    type Output = ExtensionFieldElement;
+
+    // Multiply as polynomials and then reduce using x^k = -(m_0 + m_1 x + ... + m_(k-1) x^(k-1)).
+    // Coefficients are only reduced mod p once at the end.
+    fn mul(self, rhs: &ExtensionFieldElement) -> ExtensionFieldElement {
+        let k = self.field.degree();
+        let modulus = &self.field.params.modulus;
+
+        let mut product = vec![BigInt::zero(); 2 * k - 1];
+        for (i, a) in self.coeffs.iter().enumerate().filter(|(_, a)| !a.is_zero()) {
+            for (j, b) in rhs.coeffs.iter().enumerate() {
+                product[i + j] += a * b;
+            }
+        }
+
+        for i in (k..product.len()).rev() {
+            let top = product[i].mod_floor(self.field.p_ref());
+            for (j, m) in modulus.iter().enumerate().filter(|(_, m)| !m.is_zero()) {
+                product[i - k + j] -= &top * m;
+            }
+        }
+
+        product.truncate(k);
+        ExtensionFieldElement::new(product, self.field.clone())
+    }
+}
+
+impl Mul for ExtensionFieldElement {
+    type Output = ExtensionFieldElement;
+
+    fn mul(self, rhs: ExtensionFieldElement) -> ExtensionFieldElement {
+        &self * &rhs
+    }
+}
+
+impl Div<&ExtensionFieldElement> for &ExtensionFieldElement {
+    type Output = ExtensionFieldElement;
+
+    fn div(self, rhs: &ExtensionFieldElement) -> ExtensionFieldElement {
+        self.mul(&rhs.inverse())
+    }
+}
+
+impl Div for ExtensionFieldElement {
+    type Output = ExtensionFieldElement;
+
+    fn div(self, rhs: ExtensionFieldElement) -> ExtensionFieldElement {
+        &self / &rhs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use extension_field::*;
+
+    #[test]
+    fn extension_field_fp2_math() {
+        // F_49 as F_7[u] / (u^2 + 1), which works since 7 % 4 = 3
+        let f = ExtensionField::fp2(7);
+        let a = f.elem(&[3, 2]);
+        let b = f.elem(&[5, 6]);
+
+        assert_eq!(&a + &b, f.elem(&[1, 1]));
+        assert_eq!(&a - &b, f.elem(&[5, 3]));
+        assert_eq!(-&a, f.elem(&[4, 5]));
+        // (3 + 2u)(5 + 6u) = 15 + 28u + 12u^2 = 3 + 28u
+        assert_eq!(&a * &b, f.elem(&[3, 0]));
+        assert_eq!(f.elem(&[0, 1]).pow(&BigInt::from(2)), f.elem(&[-1]));
+    }
+
+    #[test]
+    fn extension_field_inverse() {
+        let f = ExtensionField::fp2(7);
+        for a in 0..7 {
+            for b in 0..7 {
+                if a == 0 && b == 0 { continue; }
+                let elem = f.elem(&[a, b]);
+                assert!((&elem * &elem.inverse()).is_one(), "inverse of {}", elem);
+            }
+        }
+
+        let p = BigInt::parse_bytes(b"30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47", 16).unwrap();
+        let f = ExtensionField::fp12(p);
+        let elem = f.elem(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]);
+        assert!((&elem * &elem.inverse()).is_one());
+        assert_eq!(&(&elem / &elem) * &elem, elem);
+    }
+
+    #[test]
+    fn extension_field_frobenius() {
+        // Raising to the power p^k is the identity in F_p^k
+        let p = BigInt::parse_bytes(b"30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47", 16).unwrap();
+        for f in [ExtensionField::fp2(p.clone()), ExtensionField::fp6(p.clone())] {
+            let coeffs: Vec<i64> = (1..=f.degree() as i64).collect();
+            let elem = f.elem(&coeffs);
+            let p_k = p.pow(f.degree());
+            assert_eq!(elem.pow(&p_k), elem);
+            assert_eq!(elem.frobenius(), elem.pow(&p));
+        }
+
+        let f = ExtensionField::fp12(p.clone());
+        let elem = f.elem(&(1..=12).collect::<Vec<i64>>());
+        let frobenius_12 = (0..12).fold(elem.clone(), |acc, _| acc.frobenius());
+        assert_eq!(frobenius_12, elem);
+
+        // Filling the cache in one copy of a field doesn't make it differ from another
+        let other = ExtensionField::fp12(p.clone());
+        assert_eq!(f, other);
+        assert_eq!(elem, other.elem(&(1..=12).collect::<Vec<i64>>()));
+    }
+}
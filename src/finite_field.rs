@@ -15,8 +15,8 @@ fn extended_euclidean_algorithm(a: BigInt, b: BigInt) -> (BigInt, BigInt, BigInt
     let mut s = BigInt::zero();
     let mut old_s = BigInt::one();
 
-    let mut t = BigInt::zero();
-    let mut old_t = BigInt::one();
+    let mut t = BigInt::one();
+    let mut old_t = BigInt::zero();
 
     let mut r = b;
     let mut old_r = a;
@@ -37,6 +37,50 @@ fn extended_euclidean_algorithm(a: BigInt, b: BigInt) -> (BigInt, BigInt, BigInt
     (old_r, old_s, old_t)
 }
 
+/// Inverse of a modulo m, which doesn't need to be prime. None when a and m aren't coprime.
+pub fn mod_inverse(a: &BigInt, m: &BigInt) -> Option<BigInt> {
+    let (gcd, x, _) = extended_euclidean_algorithm(a.mod_floor(m), m.clone());
+    if gcd.is_one() {
+        Some(x.mod_floor(m))
+    } else {
+        None
+    }
+}
+
+/// Jacobi symbol (a/n) for odd positive n. Returns 1, -1 or 0 when a and n share a factor. For a
+/// prime n this is the Legendre symbol: 1 iff a is a non-zero square mod n.
+///
+/// Uses quadratic reciprocity so it only needs a gcd-like loop rather than exponentiation.
+pub fn jacobi(a: &BigInt, n: &BigInt) -> i8 {
+    assert!(n.is_positive() && n.is_odd(), "jacobi symbol needs an odd positive modulus");
+
+    let mut a = a.mod_floor(n);
+    let mut n = n.clone();
+    let mut result = 1;
+    let three = BigInt::from(3);
+    let five = BigInt::from(5);
+
+    while !a.is_zero() {
+        // (2/n) = -1 iff n % 8 is 3 or 5
+        while a.is_even() {
+            a >>= 1;
+            let n_mod_8 = &n % 8;
+            if n_mod_8 == three || n_mod_8 == five {
+                result = -result;
+            }
+        }
+
+        // Reciprocity flips the sign iff both are 3 mod 4
+        mem::swap(&mut a, &mut n);
+        if &a % 4 == three && &n % 4 == three {
+            result = -result;
+        }
+        a = a.mod_floor(&n);
+    }
+
+    if n.is_one() { result } else { 0 }
+}
+
 /// Finite field over p
 #[derive(Debug, PartialEq, Clone)]
 pub struct Field {
@@ -99,6 +143,10 @@ impl FieldElement {
         FieldElement::new(inv, self.p.clone())
     }
 
+    pub fn p_ref(&self) -> &BigInt {
+        &self.p
+    }
+
     pub fn is_even(&self) -> bool {
         &self.value & BigInt::one() == BigInt::zero()
     }
@@ -108,6 +156,30 @@ impl FieldElement {
         FieldElement::new(val, self.p.clone())
     }
 
+    /// Legendre symbol of the element: 1 for non-zero squares, -1 for non-squares and 0 for 0.
+    pub fn legendre(&self) -> i8 {
+        jacobi(&self.value, &self.p)
+    }
+
+    /// Whether the element has a square root in the field. Zero counts as a square.
+    pub fn is_square(&self) -> bool {
+        self.legendre() != -1
+    }
+
+    /// Square root of the element, or None if it isn't a square. Uses the direct formula when
+    /// p % 4 = 3 and Tonelli-Shanks otherwise.
+    pub fn sqrt(&self) -> Option<FieldElement> {
+        if !self.is_square() {
+            return None;
+        }
+
+        if (&self.p % BigInt::from(4)) == BigInt::from(3) {
+            Some(self.sqrt_3_mod_4())
+        } else {
+            Some(self.tonelli_shanks())
+        }
+    }
+
     // Only works on curves where: p % 4 = 3
     // Derived from fact that p % 4 = 3 and a^(p-1) = 1 which gives us:
     //
@@ -116,10 +188,63 @@ impl FieldElement {
     // w^(2/2) = w^(p+1)/2
     // w = w^(p+1)/2
     // w = w^2(p+1)/4 = (w^2)^(p+1)/4 = v^(p+1)/4 = w
-    pub fn sqrt(&self) -> FieldElement {
+    fn sqrt_3_mod_4(&self) -> FieldElement {
         let exp = (&self.p + 1) / 4;
         self.pow(&exp)
     }
+
+    // Tonelli-Shanks works for any odd prime. Write p - 1 = q * 2^s with q odd, then start from a
+    // guess r = n^((q+1)/2) where r^2 = n * t. Each step uses powers of a non-residue z to cancel
+    // out part of t until t = 1 and r is the root.
+    //
+    // https://en.wikipedia.org/wiki/Tonelli%E2%80%93Shanks_algorithm
+    //
+    // Assumes the element is a square.
+    fn tonelli_shanks(&self) -> FieldElement {
+        if self.value.is_zero() {
+            return self.clone();
+        }
+
+        let one = BigInt::one();
+        let p_minus_one = &self.p - &one;
+        let euler_exp = &p_minus_one / 2;
+
+        let mut q = p_minus_one.clone();
+        let mut s = 0u32;
+        while q.is_even() {
+            q >>= 1;
+            s += 1;
+        }
+
+        // Half of the elements are non-residues so this doesn't take long
+        let mut z = FieldElement::new(BigInt::from(2), self.p.clone());
+        while z.is_square() {
+            z = z + FieldElement::new(one.clone(), self.p.clone());
+        }
+
+        let mut m = s;
+        let mut c = z.pow(&q);
+        let mut t = self.pow(&q);
+        let mut r = self.pow(&((&q + &one) / 2));
+
+        while t.value != one {
+            // Find the least i such that t^(2^i) = 1
+            let mut i = 0;
+            let mut t_2i = t.clone();
+            while t_2i.value != one {
+                t_2i = &t_2i * &t_2i;
+                i += 1;
+            }
+
+            let b = c.pow(&(BigInt::one() << (m - i - 1) as usize));
+            m = i;
+            c = &b * &b;
+            t = &t * &c;
+            r = &r * &b;
+        }
+
+        r
+    }
 }
 
 impl fmt::Display for FieldElement {
@@ -299,6 +424,74 @@ mod tests {
         assert_eq!(f.elem(3) / f.elem(BigInt::from(3)), 1);
     }
 
+    #[test]
+    fn field_element_sqrt() {
+        // p % 4 = 3
+        let f = Field::new(23);
+        let root = f.elem(18).sqrt().unwrap();
+        assert_eq!(&root * &root, 18);
+        assert_eq!(f.elem(5).sqrt(), None);
+
+        // p % 4 = 1 needs Tonelli-Shanks
+        let f = Field::new(17);
+        for n in 1..17 {
+            let square = f.elem(n) * f.elem(n);
+            let root = square.sqrt().unwrap();
+            assert_eq!(&root * &root, square);
+        }
+        assert_eq!(f.elem(3).sqrt(), None);
+        assert_eq!(f.elem(0).sqrt(), Some(f.elem(0)));
+
+        // P-224 has p - 1 = q * 2^96, which is the worst case for Tonelli-Shanks
+        let p = BigInt::parse_bytes(b"ffffffffffffffffffffffffffffffff000000000000000000000001", 16).unwrap();
+        let f = Field::new(p);
+        let n = f.elem(BigInt::parse_bytes(b"b70e0cbd6bb4bf7f321390b94a03c1d356c21122343280d6115c1d21", 16).unwrap());
+        let square = &n * &n;
+        let root = square.sqrt().unwrap();
+        assert_eq!(&root * &root, square);
+        assert!(root == n || root == -n);
+    }
+
+    #[test]
+    fn field_element_legendre() {
+        let f = Field::new(23);
+        let squares: Vec<BigInt> = (1..23).map(|n| (f.elem(n) * f.elem(n)).value).collect();
+
+        for n in 1..23 {
+            let elem = f.elem(n);
+            assert_eq!(elem.is_square(), squares.contains(&elem.value));
+            assert_eq!(elem.legendre(), if elem.is_square() { 1 } else { -1 });
+        }
+        assert_eq!(f.elem(0).legendre(), 0);
+    }
+
+    #[test]
+    fn jacobi_symbol() {
+        assert_eq!(jacobi(&BigInt::from(1001), &BigInt::from(9907)), -1);
+        assert_eq!(jacobi(&BigInt::from(19), &BigInt::from(45)), 1);
+        assert_eq!(jacobi(&BigInt::from(8), &BigInt::from(21)), -1);
+        assert_eq!(jacobi(&BigInt::from(5), &BigInt::from(21)), 1);
+        assert_eq!(jacobi(&BigInt::from(6), &BigInt::from(9)), 0);
+        assert_eq!(jacobi(&BigInt::from(-1), &BigInt::from(7)), -1);
+    }
+
+    #[test]
+    fn extended_euclidean() {
+        for &(a, b, gcd) in &[(240, 46, 2), (46, 240, 2), (17, 5, 1), (12, 12, 12), (0, 5, 5), (7, 0, 7), (-240, 46, 2)] {
+            let (a, b) = (BigInt::from(a), BigInt::from(b));
+            let (g, s, t) = extended_euclidean_algorithm(a.clone(), b.clone());
+            assert_eq!(g.abs(), BigInt::from(gcd));
+            assert_eq!(a * s + b * t, g);
+        }
+    }
+
+    #[test]
+    fn modular_inverse() {
+        assert_eq!(mod_inverse(&BigInt::from(3), &BigInt::from(10)), Some(BigInt::from(7)));
+        assert_eq!(mod_inverse(&BigInt::from(-3), &BigInt::from(10)), Some(BigInt::from(3)));
+        assert_eq!(mod_inverse(&BigInt::from(4), &BigInt::from(10)), None);
+    }
+
     #[test]
     fn field_element_is_even() {
         let f = Field::new(7);
@@ -0,0 +1,256 @@
+use std::ops::{Add, Mul};
+use block_cipher::{BlockCipher};
+use modes::{Counter, Ctr};
+use util::{constant_time_eq};
+
+// Galois/Counter Mode as specified in NIST SP 800-38D.
+//
+// Data is encrypted with CTR using a big-endian 32-bit counter. The tag authenticates the
+// associated data and ciphertext by evaluating them as a polynomial at the hash key H = E(0^128)
+// over GF(2^128) (GHASH), then masking the result with E(J0), the first counter block.
+//
+// The mask is the only thing hiding H. Reusing a nonce reuses the mask, so the XOR of two tags is a
+// known polynomial in H whose roots an attacker can find. See `set8::gcm_nonce_reuse`.
+//
+// https://nvlpubs.nist.gov/nistpubs/Legacy/SP/nistspecialpublication800-38d.pdf
+
+pub const BLOCK_SIZE: usize = 16;
+pub const TAG_SIZE: usize = 16;
+
+// Nonce length that's used directly as the start of the counter block
+const DEFAULT_NONCE_SIZE: usize = 12;
+
+/// Element of GF(2^128) modulo x^128 + x^7 + x^2 + x + 1 in GCM's bit order: the most significant
+/// bit of the first byte is the coefficient of x^0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Gf128(pub u128);
+
+impl Gf128 {
+    pub fn zero() -> Self {
+        Gf128(0)
+    }
+
+    pub fn one() -> Self {
+        Gf128(1 << 127)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Interpret up to 16 bytes as an element, zero-padding on the right
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut block = [0; BLOCK_SIZE];
+        block[..bytes.len()].copy_from_slice(bytes);
+        Gf128(u128::from_be_bytes(block))
+    }
+
+    pub fn to_bytes(self) -> [u8; BLOCK_SIZE] {
+        self.0.to_be_bytes()
+    }
+
+    pub fn pow(self, mut exp: u128) -> Self {
+        let (mut base, mut result) = (self, Gf128::one());
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result * base;
+            }
+            base = base * base;
+            exp >>= 1;
+        }
+        result
+    }
+
+    /// a^(2^128 - 2) = a^-1 for a != 0
+    pub fn inverse(self) -> Self {
+        self.pow(u128::MAX - 1)
+    }
+}
+
+impl Add for Gf128 {
+    type Output = Gf128;
+
+    // Characteristic 2, so adding coefficients is XOR
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn add(self, rhs: Gf128) -> Gf128 {
+        Gf128(self.0 ^ rhs.0)
+    }
+}
+
+impl Mul for Gf128 {
+    type Output = Gf128;
+
+    // Algorithm 1 in SP 800-38D. Shifting right multiplies by x in this bit order.
+    fn mul(self, rhs: Gf128) -> Gf128 {
+        const R: u128 = 0xe1 << 120;
+        let (mut z, mut v) = (0u128, rhs.0);
+        for i in (0..128).rev() {
+            if (self.0 >> i) & 1 == 1 {
+                z ^= v;
+            }
+            v = if v & 1 == 1 { (v >> 1) ^ R } else { v >> 1 };
+        }
+        Gf128(z)
+    }
+}
+
+/// GHASH of the associated data and ciphertext, each zero-padded to whole blocks, followed by a
+/// block with both lengths in bits
+pub fn ghash(h: Gf128, ad: &[u8], ciphertext: &[u8]) -> Gf128 {
+    ghash_blocks(h, &ghash_input(ad, ciphertext))
+}
+
+/// The blocks GHASH runs over. They're the coefficients of the polynomial that GHASH evaluates at
+/// H, highest power first, with no constant term.
+pub fn ghash_input(ad: &[u8], ciphertext: &[u8]) -> Vec<Gf128> {
+    let mut blocks: Vec<Gf128> = ad.chunks(BLOCK_SIZE).map(Gf128::from_bytes).collect();
+    blocks.extend(ciphertext.chunks(BLOCK_SIZE).map(Gf128::from_bytes));
+
+    let mut lengths = (8 * ad.len() as u64).to_be_bytes().to_vec();
+    lengths.extend_from_slice(&(8 * ciphertext.len() as u64).to_be_bytes());
+    blocks.push(Gf128::from_bytes(&lengths));
+    blocks
+}
+
+fn ghash_blocks(h: Gf128, blocks: &[Gf128]) -> Gf128 {
+    blocks.iter().fold(Gf128::zero(), |y, &block| (y + block) * h)
+}
+
+/// GCM over a block cipher with 16-byte blocks, e.g. AES
+pub struct Gcm<C: BlockCipher> {
+    ctr: Ctr<C>,
+    h: Gf128,
+}
+
+impl<C: BlockCipher> Gcm<C> {
+    pub fn new(cipher: C) -> Result<Self, String> {
+        if cipher.block_size() != BLOCK_SIZE {
+            return Err(format!("GCM needs a 16-byte block cipher, not {} bytes", cipher.block_size()));
+        }
+
+        let ctr = Ctr::with_counter(cipher, Counter::BigEndian);
+        let h = Gf128::from_bytes(&ctr.apply_keystream(&[0; BLOCK_SIZE], &[0; DEFAULT_NONCE_SIZE], 0)?);
+        Ok(Gcm { ctr, h })
+    }
+
+    /// The hash key H = E(0^128)
+    pub fn hash_key(&self) -> Gf128 {
+        self.h
+    }
+
+    /// Encrypt and authenticate plaintext, and authenticate ad, returning (ciphertext, tag)
+    pub fn encrypt(&self, plaintext: &[u8], ad: &[u8], nonce: &[u8]) -> Result<(Vec<u8>, Vec<u8>), String> {
+        let (prefix, j0) = self.counter_start(nonce)?;
+        let ciphertext = self.ctr.apply_keystream(plaintext, &prefix, j0.wrapping_add(1))?;
+        let tag = self.tag(&ciphertext, ad, &prefix, j0)?;
+        Ok((ciphertext, tag))
+    }
+
+    /// Check the tag and decrypt. Nothing is decrypted if the tag doesn't match.
+    pub fn decrypt(&self, ciphertext: &[u8], ad: &[u8], nonce: &[u8], tag: &[u8]) -> Result<Vec<u8>, String> {
+        let (prefix, j0) = self.counter_start(nonce)?;
+        if !constant_time_eq(&self.tag(ciphertext, ad, &prefix, j0)?, tag) {
+            return Err(String::from("Invalid tag"));
+        }
+        self.ctr.apply_keystream(ciphertext, &prefix, j0.wrapping_add(1))
+    }
+
+    // T = E(J0) + GHASH(A, C)
+    fn tag(&self, ciphertext: &[u8], ad: &[u8], prefix: &[u8], j0: u64) -> Result<Vec<u8>, String> {
+        let s = ghash(self.h, ad, ciphertext);
+        let mask = Gf128::from_bytes(&self.ctr.apply_keystream(&[0; BLOCK_SIZE], prefix, j0)?);
+        Ok((mask + s).to_bytes().to_vec())
+    }
+
+    // Split the first counter block J0 into the 12 fixed bytes and the 32-bit counter. A 96-bit nonce
+    // is used as is with the counter at 1, anything else is hashed.
+    fn counter_start(&self, nonce: &[u8]) -> Result<(Vec<u8>, u64), String> {
+        if nonce.is_empty() {
+            return Err(String::from("Nonce can't be empty"));
+        }
+        if nonce.len() == DEFAULT_NONCE_SIZE {
+            return Ok((nonce.to_vec(), 1));
+        }
+
+        let mut blocks: Vec<Gf128> = nonce.chunks(BLOCK_SIZE).map(Gf128::from_bytes).collect();
+        blocks.push(Gf128::from_bytes(&(8 * nonce.len() as u128).to_be_bytes()));
+        let j0 = ghash_blocks(self.h, &blocks).to_bytes();
+
+        let mut counter = [0; 4];
+        counter.copy_from_slice(&j0[DEFAULT_NONCE_SIZE..]);
+        Ok((j0[..DEFAULT_NONCE_SIZE].to_vec(), u64::from(u32::from_be_bytes(counter))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gcm::*;
+    use aes::{Aes};
+    use set1::{hex_decode};
+
+    // Test cases from the GCM specification, also used by NIST's CAVP
+    const KEY: &str = "feffe9928665731c6d6a8f9467308308";
+    const PLAINTEXT: &str = "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39";
+    const AD: &str = "feedfacedeadbeeffeedfacedeadbeefabaddad2";
+
+    fn gcm(key: &str) -> Gcm<Aes> {
+        Gcm::new(Aes::new(&hex_decode(key)).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn gf128_arithmetic() {
+        let a = Gf128::from_bytes(&hex_decode("66e94bd4ef8a2c3b884cfa59ca342b2e"));
+        let b = Gf128::from_bytes(&hex_decode("0388dace60b6a392f328c2b971b2fe78"));
+        assert_eq!(a * Gf128::one(), a);
+        assert_eq!(a * b, b * a);
+        assert_eq!(a * a.inverse(), Gf128::one());
+        assert_eq!(a + a, Gf128::zero());
+        assert_eq!(a.pow(3), a * a * a);
+
+        // x * x^127 = x^128 = x^7 + x^2 + x + 1
+        let x = Gf128(1 << 126);
+        assert_eq!(x * Gf128(1), Gf128(0xe1 << 120));
+    }
+
+    #[test]
+    fn gcm_ghash() {
+        // Test case 2
+        let gcm = gcm("00000000000000000000000000000000");
+        assert_eq!(gcm.hash_key().to_bytes().to_vec(), hex_decode("66e94bd4ef8a2c3b884cfa59ca342b2e"));
+        let ciphertext = hex_decode("0388dace60b6a392f328c2b971b2fe78");
+        assert_eq!(ghash(gcm.hash_key(), &[], &ciphertext).to_bytes().to_vec(), hex_decode("f38cbb1ad69223dcc3457ae5b6b0f885"));
+    }
+
+    #[test]
+    fn gcm_spec_vectors() {
+        // (key, plaintext, ad, nonce, ciphertext, tag)
+        let vectors = [
+            ("00000000000000000000000000000000", "", "", "000000000000000000000000", "", "58e2fccefa7e3061367f1d57a4e7455a"),
+            ("00000000000000000000000000000000", "00000000000000000000000000000000", "", "000000000000000000000000", "0388dace60b6a392f328c2b971b2fe78", "ab6e47d42cec13bdf53a67b21257bddf"),
+            (KEY, PLAINTEXT, AD, "cafebabefacedbaddecaf888", "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091", "5bc94fbc3221a5db94fae95ae7121a47"),
+            (KEY, PLAINTEXT, AD, "cafebabefacedbad", "61353b4c2806934a777ff51fa22a4755699b2a714fcdc6f83766e5f97b6c742373806900e49f24b22b097544d4896b424989b5e1ebac0f07c23f4598", "3612d2e79e3b0785561be14aaca2fccb"),
+            (KEY, PLAINTEXT, AD, "9313225df88406e555909c5aff5269aa6a7a9538534f7da1e4c303d2a318a728c3c0c95156809539fcf0e2429a6b525416aedbf5a0de6a57a637b39b", "8ce24998625615b603a033aca13fb894be9112a5c3a211a8ba262a3cca7e2ca701e4a9a4fba43c90ccdcb281d48c7c6fd62875d2aca417034c34aee5", "619cc5aefffe0bfa462af43c1699d050"),
+        ];
+
+        for (key, plaintext, ad, nonce, ciphertext, tag) in vectors.iter() {
+            let gcm = gcm(key);
+            let (plaintext, ad, nonce) = (hex_decode(plaintext), hex_decode(ad), hex_decode(nonce));
+            assert_eq!(gcm.encrypt(&plaintext, &ad, &nonce), Ok((hex_decode(ciphertext), hex_decode(tag))), "nonce {:?}", nonce);
+            assert_eq!(gcm.decrypt(&hex_decode(ciphertext), &ad, &nonce, &hex_decode(tag)), Ok(plaintext));
+        }
+    }
+
+    #[test]
+    fn gcm_rejects_tampering() {
+        let gcm = gcm(KEY);
+        let nonce = hex_decode("cafebabefacedbaddecaf888");
+        let (ciphertext, tag) = gcm.encrypt(&hex_decode(PLAINTEXT), &hex_decode(AD), &nonce).unwrap();
+
+        let mut flipped = ciphertext.clone();
+        flipped[0] ^= 1;
+        assert!(gcm.decrypt(&flipped, &hex_decode(AD), &nonce, &tag).is_err());
+        assert!(gcm.decrypt(&ciphertext, b"other data", &nonce, &tag).is_err());
+        assert!(gcm.decrypt(&ciphertext, &hex_decode(AD), &nonce, &tag[..12]).is_err());
+        assert!(gcm.encrypt(b"", b"", b"").is_err());
+    }
+}
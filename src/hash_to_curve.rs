@@ -0,0 +1,222 @@
+use num_bigint::{BigInt, Sign};
+use num_traits::*;
+use finite_field::{FieldElement};
+use elliptic_curve::{Point as ECPoint};
+use secp256k1::{Point, Secp256k1};
+use util::{sha256};
+
+// Hashing to secp256k1 as described in RFC 9380 using the secp256k1_XMD:SHA-256_SSWU_RO_ suite.
+//
+// Simplified SWU needs a curve where a and b are both non-zero, which isn't true for secp256k1.
+// Instead points are mapped onto an isogenous curve E' and then moved over to secp256k1 with the
+// 3-isogeny map between them.
+//
+// https://www.rfc-editor.org/rfc/rfc9380.html
+
+// SHA-256 output and block sizes in bytes
+const B_IN_BYTES: usize = 32;
+const S_IN_BYTES: usize = 64;
+
+// Bytes hashed per field element. ceil((ceil(log2(p)) + k) / 8) where k = 128 is the security level
+const L: usize = 48;
+
+// Curve E': y^2 = x^3 + A'x + B'
+const ISO_A: &str = "3f8731abdd661adca08a5558f0f5d272e953d363cb6f0e5d405447c01a444533";
+const ISO_B: i64 = 1771;
+const Z: i64 = -11;
+
+// Coefficients of the 3-isogeny map from E' to secp256k1, lowest degree first
+const X_NUM: &[&str] = &[
+    "8e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38daaaaa8c7",
+    "07d3d4c80bc321d5b9f315cea7fd44c5d595d2fc0bf63b92dfff1044f17c6581",
+    "534c328d23f234e6e2a413deca25caece4506144037c40314ecbd0b53d9dd262",
+    "8e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38e38daaaaa88c",
+];
+const X_DEN: &[&str] = &[
+    "d35771193d94918a9ca34ccbb7b640dd86cd409542f8487d9fe6b745781eb49b",
+    "edadc6f64383dc1df7c4b2d51b54225406d36b641f5e41bbc52a56612a8c6d14",
+    "01",
+];
+const Y_NUM: &[&str] = &[
+    "4bda12f684bda12f684bda12f684bda12f684bda12f684bda12f684b8e38e23c",
+    "c75e0c32d5cb7c0fa9d0a54b12a0a6d5647ab046d686da6fdffc90fc201d71a3",
+    "29a6194691f91a73715209ef6512e576722830a201be2018a765e85a9ecee931",
+    "2f684bda12f684bda12f684bda12f684bda12f684bda12f684bda12f38e38d84",
+];
+const Y_DEN: &[&str] = &[
+    "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffff93b",
+    "7a06534bb8bdb49fd5e9e6632722c2989467c1bfc8e8d978dfb425d2685c2573",
+    "6484aa716545ca2cf3a70c3fa8fe337e0a3d21162f0d6299a7bf8192bfd2a76f",
+    "01",
+];
+
+/// Expand msg into len_in_bytes pseudo-random bytes using SHA-256. The domain separation tag
+/// keeps outputs for different protocols independent of each other.
+pub fn expand_message_xmd(msg: &[u8], dst: &[u8], len_in_bytes: usize) -> Result<Vec<u8>, String> {
+    let ell = len_in_bytes.div_ceil(B_IN_BYTES);
+    if ell > 255 || len_in_bytes > 65535 {
+        return Err(format!("Cannot expand to {} bytes", len_in_bytes));
+    }
+    if dst.len() > 255 {
+        return Err(String::from("Domain separation tag must be at most 255 bytes"));
+    }
+
+    let mut dst_prime = dst.to_vec();
+    dst_prime.push(dst.len() as u8);
+
+    let mut msg_prime = vec![0u8; S_IN_BYTES];
+    msg_prime.extend_from_slice(msg);
+    msg_prime.extend_from_slice(&[(len_in_bytes >> 8) as u8, len_in_bytes as u8, 0]);
+    msg_prime.extend_from_slice(&dst_prime);
+
+    let b_0 = sha256(&msg_prime);
+    let mut b_i = vec![0u8; B_IN_BYTES];
+    let mut uniform_bytes = vec![];
+    for i in 1..=ell {
+        // b_1 = H(b_0 || 1 || DST'), b_i = H((b_0 ^ b_(i-1)) || i || DST')
+        let mut input: Vec<u8> = b_0.iter().zip(b_i.iter()).map(|(a, b)| a ^ b).collect();
+        input.push(i as u8);
+        input.extend_from_slice(&dst_prime);
+
+        b_i = sha256(&input);
+        uniform_bytes.extend_from_slice(&b_i);
+    }
+
+    uniform_bytes.truncate(len_in_bytes);
+    Ok(uniform_bytes)
+}
+
+/// Hash msg to count elements of the secp256k1 base field.
+pub fn hash_to_field(curve: &Secp256k1, msg: &[u8], dst: &[u8], count: usize) -> Result<Vec<FieldElement>, String> {
+    let uniform_bytes = expand_message_xmd(msg, dst, count * L)?;
+
+    Ok(uniform_bytes
+        .chunks(L)
+        .map(|chunk| curve.field_elem(BigInt::from_bytes_be(Sign::Plus, chunk)))
+        .collect())
+}
+
+/// Hash msg to a point on secp256k1 whose discrete log is unknown. This is the random oracle
+/// variant which hashes to two field elements and adds the resulting points.
+pub fn hash_to_curve(curve: &Secp256k1, msg: &[u8], dst: &[u8]) -> Result<Point, String> {
+    let u = hash_to_field(curve, msg, dst, 2)?;
+    let q_0 = Point::new(map_to_curve(curve, &u[0]), curve.clone());
+    let q_1 = Point::new(map_to_curve(curve, &u[1]), curve.clone());
+
+    // secp256k1 has a cofactor of 1 so there's nothing to clear
+    Ok(q_0 + q_1)
+}
+
+// Map a field element to a point on secp256k1 via E'
+fn map_to_curve(curve: &Secp256k1, u: &FieldElement) -> ECPoint {
+    let (x, y) = map_to_curve_sswu(curve, u);
+    iso_map(curve, &x, &y)
+}
+
+// Simplified Shallue-van de Woestijne-Ulas method onto E'. This is the straightforward version
+// from section 6.6.2 rather than the constant time one.
+fn map_to_curve_sswu(curve: &Secp256k1, u: &FieldElement) -> (FieldElement, FieldElement) {
+    let a = curve.field_elem(hex_to_bigint(ISO_A));
+    let b = curve.field_elem(ISO_B);
+    let z = curve.field_elem(Z);
+    let one = curve.field_elem(1);
+    let g = |x: &FieldElement| &(&(x * x) * x) + (&a * x) + &b;
+
+    // tv1 = 1 / (Z^2 * u^4 + Z * u^2), with 1 / 0 = 0
+    let z_u2 = &z * &(u * u);
+    let denominator = &(&z_u2 * &z_u2) + z_u2.clone();
+    let x_1 = if denominator.value.is_zero() {
+        b.clone() / (&z * &a)
+    } else {
+        (-b.clone() / a.clone()) * (one + denominator.inverse())
+    };
+
+    let gx_1 = g(&x_1);
+    let (x, y) = match gx_1.sqrt() {
+        Some(y) => (x_1, y),
+        None => {
+            // Exactly one of g(x1) and g(x2) is square
+            let x_2 = &z_u2 * &x_1;
+            let y = g(&x_2).sqrt().expect("g(x2) is square when g(x1) isn't");
+            (x_2, y)
+        }
+    };
+
+    // Match the sign of y to the sign of u
+    let y = if u.is_even() == y.is_even() { y } else { -y };
+    (x, y)
+}
+
+// Map (x', y') on E' to (x, y) on secp256k1 where
+//   x = x_num(x') / x_den(x')
+//   y = y' * y_num(x') / y_den(x')
+fn iso_map(curve: &Secp256k1, x: &FieldElement, y: &FieldElement) -> ECPoint {
+    let x_num = eval_poly(curve, X_NUM, x);
+    let x_den = eval_poly(curve, X_DEN, x);
+    let y_num = eval_poly(curve, Y_NUM, x);
+    let y_den = eval_poly(curve, Y_DEN, x);
+
+    ECPoint::coord(x_num / x_den, y * &(y_num / y_den))
+}
+
+// Evaluate a polynomial with hex coefficients at x using Horner's method
+fn eval_poly(curve: &Secp256k1, coefficients: &[&str], x: &FieldElement) -> FieldElement {
+    coefficients.iter().rev().fold(curve.field_elem(0), |acc, c| {
+        &(&acc * x) + curve.field_elem(hex_to_bigint(c))
+    })
+}
+
+fn hex_to_bigint(hex: &str) -> BigInt {
+    BigInt::parse_bytes(hex.as_bytes(), 16).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use hash_to_curve::*;
+    use set1::{hex_encode};
+
+    #[test]
+    fn expand_message_xmd_vectors() {
+        // RFC 9380 K.1
+        let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+
+        let output = expand_message_xmd(b"", dst, 0x20).unwrap();
+        assert_eq!(hex_encode(&output), "68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235");
+
+        let output = expand_message_xmd(b"abc", dst, 0x20).unwrap();
+        assert_eq!(hex_encode(&output), "d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615");
+
+        assert!(expand_message_xmd(b"", dst, 256 * 32).is_err());
+    }
+
+    #[test]
+    fn hash_to_curve_vectors() {
+        // RFC 9380 J.8.1
+        let curve = Secp256k1::new();
+        let dst = b"QUUX-V01-CS02-with-secp256k1_XMD:SHA-256_SSWU_RO_";
+        let vectors = vec![
+            (
+                "",
+                "c1cae290e291aee617ebaef1be6d73861479c48b841eaba9b7b5852ddfeb1346",
+                "64fa678e07ae116126f08b022a94af6de15985c996c3a91b64c406a960e51067",
+            ),
+            (
+                "abc",
+                "3377e01eab42db296b512293120c6cee72b6ecf9f9205760bd9ff11fb3cb2c4b",
+                "7f95890f33efebd1044d382a01b1bee0900fb6116f94688d487c6c7b9c8371f6",
+            ),
+            (
+                "abcdef0123456789",
+                "bac54083f293f1fe08e4a70137260aa90783a5cb84d3f35848b324d0674b0e3a",
+                "4436476085d4c3c4508b60fcf4389c40176adce756b398bdee27bca19758d828",
+            ),
+        ];
+
+        for (msg, expected_x, expected_y) in vectors {
+            let point = hash_to_curve(&curve, msg.as_bytes(), dst).unwrap();
+            let (x, y) = point.as_coord().unwrap();
+            assert_eq!(x.value, hex_to_bigint(expected_x), "x for {:?}", msg);
+            assert_eq!(y.value, hex_to_bigint(expected_y), "y for {:?}", msg);
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use std::cmp::{max, Ordering};
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+use num_bigint::{BigInt};
+use num_integer::{Integer};
+use num_traits::*;
+
+// Lattice basis reduction over exact rationals.
+//
+// A lattice is every integer combination of a set of basis vectors, here the rows of a matrix. LLL
+// turns a basis into one for the same lattice whose vectors are short and close to orthogonal, and
+// the first of them is within a factor of 2^((n-1)/2) of the shortest vector in the lattice. In
+// practice it does much better, which is what makes attacks on biased nonces, truncated LCG
+// outputs and knapsacks work.
+//
+// Everything is computed with exact rationals. That's slow compared to floating point, but there's
+// no precision to tune and the result doesn't depend on the size of the entries.
+//
+// https://en.wikipedia.org/wiki/Lenstra%E2%80%93Lenstra%E2%80%93Lov%C3%A1sz_lattice_basis_reduction_algorithm
+
+/// Exact rational number, always stored in lowest terms with a positive denominator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rational {
+    num: BigInt,
+    den: BigInt,
+}
+
+impl Rational {
+    pub fn new<T: Into<BigInt>>(num: T, den: T) -> Self {
+        let (num, den) = (num.into(), den.into());
+        assert!(!den.is_zero(), "rational with a zero denominator");
+
+        let gcd = num.gcd(&den);
+        let (num, den) = if den.is_negative() { (-num / &gcd, -den / &gcd) } else { (num / &gcd, den / &gcd) };
+        Rational { num, den }
+    }
+
+    pub fn from_int<T: Into<BigInt>>(n: T) -> Self {
+        Rational { num: n.into(), den: BigInt::one() }
+    }
+
+    pub fn zero() -> Self {
+        Rational::from_int(0)
+    }
+
+    pub fn numer(&self) -> &BigInt {
+        &self.num
+    }
+
+    pub fn denom(&self) -> &BigInt {
+        &self.den
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num.is_zero()
+    }
+
+    /// Nearest integer, with halves rounded down
+    pub fn round(&self) -> BigInt {
+        (&self.num * BigInt::from(2) + &self.den - BigInt::one()).div_floor(&(&self.den * BigInt::from(2)))
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.den.is_one() {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Rational) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    // Denominators are positive so cross multiplying keeps the order
+    fn cmp(&self, other: &Rational) -> Ordering {
+        (&self.num * &other.den).cmp(&(&other.num * &self.den))
+    }
+}
+
+impl Add<&Rational> for &Rational {
+    type Output = Rational;
+
+    fn add(self, rhs: &Rational) -> Rational {
+        Rational::new(&self.num * &rhs.den + &rhs.num * &self.den, &self.den * &rhs.den)
+    }
+}
+
+impl Sub<&Rational> for &Rational {
+    type Output = Rational;
+
+    fn sub(self, rhs: &Rational) -> Rational {
+        Rational::new(&self.num * &rhs.den - &rhs.num * &self.den, &self.den * &rhs.den)
+    }
+}
+
+impl Mul<&Rational> for &Rational {
+    type Output = Rational;
+
+    fn mul(self, rhs: &Rational) -> Rational {
+        Rational::new(&self.num * &rhs.num, &self.den * &rhs.den)
+    }
+}
+
+impl Div<&Rational> for &Rational {
+    type Output = Rational;
+
+    fn div(self, rhs: &Rational) -> Rational {
+        Rational::new(&self.num * &rhs.den, &self.den * &rhs.num)
+    }
+}
+
+impl Neg for &Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Rational {
+        Rational { num: -&self.num, den: self.den.clone() }
+    }
+}
+
+/// Gram-Schmidt orthogonalization of the rows of a basis, without normalizing.
+///
+/// b*_i = b_i - sum(mu[i][j] b*_j for j < i) where mu[i][j] = <b_i, b*_j> / <b*_j, b*_j>, and
+/// mu[i][i] = 1. norms holds the squared lengths <b*_i, b*_i>.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GramSchmidt {
+    pub ortho: Vec<Vec<Rational>>,
+    pub mu: Vec<Vec<Rational>>,
+    pub norms: Vec<Rational>,
+}
+
+/// Orthogonalize the rows of basis. Errors if the rows have different lengths or are linearly
+/// dependent.
+pub fn gram_schmidt(basis: &[Vec<BigInt>]) -> Result<GramSchmidt, String> {
+    let n = basis.len();
+    if basis.iter().any(|row| row.len() != basis[0].len()) {
+        return Err(String::from("Basis rows have different lengths"));
+    }
+
+    let mut ortho: Vec<Vec<Rational>> = vec![];
+    let mut mu = vec![vec![Rational::zero(); n]; n];
+    let mut norms = vec![];
+
+    for i in 0..n {
+        let b_i: Vec<Rational> = basis[i].iter().map(|x| Rational::from_int(x.clone())).collect();
+        let mut b_star = b_i.clone();
+        for j in 0..i {
+            mu[i][j] = &dot(&b_i, &ortho[j]) / &norms[j];
+            for (x, y) in b_star.iter_mut().zip(&ortho[j]) {
+                *x = &*x - &(&mu[i][j] * y);
+            }
+        }
+
+        let norm = dot(&b_star, &b_star);
+        if norm.is_zero() {
+            return Err(format!("Basis row {} is linearly dependent on the ones before it", i));
+        }
+
+        mu[i][i] = Rational::from_int(1);
+        norms.push(norm);
+        ortho.push(b_star);
+    }
+
+    Ok(GramSchmidt { ortho, mu, norms })
+}
+
+/// Size reduce every row of basis against the ones before it so that |mu[i][j]| <= 1/2 for j < i.
+/// The lattice and its Gram-Schmidt vectors are unchanged.
+pub fn size_reduce(basis: &[Vec<BigInt>]) -> Result<Vec<Vec<BigInt>>, String> {
+    let mut basis = basis.to_vec();
+    let mut mu = gram_schmidt(&basis)?.mu;
+    for k in 1..basis.len() {
+        size_reduce_row(&mut basis, &mut mu, k);
+    }
+
+    Ok(basis)
+}
+
+/// LLL reduce the rows of basis with the usual delta = 3/4.
+pub fn lll_reduce(basis: &[Vec<BigInt>]) -> Result<Vec<Vec<BigInt>>, String> {
+    lll_reduce_with_delta(basis, &Rational::new(3, 4))
+}
+
+/// LLL reduce the rows of basis. delta in (1/4, 1] controls the trade off between running time and
+/// how short the result is: larger values get closer to the shortest vector.
+pub fn lll_reduce_with_delta(basis: &[Vec<BigInt>], delta: &Rational) -> Result<Vec<Vec<BigInt>>, String> {
+    if delta <= &Rational::new(1, 4) || delta > &Rational::from_int(1) {
+        return Err(format!("delta must be in (1/4, 1], got {}", delta));
+    }
+
+    let mut basis = basis.to_vec();
+    let GramSchmidt { mut mu, mut norms, .. } = gram_schmidt(&basis)?;
+
+    let mut k = 1;
+    while k < basis.len() {
+        size_reduce_row(&mut basis, &mut mu, k);
+
+        // Lovász condition
+        let mu_sq = &mu[k][k - 1] * &mu[k][k - 1];
+        if norms[k] >= &(delta - &mu_sq) * &norms[k - 1] {
+            k += 1;
+            continue;
+        }
+
+        // Swap b_k and b_(k-1) and update the Gram-Schmidt data in place rather than
+        // orthogonalizing from scratch, since only these two vectors change
+        basis.swap(k, k - 1);
+        let mu_k = mu[k][k - 1].clone();
+        let norm = &norms[k] + &(&(&mu_k * &mu_k) * &norms[k - 1]);
+        mu[k][k - 1] = &(&mu_k * &norms[k - 1]) / &norm;
+        norms[k] = &(&norms[k - 1] * &norms[k]) / &norm;
+        norms[k - 1] = norm;
+
+        let (before, after) = mu.split_at_mut(k);
+        before[k - 1][..k - 1].swap_with_slice(&mut after[0][..k - 1]);
+        for i in k + 1..basis.len() {
+            let t = mu[i][k].clone();
+            mu[i][k] = &mu[i][k - 1] - &(&mu_k * &t);
+            mu[i][k - 1] = &t + &(&mu[k][k - 1] * &mu[i][k]);
+        }
+
+        k = max(k - 1, 1);
+    }
+
+    Ok(basis)
+}
+
+/// Whether the rows of basis are size reduced and satisfy the Lovász condition for delta.
+pub fn is_lll_reduced(basis: &[Vec<BigInt>], delta: &Rational) -> bool {
+    let GramSchmidt { mu, norms, .. } = match gram_schmidt(basis) {
+        Ok(gs) => gs,
+        Err(_) => return false,
+    };
+
+    let half = Rational::new(1, 2);
+    let size_reduced = (0..basis.len()).all(|i| (0..i).all(|j| mu[i][j] <= half && -&mu[i][j] <= half));
+    let lovasz = (1..basis.len()).all(|k| norms[k] >= &(delta - &(&mu[k][k - 1] * &mu[k][k - 1])) * &norms[k - 1]);
+    size_reduced && lovasz
+}
+
+// Subtract the nearest integer multiple of each earlier row from row k, updating its mu
+// coefficients to match
+fn size_reduce_row(basis: &mut [Vec<BigInt>], mu: &mut [Vec<Rational>], k: usize) {
+    for j in (0..k).rev() {
+        let q = mu[k][j].round();
+        if q.is_zero() {
+            continue;
+        }
+
+        let b_j = basis[j].clone();
+        for (x, y) in basis[k].iter_mut().zip(&b_j) {
+            *x -= &q * y;
+        }
+
+        let q = Rational::from_int(q);
+        let mu_j = mu[j].clone();
+        for (x, y) in mu[k].iter_mut().zip(&mu_j).take(j + 1) {
+            *x = &*x - &(&q * y);
+        }
+    }
+}
+
+fn dot(a: &[Rational], b: &[Rational]) -> Rational {
+    a.iter().zip(b).fold(Rational::zero(), |acc, (x, y)| &acc + &(x * y))
+}
+
+#[cfg(test)]
+mod tests {
+    use lattice::*;
+    use rand::{thread_rng, Rng};
+
+    fn matrix(rows: &[&[i64]]) -> Vec<Vec<BigInt>> {
+        rows.iter().map(|row| row.iter().map(|x| BigInt::from(*x)).collect()).collect()
+    }
+
+    #[test]
+    fn lattice_rational() {
+        let a = Rational::new(6, -8);
+        assert_eq!(a, Rational::new(-3, 4));
+        assert_eq!(a.to_string(), "-3/4");
+        assert_eq!(&a + &Rational::new(1, 4), Rational::new(-1, 2));
+        assert_eq!(&a * &Rational::from_int(4), Rational::from_int(-3));
+        assert_eq!(&a / &Rational::new(3, 2), Rational::new(-1, 2));
+        assert!(a < Rational::zero());
+
+        assert_eq!(Rational::new(5, 2).round(), BigInt::from(2));
+        assert_eq!(Rational::new(7, 3).round(), BigInt::from(2));
+        assert_eq!(Rational::new(8, 3).round(), BigInt::from(3));
+        assert_eq!(Rational::new(-8, 3).round(), BigInt::from(-3));
+    }
+
+    #[test]
+    fn lattice_gram_schmidt() {
+        let gs = gram_schmidt(&matrix(&[&[3, 1], &[2, 2]])).unwrap();
+        assert_eq!(gs.ortho[1], vec![Rational::new(-2, 5), Rational::new(6, 5)]);
+        assert_eq!(gs.mu[1][0], Rational::new(4, 5));
+        assert_eq!(gs.norms, vec![Rational::from_int(10), Rational::new(8, 5)]);
+
+        assert!(gram_schmidt(&matrix(&[&[1, 2], &[2, 4]])).is_err());
+        assert!(gram_schmidt(&matrix(&[&[1, 2], &[3]])).is_err());
+    }
+
+    #[test]
+    fn lattice_size_reduce() {
+        let basis = matrix(&[&[1, 0], &[7, 1]]);
+        assert_eq!(size_reduce(&basis), Ok(matrix(&[&[1, 0], &[0, 1]])));
+    }
+
+    #[test]
+    fn lattice_lll_reduce() {
+        let basis = matrix(&[&[1, 1, 1], &[-1, 0, 2], &[3, 5, 6]]);
+        let reduced = lll_reduce(&basis).unwrap();
+        assert_eq!(reduced, matrix(&[&[0, 1, 0], &[1, 0, 1], &[-1, 0, 2]]));
+        assert!(is_lll_reduced(&reduced, &Rational::new(3, 4)));
+        assert!(!is_lll_reduced(&basis, &Rational::new(3, 4)));
+
+        assert!(lll_reduce(&matrix(&[&[1, 2], &[2, 4]])).is_err());
+        assert!(lll_reduce_with_delta(&basis, &Rational::new(1, 4)).is_err());
+    }
+
+    #[test]
+    fn lattice_lll_random() {
+        let mut rng = thread_rng();
+        let basis: Vec<Vec<BigInt>> = (0..6).map(|_| (0..6).map(|_| BigInt::from(rng.gen_range(-1000, 1000))).collect()).collect();
+        let delta = Rational::new(99, 100);
+
+        // Random integer matrices are singular so rarely that it's not worth retrying
+        if let Ok(reduced) = lll_reduce_with_delta(&basis, &delta) {
+            assert!(is_lll_reduced(&reduced, &delta));
+
+            // Same lattice, so the absolute determinant is unchanged and the Gram-Schmidt norms
+            // multiply to the same volume
+            let volume = |b: &[Vec<BigInt>]| gram_schmidt(b).unwrap().norms.iter().fold(Rational::from_int(1), |acc, n| &acc * n);
+            assert_eq!(volume(&reduced), volume(&basis));
+        }
+    }
+
+    #[test]
+    fn lattice_knapsack() {
+        // Subset sum with a low density knapsack: find which weights add up to the target. The
+        // solution is a short vector of the lattice below, scaled so that it has to use the last row.
+        let weights: &[i64] = &[366, 385, 392, 401, 422, 437];
+        let target = 366 + 392 + 437;
+        let scale = 1000;
+
+        let n = weights.len();
+        let mut rows = vec![];
+        for (i, w) in weights.iter().enumerate() {
+            let mut row = vec![BigInt::zero(); n + 1];
+            row[i] = BigInt::from(2);
+            row[n] = BigInt::from(w * scale);
+            rows.push(row);
+        }
+        let mut last = vec![BigInt::one(); n + 1];
+        last[n] = BigInt::from(target * scale);
+        rows.push(last);
+
+        // The solution is (2x_i - 1) for x_i in {0, 1}, up to sign
+        let reduced = lll_reduce(&rows).unwrap();
+        let solution = reduced.iter().find(|row| row[n].is_zero() && row[..n].iter().all(|x| x.abs().is_one())).unwrap();
+        let subset = |sign: i64| -> Vec<usize> { (0..n).filter(|i| solution[*i] == BigInt::from(sign)).collect() };
+        let sum = |subset: &[usize]| -> i64 { subset.iter().map(|i| weights[*i]).sum() };
+
+        let chosen = if sum(&subset(1)) == target { subset(1) } else { subset(-1) };
+        assert_eq!(chosen, vec![0, 2, 5]);
+    }
+}
@@ -0,0 +1,61 @@
+//! Cryptography playground: the cryptopals challenges alongside elliptic curves, ECDSA and the
+//! Bitcoin encodings built on top of them.
+//!
+//! The most commonly used types are re-exported from the crate root so that callers don't need to
+//! know which module they live in.
+#![allow(dead_code)]
+#![allow(unused_variables)]
+
+extern crate base64;
+extern crate openssl;
+extern crate rand;
+extern crate num_bigint;
+extern crate num_traits;
+extern crate num_iter;
+extern crate num_integer;
+extern crate sha2;
+extern crate ripemd160;
+extern crate secp256k1 as cSecp256k1;
+
+pub mod set1;
+pub mod set2;
+pub mod set3;
+pub mod set5;
+pub mod set6;
+pub mod set8;
+mod ecc;
+pub mod finite_field;
+pub mod extension_field;
+pub mod elliptic_curve;
+pub mod secp256k1;
+pub mod curves;
+pub mod hash_to_curve;
+pub mod ecies;
+pub mod bn254;
+pub mod bls;
+pub mod edwards;
+pub mod ed25519;
+pub mod montgomery;
+pub mod x25519;
+pub mod discrete_log;
+pub mod point_counting;
+pub mod lattice;
+pub mod provisions;
+pub mod ecdsa;
+pub mod dsa;
+pub mod rsa;
+pub mod aes;
+pub mod block_cipher;
+pub mod modes;
+pub mod gcm;
+pub mod util;
+pub mod base58;
+pub mod bitcoin;
+
+pub use finite_field::{Field, FieldElement};
+pub use elliptic_curve::{CurveGroup, FiniteCurve, FiniteCurvy, Sec, Point as CurvePoint};
+pub use secp256k1::{Secp256k1, Point};
+pub use curves::{NamedCurve};
+pub use ecdsa::{Der, Sig, Signer, der_decode};
+pub use base58::{base58_encode, base58_decode, base58check_encode, base58check_decode};
+pub use bitcoin::{derive_address};
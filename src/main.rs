@@ -1,28 +1,22 @@
-#![allow(dead_code)]
-#![allow(unused_variables)]
-
+extern crate pals;
 extern crate base64;
-extern crate openssl;
-extern crate rand;
 extern crate num_bigint;
 extern crate num_traits;
-extern crate num_iter;
-extern crate num_integer;
-extern crate sha2;
-extern crate ripemd160;
-extern crate secp256k1 as cSecp256k1;
+extern crate rand;
+
+mod cli;
+
+use std::env;
+use std::process;
 
-mod set1;
-mod set2;
-mod set3;
-mod ecc;
-mod finite_field;
-mod elliptic_curve;
-mod secp256k1;
-mod provisions;
-mod ecdsa;
-mod util;
-mod base58;
-mod bitcoin;
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
 
-fn main() {}
+    match cli::run(&args) {
+        Ok(output) => println!("{}", output),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,345 @@
+use block_cipher::{BlockCipher};
+use set2::{pkcs_7_pad, pkcs_7_unpad};
+
+// Modes of operation over any `BlockCipher`, following NIST SP 800-38A.
+//
+// ECB and CBC work on whole blocks and PKCS#7 pad the plaintext. CTR, CFB and OFB turn the cipher
+// into a stream cipher so ciphertexts are the same length as plaintexts. CFB here is the full-block
+// variant (CFB-128 for AES).
+//
+// https://nvlpubs.nist.gov/nistpubs/Legacy/SP/nistspecialpublication800-38a.pdf
+
+/// Electronic codebook: every block encrypted independently, so equal blocks leak
+pub struct Ecb<C: BlockCipher> {
+    cipher: C,
+}
+
+impl<C: BlockCipher> Ecb<C> {
+    pub fn new(cipher: C) -> Self {
+        Ecb { cipher }
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> {
+        self.encrypt_blocks(&pkcs_7_pad(plaintext, self.cipher.block_size())).expect("padded to whole blocks")
+    }
+
+    pub fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+        pkcs_7_unpad(&self.decrypt_blocks(ciphertext)?, self.cipher.block_size()).map_err(|err| format!("Invalid padding: {:?}", err))
+    }
+
+    /// Encrypt without padding
+    pub fn encrypt_blocks(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        check_whole_blocks(data, self.cipher.block_size())?;
+        Ok(data.chunks(self.cipher.block_size()).flat_map(|block| self.cipher.encrypt_block(block)).collect())
+    }
+
+    /// Decrypt without removing padding
+    pub fn decrypt_blocks(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        check_whole_blocks(data, self.cipher.block_size())?;
+        Ok(data.chunks(self.cipher.block_size()).flat_map(|block| self.cipher.decrypt_block(block)).collect())
+    }
+}
+
+/// Cipher block chaining: each plaintext block is XORed with the previous ciphertext block, or the
+/// IV for the first, before encryption
+pub struct Cbc<C: BlockCipher> {
+    cipher: C,
+}
+
+impl<C: BlockCipher> Cbc<C> {
+    pub fn new(cipher: C) -> Self {
+        Cbc { cipher }
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8], iv: &[u8]) -> Result<Vec<u8>, String> {
+        self.encrypt_blocks(&pkcs_7_pad(plaintext, self.cipher.block_size()), iv)
+    }
+
+    pub fn decrypt(&self, ciphertext: &[u8], iv: &[u8]) -> Result<Vec<u8>, String> {
+        pkcs_7_unpad(&self.decrypt_blocks(ciphertext, iv)?, self.cipher.block_size()).map_err(|err| format!("Invalid padding: {:?}", err))
+    }
+
+    /// Encrypt without padding
+    pub fn encrypt_blocks(&self, data: &[u8], iv: &[u8]) -> Result<Vec<u8>, String> {
+        let block_size = self.cipher.block_size();
+        check_iv(iv, block_size)?;
+        check_whole_blocks(data, block_size)?;
+
+        let mut ciphertext = Vec::with_capacity(data.len());
+        let mut prev = iv.to_vec();
+        for block in data.chunks(block_size) {
+            prev = self.cipher.encrypt_block(&xor(block, &prev));
+            ciphertext.extend_from_slice(&prev);
+        }
+        Ok(ciphertext)
+    }
+
+    /// Decrypt without removing padding
+    pub fn decrypt_blocks(&self, data: &[u8], iv: &[u8]) -> Result<Vec<u8>, String> {
+        let block_size = self.cipher.block_size();
+        check_iv(iv, block_size)?;
+        check_whole_blocks(data, block_size)?;
+
+        let prevs = Some(iv).into_iter().chain(data.chunks(block_size));
+        Ok(data.chunks(block_size)
+            .zip(prevs)
+            .flat_map(|(block, prev)| xor(&self.cipher.decrypt_block(block), prev))
+            .collect())
+    }
+}
+
+/// Byte order of the counter in CTR mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Counter {
+    /// Little-endian, as in the cryptopals challenges
+    LittleEndian,
+    /// Big-endian, as in SP 800-38A and GCM
+    BigEndian,
+}
+
+/// Counter mode: the keystream is the encryption of nonce || counter for successive counters. The
+/// counter fills the rest of the block after the nonce and wraps around at its width.
+pub struct Ctr<C: BlockCipher> {
+    cipher: C,
+    counter: Counter,
+}
+
+impl<C: BlockCipher> Ctr<C> {
+    /// CTR with a little-endian counter
+    pub fn new(cipher: C) -> Self {
+        Ctr::with_counter(cipher, Counter::LittleEndian)
+    }
+
+    pub fn with_counter(cipher: C, counter: Counter) -> Self {
+        Ctr { cipher, counter }
+    }
+
+    /// Encrypt with the counter starting at 0
+    pub fn encrypt(&self, data: &[u8], nonce: &[u8]) -> Result<Vec<u8>, String> {
+        self.apply_keystream(data, nonce, 0)
+    }
+
+    pub fn decrypt(&self, data: &[u8], nonce: &[u8]) -> Result<Vec<u8>, String> {
+        self.encrypt(data, nonce)
+    }
+
+    /// XOR data with the keystream starting at counter value initial
+    pub fn apply_keystream(&self, data: &[u8], nonce: &[u8], initial: u64) -> Result<Vec<u8>, String> {
+        let block_size = self.cipher.block_size();
+        if nonce.len() >= block_size {
+            return Err(format!("Nonce must be shorter than the block: {} bytes", nonce.len()));
+        }
+
+        Ok(data.chunks(block_size).enumerate().flat_map(|(i, chunk)| {
+            let keystream = self.cipher.encrypt_block(&self.counter_block(nonce, initial.wrapping_add(i as u64)));
+            xor(chunk, &keystream)
+        }).collect())
+    }
+
+    /// nonce || counter, with the counter truncated to the bytes left in the block
+    pub fn counter_block(&self, nonce: &[u8], counter: u64) -> Vec<u8> {
+        let width = (self.cipher.block_size() - nonce.len()).min(8);
+        let mut block = nonce.to_vec();
+        block.resize(self.cipher.block_size() - width, 0);
+        match self.counter {
+            Counter::LittleEndian => block.extend_from_slice(&counter.to_le_bytes()[..width]),
+            Counter::BigEndian => block.extend_from_slice(&counter.to_be_bytes()[8 - width..]),
+        }
+        block
+    }
+}
+
+/// Cipher feedback: the keystream block is the encryption of the previous ciphertext block
+pub struct Cfb<C: BlockCipher> {
+    cipher: C,
+}
+
+impl<C: BlockCipher> Cfb<C> {
+    pub fn new(cipher: C) -> Self {
+        Cfb { cipher }
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8], iv: &[u8]) -> Result<Vec<u8>, String> {
+        check_iv(iv, self.cipher.block_size())?;
+
+        let mut ciphertext = Vec::with_capacity(plaintext.len());
+        let mut prev = iv.to_vec();
+        for block in plaintext.chunks(self.cipher.block_size()) {
+            prev = xor(block, &self.cipher.encrypt_block(&prev));
+            ciphertext.extend_from_slice(&prev);
+        }
+        Ok(ciphertext)
+    }
+
+    pub fn decrypt(&self, ciphertext: &[u8], iv: &[u8]) -> Result<Vec<u8>, String> {
+        let block_size = self.cipher.block_size();
+        check_iv(iv, block_size)?;
+
+        // Only the last block can be partial, and it's never fed back
+        let prevs = Some(iv).into_iter().chain(ciphertext.chunks(block_size));
+        Ok(ciphertext.chunks(block_size)
+            .zip(prevs)
+            .flat_map(|(block, prev)| xor(block, &self.cipher.encrypt_block(prev)))
+            .collect())
+    }
+}
+
+/// Output feedback: the keystream is the IV encrypted over and over, independent of the data
+pub struct Ofb<C: BlockCipher> {
+    cipher: C,
+}
+
+impl<C: BlockCipher> Ofb<C> {
+    pub fn new(cipher: C) -> Self {
+        Ofb { cipher }
+    }
+
+    pub fn encrypt(&self, data: &[u8], iv: &[u8]) -> Result<Vec<u8>, String> {
+        check_iv(iv, self.cipher.block_size())?;
+
+        let mut output = Vec::with_capacity(data.len());
+        let mut keystream = iv.to_vec();
+        for block in data.chunks(self.cipher.block_size()) {
+            keystream = self.cipher.encrypt_block(&keystream);
+            output.extend(xor(block, &keystream));
+        }
+        Ok(output)
+    }
+
+    pub fn decrypt(&self, data: &[u8], iv: &[u8]) -> Result<Vec<u8>, String> {
+        self.encrypt(data, iv)
+    }
+}
+
+// Truncates to the shorter of the two, so a partial last block uses only part of the keystream
+fn xor(left: &[u8], right: &[u8]) -> Vec<u8> {
+    left.iter().zip(right.iter()).map(|(l, r)| l ^ r).collect()
+}
+
+fn check_iv(iv: &[u8], block_size: usize) -> Result<(), String> {
+    if iv.len() != block_size {
+        return Err(format!("IV must be one block: {} bytes", iv.len()));
+    }
+    Ok(())
+}
+
+fn check_whole_blocks(data: &[u8], block_size: usize) -> Result<(), String> {
+    if data.len() % block_size != 0 {
+        return Err(format!("Data isn't a whole number of blocks: {} bytes", data.len()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use modes::*;
+    use aes::{Aes};
+    use block_cipher::{OpenSslAes};
+    use set1::{hex_decode};
+    use util::{rand_bytes};
+
+    // NIST SP 800-38A, appendix F, AES-128
+    const KEY: &str = "2b7e151628aed2a6abf7158809cf4f3c";
+    const IV: &str = "000102030405060708090a0b0c0d0e0f";
+    const PLAINTEXT: &str = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e5130c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+
+    // 8-byte block cipher that adds the key byte by byte. Fine for checking the modes.
+    struct Toy {
+        key: Vec<u8>,
+    }
+
+    impl BlockCipher for Toy {
+        fn block_size(&self) -> usize {
+            8
+        }
+
+        fn encrypt_block(&self, block: &[u8]) -> Vec<u8> {
+            block.iter().zip(self.key.iter()).map(|(b, k)| b.wrapping_add(*k)).collect()
+        }
+
+        fn decrypt_block(&self, block: &[u8]) -> Vec<u8> {
+            block.iter().zip(self.key.iter()).map(|(b, k)| b.wrapping_sub(*k)).collect()
+        }
+    }
+
+    fn aes() -> Aes {
+        Aes::new(&hex_decode(KEY)).unwrap()
+    }
+
+    #[test]
+    fn modes_sp_800_38a_vectors() {
+        let (pt, iv) = (hex_decode(PLAINTEXT), hex_decode(IV));
+
+        let ecb = Ecb::new(aes());
+        let ct = hex_decode("3ad77bb40d7a3660a89ecaf32466ef97f5d3d58503b9699de785895a96fdbaaf43b1cd7f598ece23881b00e3ed0306887b0c785e27e8ad3f8223207104725dd4");
+        assert_eq!(ecb.encrypt_blocks(&pt), Ok(ct.clone()));
+        assert_eq!(ecb.decrypt_blocks(&ct), Ok(pt.clone()));
+
+        let cbc = Cbc::new(aes());
+        let ct = hex_decode("7649abac8119b246cee98e9b12e9197d5086cb9b507219ee95db113a917678b273bed6b8e3c1743b7116e69e222295163ff1caa1681fac09120eca307586e1a7");
+        assert_eq!(cbc.encrypt_blocks(&pt, &iv), Ok(ct.clone()));
+        assert_eq!(cbc.decrypt_blocks(&ct, &iv), Ok(pt.clone()));
+
+        let cfb = Cfb::new(aes());
+        let ct = hex_decode("3b3fd92eb72dad20333449f8e83cfb4ac8a64537a0b3a93fcde3cdad9f1ce58b26751f67a3cbb140b1808cf187a4f4dfc04b05357c5d1c0eeac4c66f9ff7f2e6");
+        assert_eq!(cfb.encrypt(&pt, &iv), Ok(ct.clone()));
+        assert_eq!(cfb.decrypt(&ct, &iv), Ok(pt.clone()));
+
+        let ofb = Ofb::new(aes());
+        let ct = hex_decode("3b3fd92eb72dad20333449f8e83cfb4a7789508d16918f03f53c52dac54ed8259740051e9c5fecf64344f7a82260edcc304c6528f659c77866a510d9c1d6ae5e");
+        assert_eq!(ofb.encrypt(&pt, &iv), Ok(ct.clone()));
+        assert_eq!(ofb.decrypt(&ct, &iv), Ok(pt.clone()));
+
+        // Initial counter block f0f1...feff
+        let ctr = Ctr::with_counter(aes(), Counter::BigEndian);
+        let ct = hex_decode("874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff5ae4df3edbd5d35e5b4f09020db03eab1e031dda2fbe03d1792170a0f3009cee");
+        let nonce = hex_decode("f0f1f2f3f4f5f6f7");
+        assert_eq!(ctr.apply_keystream(&pt, &nonce, 0xf8f9fafbfcfdfeff), Ok(ct.clone()));
+        assert_eq!(ctr.apply_keystream(&ct, &nonce, 0xf8f9fafbfcfdfeff), Ok(pt));
+    }
+
+    #[test]
+    fn modes_padding_and_partial_blocks() {
+        let cipher = OpenSslAes::new(&hex_decode(KEY)).unwrap();
+        let iv = rand_bytes(16);
+        let cbc = Cbc::new(cipher.clone());
+        for len in &[0, 1, 15, 16, 17, 33] {
+            let pt = rand_bytes(*len);
+            let ct = cbc.encrypt(&pt, &iv).unwrap();
+            assert_eq!(ct.len(), (len / 16 + 1) * 16);
+            assert_eq!(cbc.decrypt(&ct, &iv), Ok(pt.clone()));
+
+            let ecb = Ecb::new(cipher.clone());
+            assert_eq!(ecb.decrypt(&ecb.encrypt(&pt)), Ok(pt.clone()));
+
+            for ct in &[Cfb::new(cipher.clone()).encrypt(&pt, &iv).unwrap(), Ofb::new(cipher.clone()).encrypt(&pt, &iv).unwrap()] {
+                assert_eq!(ct.len(), *len);
+            }
+            assert_eq!(Cfb::new(cipher.clone()).decrypt(&Cfb::new(cipher.clone()).encrypt(&pt, &iv).unwrap(), &iv), Ok(pt.clone()));
+        }
+
+        assert!(cbc.encrypt(b"hello", &iv[..8]).is_err());
+        assert!(cbc.decrypt(&[0; 17], &iv).is_err());
+        assert!(cbc.decrypt(&cbc.encrypt_blocks(b"ICE ICE BABY\x05\x05\x05\x05", &iv).unwrap(), &iv).is_err());
+    }
+
+    #[test]
+    fn modes_toy_cipher() {
+        let toy = || Toy { key: b"toy key!".to_vec() };
+        let (pt, iv) = (b"Attack at dawn, retreat at dusk".to_vec(), b"01234567".to_vec());
+
+        let cbc = Cbc::new(toy());
+        let ct = cbc.encrypt(&pt, &iv).unwrap();
+        assert_eq!(ct.len(), 32);
+        assert_eq!(cbc.decrypt(&ct, &iv), Ok(pt.clone()));
+
+        let ctr = Ctr::new(toy());
+        assert_eq!(ctr.counter_block(b"abc", 0x0102), b"abc\x02\x01\x00\x00\x00".to_vec());
+        assert_eq!(ctr.decrypt(&ctr.encrypt(&pt, b"abc").unwrap(), b"abc"), Ok(pt.clone()));
+        assert!(ctr.encrypt(&pt, &iv).is_err());
+
+        let ecb = Ecb::new(toy());
+        let ct = ecb.encrypt(b"YELLOW SYELLOW S");
+        assert_eq!(ct[..8], ct[8..16]);
+    }
+}
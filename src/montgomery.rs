@@ -0,0 +1,122 @@
+use num_bigint::{BigInt};
+use num_traits::*;
+use finite_field::{Field, FieldElement};
+
+/// Montgomery curve By^2 = x^3 + Ax^2 + x over F_p.
+///
+/// Only x coordinates (called u in RFC 7748) are used. Multiplication with the Montgomery ladder
+/// needs nothing else, and both P and -P share the same u so it's all Diffie-Hellman needs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MontgomeryCurve {
+    field: Field,
+    a: FieldElement,
+    b: FieldElement,
+}
+
+impl MontgomeryCurve {
+    pub fn new<T: Into<BigInt>>(a: T, b: T, p: T) -> Self {
+        let field = Field::new(p);
+        MontgomeryCurve { a: field.elem(a), b: field.elem(b), field }
+    }
+
+    /// Curve25519: y^2 = x^3 + 486662x^2 + x over p = 2^255 - 19
+    pub fn curve25519() -> Self {
+        let p = (BigInt::one() << 255) - BigInt::from(19);
+        MontgomeryCurve::new(BigInt::from(486662), BigInt::one(), p)
+    }
+
+    pub fn field_ref(&self) -> &Field {
+        &self.field
+    }
+
+    pub fn field_elem<T: Into<BigInt>>(&self, n: T) -> FieldElement {
+        self.field.elem(n)
+    }
+
+    /// Whether u is the x coordinate of a point on the curve rather than on its quadratic twist
+    pub fn is_on_curve(&self, u: &FieldElement) -> bool {
+        // By^2 = u^3 + Au^2 + u has a solution when the right hand side over B is square
+        let u2 = u * u;
+        let rhs = &(&u2 * u) + (&self.a * &u2) + u.clone();
+        (rhs / self.b.clone()).is_square()
+    }
+
+    /// Compute the u coordinate of kP from the u coordinate of P using the Montgomery ladder over
+    /// the lowest bits of k, following RFC 7748 section 5.
+    ///
+    /// Every bit runs the same field operations and the conditional swaps are done with arithmetic
+    /// instead of branches. The underlying BigInt arithmetic isn't constant time though, so this
+    /// only shows the structure of a constant time implementation.
+    pub fn ladder(&self, k: &BigInt, u: &FieldElement, bits: usize) -> FieldElement {
+        let a24 = (&self.a - &self.field_elem(2)) / self.field_elem(4);
+        let x_1 = u.clone();
+        let (mut x_2, mut z_2) = (self.field_elem(1), self.field_elem(0));
+        let (mut x_3, mut z_3) = (u.clone(), self.field_elem(1));
+        let mut swap = BigInt::zero();
+
+        for t in (0..bits).rev() {
+            let k_t = (k >> t) & BigInt::one();
+            swap ^= &k_t;
+            self.cswap(&swap, &mut x_2, &mut x_3);
+            self.cswap(&swap, &mut z_2, &mut z_3);
+            swap = k_t;
+
+            let a = &x_2 + z_2.clone();
+            let aa = &a * &a;
+            let b = &x_2 - &z_2;
+            let bb = &b * &b;
+            let e = &aa - &bb;
+            let c = &x_3 + z_3.clone();
+            let d = &x_3 - &z_3;
+            let da = &d * &a;
+            let cb = &c * &b;
+
+            let sum = &da + cb.clone();
+            let diff = &da - &cb;
+            x_3 = &sum * &sum;
+            z_3 = &x_1 * &(&diff * &diff);
+            x_2 = &aa * &bb;
+            z_2 = &e * &(aa + &a24 * &e);
+        }
+
+        self.cswap(&swap, &mut x_2, &mut x_3);
+        self.cswap(&swap, &mut z_2, &mut z_3);
+
+        // z_2 is 0 for the point at infinity, which maps to 0 as z_2^(p - 2) does
+        &x_2 * &z_2.pow(&(self.field.p_ref() - BigInt::from(2)))
+    }
+
+    // Swap x and y when swap is 1 and leave them alone when it's 0, without branching on it
+    fn cswap(&self, swap: &BigInt, x: &mut FieldElement, y: &mut FieldElement) {
+        let dummy = &self.field_elem(swap.clone()) * &(&*x - &*y);
+        *x = &*x - &dummy;
+        *y = &*y + dummy;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use montgomery::*;
+
+    #[test]
+    fn montgomery_ladder() {
+        // u = 9 is the Curve25519 base point and its order L is prime
+        let curve = MontgomeryCurve::curve25519();
+        let g = curve.field_elem(9);
+        let l = (BigInt::one() << 252) + BigInt::parse_bytes(b"27742317777372353535851937790883648493", 10).unwrap();
+
+        assert!(curve.is_on_curve(&g));
+        assert_eq!(curve.ladder(&BigInt::one(), &g, 255), g);
+        assert_eq!(curve.ladder(&l, &g, 255).value, BigInt::zero(), "L * G is the point at infinity");
+        assert_eq!(curve.ladder(&(&l + 1), &g, 255), g);
+
+        // Scalars commute: a(bG) = b(aG)
+        let (a, b) = (BigInt::from(0xdeadbeefu32), BigInt::from(31337));
+        let a_g = curve.ladder(&a, &g, 255);
+        let b_g = curve.ladder(&b, &g, 255);
+        assert_eq!(curve.ladder(&a, &b_g, 255), curve.ladder(&b, &a_g, 255));
+
+        // u = 2 is on the twist rather than the curve for Curve25519
+        assert!(!curve.is_on_curve(&curve.field_elem(2)));
+    }
+}
@@ -0,0 +1,488 @@
+use std::slice;
+use num_bigint::{BigInt, RandBigInt};
+use num_integer::{Integer};
+use num_traits::*;
+use rand::{thread_rng};
+use finite_field::{Field, jacobi};
+use elliptic_curve::{FiniteCurve, FiniteCurvy, Point};
+use extension_field::{poly_divmod, poly_mul, poly_sub, poly_trim};
+use discrete_log::{crt};
+use rsa::{is_probable_prime};
+
+// Counting the points on y^2 = x^3 + ax + b over F_p, and using the count to find a generator of a
+// large prime order subgroup.
+//
+// Counting naively takes O(p) work so it only works for small p. Schoof's algorithm finds the
+// trace of Frobenius t mod small primes l by working with the l-torsion points symbolically and
+// recombines them with the CRT. Hasse's theorem says |t| <= 2 sqrt(p) so once the product of the
+// primes passes 4 sqrt(p) the order #E = p + 1 - t is known.
+//
+// https://en.wikipedia.org/wiki/Schoof%27s_algorithm
+
+// Fields up to this size are counted naively
+const NAIVE_LIMIT: u32 = 1 << 16;
+
+// Factors below this are found by trial division. Whatever is left over has to be prime, so any
+// n up to the bound squared can be factored, and larger n only when they have a single factor
+// above the bound. Group orders of curves picked for crypto are like that.
+const TRIAL_DIVISION_BOUND: u32 = 1 << 20;
+
+// Miller-Rabin rounds for checking that a subgroup order is prime
+const MILLER_RABIN_ROUNDS: usize = 40;
+
+/// Parameters of a prime order subgroup: #E = cofactor * n with generator g of order n.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubgroupParams {
+    pub order: BigInt,
+    pub n: BigInt,
+    pub cofactor: BigInt,
+    pub g: Point,
+}
+
+/// Count the points on the curve, including infinity, by checking whether x^3 + ax + b is a
+/// square for every x. Each non-zero square gives two points and zero gives one.
+pub fn count_points_naive(curve: &FiniteCurve) -> BigInt {
+    let p = curve.field_ref().p_ref();
+    let mut count = BigInt::one();
+    let mut x = BigInt::zero();
+    while &x < p {
+        let rhs = curve_rhs(curve, &curve.field_elem(x.clone()).value);
+        count += BigInt::from(1 + jacobi(&rhs, p));
+        x += 1;
+    }
+
+    count
+}
+
+/// Count the points on the curve using Schoof's algorithm. p must be a prime greater than 3.
+pub fn schoof(curve: &FiniteCurve) -> BigInt {
+    let p = curve.field_ref().p_ref().clone();
+    assert!(p > BigInt::from(3), "Schoof's algorithm needs p > 3");
+
+    let ring = TorsionRing::new(curve);
+    let bound = BigInt::from(4) * (p.sqrt() + BigInt::one());
+
+    let mut residues = vec![(ring.trace_mod_2(), BigInt::from(2))];
+    let mut modulus = BigInt::from(2);
+    let mut l = 3u32;
+    while modulus <= bound {
+        if is_small_prime(l) && BigInt::from(l) != p {
+            residues.push((BigInt::from(ring.trace_mod_l(l)), BigInt::from(l)));
+            modulus *= l;
+        }
+        l += 2;
+    }
+
+    // Pick the representative of t closest to 0, which is the only one within the Hasse bound
+    let (t, modulus) = crt(&residues).expect("moduli are distinct primes");
+    let t = if &t * 2 > modulus { t - modulus } else { t };
+    p + 1 - t
+}
+
+/// Number of points on the curve, counted naively for small fields and with Schoof otherwise.
+pub fn group_order(curve: &FiniteCurve) -> BigInt {
+    if curve.field_ref().p_ref() < &BigInt::from(NAIVE_LIMIT) {
+        count_points_naive(curve)
+    } else {
+        schoof(curve)
+    }
+}
+
+/// Factor n into (prime, exponent) pairs by trial division up to 2^20. Errors if what's left after
+/// that isn't prime, which can only happen when n is above 2^40.
+pub fn factorize(n: &BigInt) -> Result<Vec<(BigInt, u32)>, String> {
+    let mut factors = vec![];
+    let mut n = n.clone();
+    let mut d = BigInt::from(2);
+    let bound = BigInt::from(TRIAL_DIVISION_BOUND);
+    while &d * &d <= n && d < bound {
+        let mut e = 0;
+        while (&n % &d).is_zero() {
+            n /= &d;
+            e += 1;
+        }
+        if e > 0 {
+            factors.push((d.clone(), e));
+        }
+        d += if d == BigInt::from(2) { BigInt::one() } else { BigInt::from(2) };
+    }
+    if n > BigInt::one() {
+        if !is_probable_prime(&n, MILLER_RABIN_ROUNDS) {
+            return Err(format!("{} has no factors below {} and isn't prime", n, TRIAL_DIVISION_BOUND));
+        }
+        factors.push((n, 1));
+    }
+
+    Ok(factors)
+}
+
+/// Find a generator for the subgroup whose order is the largest prime factor of the group order.
+/// Random points are multiplied by the cofactor until one doesn't land on infinity. Errors if the
+/// group order can't be factored, see `factorize`.
+pub fn find_subgroup(curve: &FiniteCurve) -> Result<SubgroupParams, String> {
+    let order = group_order(curve);
+    let n = factorize(&order)?.pop().map(|(q, _)| q).ok_or_else(|| String::from("Curve has a single point"))?;
+    let cofactor = &order / &n;
+
+    let p = curve.field_ref().p_ref();
+    let mut rng = thread_rng();
+    for _ in 0..100 {
+        let x = rng.gen_bigint_range(&BigInt::zero(), p);
+        let y = match curve.solve_y(&x, true) {
+            Ok(y) => y,
+            Err(_) => continue,
+        };
+
+        let g = Point::coord(curve.field_elem(x), y).mul(&cofactor, curve);
+        if !g.is_infinity() {
+            return Ok(SubgroupParams { order, n, cofactor, g });
+        }
+    }
+
+    Err(String::from("Failed to find a generator"))
+}
+
+/// Check that g generates a subgroup of prime order n with n * cofactor points on the curve.
+pub fn validate_subgroup(curve: &FiniteCurve, params: &SubgroupParams) -> Result<(), String> {
+    let p = curve.field_ref().p_ref();
+    let t = p + 1 - &params.order;
+    if &t * &t > p * BigInt::from(4) {
+        return Err(format!("Order {} is outside the Hasse bound", params.order));
+    }
+    if &params.n * &params.cofactor != params.order {
+        return Err(String::from("Order isn't n * cofactor"));
+    }
+    if !is_probable_prime(&params.n, MILLER_RABIN_ROUNDS) {
+        return Err(format!("Subgroup order {} isn't prime", params.n));
+    }
+    if params.g.is_infinity() || !curve.is_valid_point(&params.g) {
+        return Err(String::from("Generator isn't a point on the curve"));
+    }
+    if !params.g.mul(&params.n, curve).is_infinity() {
+        return Err(String::from("Generator doesn't have order n"));
+    }
+
+    Ok(())
+}
+
+// x^3 + ax + b mod p
+fn curve_rhs(curve: &FiniteCurve, x: &BigInt) -> BigInt {
+    let x = curve.field_elem(x.clone());
+    (&(&x * &x) * &x + (curve.a_ref() * &x.value) + curve.b_ref()).value
+}
+
+fn is_small_prime(n: u32) -> bool {
+    n >= 2 && (2..).take_while(|d| d * d <= n).all(|d| n % d != 0)
+}
+
+// Polynomials over F_p, lowest degree first with no trailing zeros, as in extension_field.
+type Poly = Vec<BigInt>;
+
+// A point (X(x), Y(x) y) with coordinates in F_p[x] / h(x), where h divides a division
+// polynomial. y only ever appears to the first power since y^2 = x^3 + ax + b.
+#[derive(Debug, Clone, PartialEq)]
+enum TorsionPoint {
+    Infinity,
+    Coordinate { x: Poly, y: Poly },
+}
+
+struct TorsionRing {
+    field: Field,
+    a: BigInt,
+    b: BigInt,
+    // x^3 + ax + b
+    rhs: Poly,
+}
+
+impl TorsionRing {
+    fn new(curve: &FiniteCurve) -> Self {
+        let a = curve.a_ref().value.clone();
+        let b = curve.b_ref().value.clone();
+        let rhs = poly_trim(vec![b.clone(), a.clone(), BigInt::zero(), BigInt::one()]);
+        TorsionRing { field: curve.field_ref().clone(), a, b, rhs }
+    }
+
+    fn p_ref(&self) -> &BigInt {
+        self.field.p_ref()
+    }
+
+    // t is even exactly when there's a point of order 2, which is when x^3 + ax + b has a root.
+    // The roots are shared with x^p - x.
+    fn trace_mod_2(&self) -> BigInt {
+        let x_p = self.pow_mod(&[BigInt::zero(), BigInt::one()], self.p_ref(), &self.rhs);
+        let x_p_minus_x = poly_sub(&x_p, &[BigInt::zero(), BigInt::one()], self.p_ref());
+        if self.gcd(&x_p_minus_x, &self.rhs).len() > 1 {
+            BigInt::zero()
+        } else {
+            BigInt::one()
+        }
+    }
+
+    // Find t mod l using pi^2(P) + [p]P = [t]pi(P) for the l-torsion points P. If a non-invertible
+    // element turns up we've found a factor of the division polynomial, and since its roots are
+    // still x coordinates of l-torsion points we carry on with the factor instead.
+    fn trace_mod_l(&self, l: u32) -> u32 {
+        let mut h = self.division_polynomial(l as usize);
+        loop {
+            match self.try_trace_mod_l(l, &h) {
+                Ok(t) => return t,
+                Err(factor) => h = factor,
+            }
+        }
+    }
+
+    fn try_trace_mod_l(&self, l: u32, h: &[BigInt]) -> Result<u32, Poly> {
+        let p = self.p_ref();
+        let x = vec![BigInt::zero(), BigInt::one()];
+        let p_2 = p * p;
+
+        // pi(P) = (x^p, y^p) where y^p = (y^2)^((p - 1) / 2) y
+        let pi = TorsionPoint::Coordinate {
+            x: self.pow_mod(&x, p, h),
+            y: self.pow_mod(&self.rhs, &((p - 1u32) / 2u32), h),
+        };
+        let pi_2 = TorsionPoint::Coordinate {
+            x: self.pow_mod(&x, &p_2, h),
+            y: self.pow_mod(&self.rhs, &((&p_2 - 1u32) / 2u32), h),
+        };
+
+        let generic = TorsionPoint::Coordinate { x: poly_divmod(&x, h, &self.field).1, y: vec![BigInt::one()] };
+        let q_bar = (p % l).to_u32().unwrap();
+        let target = self.add(&pi_2, &self.mul(&generic, q_bar, h)?, h)?;
+
+        let mut multiple = TorsionPoint::Infinity;
+        for t in 0..l {
+            if multiple == target {
+                return Ok(t);
+            }
+            multiple = self.add(&multiple, &pi, h)?;
+        }
+
+        panic!("no t mod {} satisfies the characteristic equation", l);
+    }
+
+    // Division polynomial for odd n, using f_n where psi_n = f_n for odd n and psi_n = y f_n for
+    // even n so everything stays in F_p[x].
+    fn division_polynomial(&self, n: usize) -> Poly {
+        let p = self.p_ref();
+        let (a, b) = (&self.a, &self.b);
+        let poly = |coeffs: Vec<BigInt>| poly_trim(coeffs.iter().map(|c| c.mod_floor(p)).collect());
+
+        let mut f: Vec<Poly> = vec![
+            vec![],
+            vec![BigInt::one()],
+            poly(vec![BigInt::from(2)]),
+            poly(vec![-(a * a), b * BigInt::from(12), a * BigInt::from(6), BigInt::zero(), BigInt::from(3)]),
+            poly(vec![
+                (b * b * BigInt::from(-8) - a * a * a) * BigInt::from(4),
+                a * b * BigInt::from(-16),
+                a * a * BigInt::from(-20),
+                b * BigInt::from(80),
+                a * BigInt::from(20),
+                BigInt::zero(),
+                BigInt::from(4),
+            ]),
+        ];
+        let rhs_2 = poly_mul(&self.rhs, &self.rhs, p);
+        let half = self.field.elem(2).inverse().value;
+
+        for i in 5..=n {
+            let m = i / 2;
+            let cube = |g: &Poly| poly_mul(&poly_mul(g, g, p), g, p);
+            let square = |g: &Poly| poly_mul(g, g, p);
+
+            let next = if i % 2 == 1 {
+                let left = poly_mul(&f[m + 2], &cube(&f[m]), p);
+                let right = poly_mul(&f[m - 1], &cube(&f[m + 1]), p);
+                if m % 2 == 0 {
+                    poly_sub(&poly_mul(&rhs_2, &left, p), &right, p)
+                } else {
+                    poly_sub(&left, &poly_mul(&rhs_2, &right, p), p)
+                }
+            } else {
+                let left = poly_mul(&f[m + 2], &square(&f[m - 1]), p);
+                let right = poly_mul(&f[m - 2], &square(&f[m + 1]), p);
+                let product = poly_mul(&f[m], &poly_sub(&left, &right, p), p);
+                poly_mul(&product, slice::from_ref(&half), p)
+            };
+            f.push(next);
+        }
+
+        f.swap_remove(n)
+    }
+
+    fn add(&self, left: &TorsionPoint, right: &TorsionPoint, h: &[BigInt]) -> Result<TorsionPoint, Poly> {
+        let p = self.p_ref();
+        let (x_1, y_1, x_2, y_2) = match (left, right) {
+            (TorsionPoint::Infinity, _) => return Ok(right.clone()),
+            (_, TorsionPoint::Infinity) => return Ok(left.clone()),
+            (TorsionPoint::Coordinate { x: x_1, y: y_1 }, TorsionPoint::Coordinate { x: x_2, y: y_2 }) => (x_1, y_1, x_2, y_2),
+        };
+
+        // The slope is m y where m is in F_p[x] / h
+        let m = if x_1 != x_2 {
+            let dx = poly_sub(x_2, x_1, p);
+            self.mul_mod(&poly_sub(y_2, y_1, p), &self.inverse_mod(&dx, h)?, h)
+        } else if y_1 == y_2 {
+            // (3x^2 + a) / 2y = (3x^2 + a) y / 2(x^3 + ax + b)
+            if y_1.is_empty() {
+                return Ok(TorsionPoint::Infinity);
+            }
+            let numerator = poly_trim(vec![self.a.clone(), BigInt::zero(), BigInt::from(3)]);
+            let numerator = self.compose_mod(&numerator, x_1, h);
+            let denominator = self.mul_mod(&poly_mul(y_1, &[BigInt::from(2)], p), &self.rhs, h);
+            self.mul_mod(&numerator, &self.inverse_mod(&denominator, h)?, h)
+        } else if poly_sub(&[], y_1, p) == *y_2 {
+            return Ok(TorsionPoint::Infinity);
+        } else {
+            // Same x but y isn't +-y so y_1 - y_2 shares a factor with h
+            return Err(self.gcd(&poly_sub(y_1, y_2, p), h));
+        };
+
+        // x_3 = m^2 y^2 - x_1 - x_2, y_3 = m (x_1 - x_3) - y_1
+        let m_2 = self.mul_mod(&m, &m, h);
+        let x_3 = poly_sub(&poly_sub(&self.mul_mod(&m_2, &self.rhs, h), x_1, p), x_2, p);
+        let y_3 = poly_sub(&self.mul_mod(&m, &poly_sub(x_1, &x_3, p), h), y_1, p);
+        Ok(TorsionPoint::Coordinate { x: x_3, y: y_3 })
+    }
+
+    fn mul(&self, point: &TorsionPoint, n: u32, h: &[BigInt]) -> Result<TorsionPoint, Poly> {
+        let mut result = TorsionPoint::Infinity;
+        let mut current = point.clone();
+        let mut n = n;
+        while n > 0 {
+            if n & 1 == 1 {
+                result = self.add(&result, &current, h)?;
+            }
+            current = self.add(&current, &current, h)?;
+            n >>= 1;
+        }
+
+        Ok(result)
+    }
+
+    fn mul_mod(&self, a: &[BigInt], b: &[BigInt], h: &[BigInt]) -> Poly {
+        poly_divmod(&poly_mul(a, b, self.p_ref()), h, &self.field).1
+    }
+
+    fn pow_mod(&self, a: &[BigInt], e: &BigInt, h: &[BigInt]) -> Poly {
+        let base = poly_divmod(a, h, &self.field).1;
+        let mut result = vec![BigInt::one()];
+        for bit in e.to_str_radix(2).chars() {
+            result = self.mul_mod(&result, &result, h);
+            if bit == '1' {
+                result = self.mul_mod(&result, &base, h);
+            }
+        }
+
+        result
+    }
+
+    // g(x) evaluated at x = a(x), mod h
+    fn compose_mod(&self, g: &[BigInt], a: &[BigInt], h: &[BigInt]) -> Poly {
+        g.iter().rev().fold(vec![], |acc, c| {
+            let product = self.mul_mod(&acc, a, h);
+            poly_sub(&product, &poly_sub(&[], slice::from_ref(c), self.p_ref()), self.p_ref())
+        })
+    }
+
+    // Monic gcd
+    fn gcd(&self, a: &[BigInt], b: &[BigInt]) -> Poly {
+        let (mut a, mut b) = (poly_trim(a.to_vec()), poly_trim(b.to_vec()));
+        while !b.is_empty() {
+            let remainder = poly_divmod(&a, &b, &self.field).1;
+            a = b;
+            b = remainder;
+        }
+
+        match a.last().cloned() {
+            Some(lead) => poly_mul(&a, &[self.field.elem(lead).inverse().value], self.p_ref()),
+            None => a,
+        }
+    }
+
+    // Inverse of a mod h, or the non-trivial factor of h that a shares with it
+    fn inverse_mod(&self, a: &[BigInt], h: &[BigInt]) -> Result<Poly, Poly> {
+        let p = self.p_ref();
+        let (mut old_r, mut r) = (h.to_vec(), poly_divmod(a, h, &self.field).1);
+        let (mut old_s, mut s) = (vec![], vec![BigInt::one()]);
+        while !r.is_empty() {
+            let (quotient, remainder) = poly_divmod(&old_r, &r, &self.field);
+            old_r = r;
+            r = remainder;
+
+            let next_s = poly_sub(&old_s, &poly_mul(&quotient, &s, p), p);
+            old_s = s;
+            s = next_s;
+        }
+
+        if old_r.len() != 1 {
+            return Err(self.gcd(&old_r, h));
+        }
+        let scale = self.field.elem(old_r[0].clone()).inverse().value;
+        Ok(poly_divmod(&poly_mul(&old_s, &[scale], p), h, &self.field).1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use point_counting::*;
+
+    #[test]
+    fn point_counting_naive() {
+        assert_eq!(count_points_naive(&FiniteCurve::new(2, 3, 97)), BigInt::from(100));
+        assert_eq!(count_points_naive(&FiniteCurve::new(1, 7, 10007)), BigInt::from(9936));
+        assert_eq!(count_points_naive(&FiniteCurve::new(2, 5, 100003)), BigInt::from(99907));
+    }
+
+    #[test]
+    fn point_counting_schoof() {
+        for (a, b) in &[(1, 7), (2, 3), (-3, 10), (0, 5), (4, 0)] {
+            let curve = FiniteCurve::new(*a, *b, 10007);
+            assert_eq!(schoof(&curve), count_points_naive(&curve), "y^2 = x^3 + {}x + {}", a, b);
+        }
+
+        // Checked with a naive count
+        assert_eq!(schoof(&FiniteCurve::new(2, 3, 1000003)), BigInt::from(999708));
+        assert_eq!(schoof(&FiniteCurve::new(-3, 7, 1000003)), BigInt::from(999122));
+    }
+
+    #[test]
+    fn point_counting_factorize() {
+        assert_eq!(factorize(&BigInt::from(9936)), Ok(vec![(BigInt::from(2), 4), (BigInt::from(3), 3), (BigInt::from(23), 1)]));
+        assert_eq!(factorize(&BigInt::from(99907)), Ok(vec![(BigInt::from(99907), 1)]));
+        assert_eq!(factorize(&BigInt::from(1)), Ok(vec![]));
+
+        // One factor above the trial division bound is fine, two aren't
+        let m127 = (BigInt::one() << 127) - BigInt::one();
+        assert_eq!(factorize(&(&m127 * 12)), Ok(vec![(BigInt::from(2), 2), (BigInt::from(3), 1), (m127, 1)]));
+        assert!(factorize(&(BigInt::from(1048583) * BigInt::from(1048589))).is_err());
+    }
+
+    #[test]
+    fn point_counting_find_subgroup() {
+        // 999122 = 2 * 607 * 823
+        let curve = FiniteCurve::new(-3, 7, 1000003);
+        let params = find_subgroup(&curve).unwrap();
+        assert_eq!(params.order, BigInt::from(999122));
+        assert_eq!(params.n, BigInt::from(823));
+        assert_eq!(params.cofactor, BigInt::from(1214));
+        assert_eq!(validate_subgroup(&curve, &params), Ok(()));
+
+        let mut bad = params.clone();
+        bad.g = params.g.mul(&3, &curve);
+        bad.n = BigInt::from(607);
+        bad.cofactor = BigInt::from(1646);
+        assert!(validate_subgroup(&curve, &bad).is_err(), "g doesn't have order 607");
+
+        let mut bad = params.clone();
+        bad.g = params.g.mul(&607, &curve);
+        bad.n = BigInt::from(2 * 607);
+        bad.cofactor = BigInt::from(823);
+        assert_eq!(validate_subgroup(&curve, &bad), Err(String::from("Subgroup order 1214 isn't prime")));
+
+        let mut bad = params.clone();
+        bad.order = BigInt::from(1000004 + 2 * 1000);
+        assert!(validate_subgroup(&curve, &bad).is_err(), "order outside the Hasse bound");
+    }
+}
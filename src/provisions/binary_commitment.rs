@@ -4,14 +4,14 @@ use num_traits::*;
 use secp256k1::{Secp256k1, Point};
 
 // Commitment to x given: (g, h, l = g^x*h^y).
-struct PedersenCommitment {
+pub struct PedersenCommitment {
     g: Point,
     h: Point,
     l: Point,
 }
 
 impl PedersenCommitment {
-    fn create_commitment<T: Into<BigInt> + Clone>(
+    pub fn create_commitment<T: Into<BigInt> + Clone>(
         g: &Point, h: &Point, curve: &Secp256k1,
         x: &T, y: &T
     ) -> PedersenCommitment {
@@ -36,7 +36,7 @@ impl PedersenCommitment {
     // 4) Verifier accepts if:
     //     h^r0 = a0(l)^(c-c1)
     //     h^r1 = a1(lg^-1)^c1
-    fn verify_binary_commitment(
+    pub fn verify_binary_commitment(
         comm: &PedersenCommitment, curve: &Secp256k1,
         x: &BigInt, y: &BigInt,
     ) -> bool {
@@ -69,7 +69,6 @@ impl PedersenCommitment {
         let p1 = comm.h_ref() * r0 == a0 + (comm.l_ref() * (c - &c1));
         let p2 = comm.h_ref() * r1 == a1 + (comm.l_ref() + &comm.g_ref().inverse()) * c1.clone();
 
-        println!("p1: {}, p2: {}", p1, p2);
         p1 && p2
     }
 
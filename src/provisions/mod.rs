@@ -1,2 +1,2 @@
-mod proof_of_assets;
-mod binary_commitment;
+pub mod proof_of_assets;
+pub mod binary_commitment;
@@ -5,14 +5,14 @@ use finite_field::{FieldElement};
 use secp256k1::{Secp256k1, Point};
 
 // Secp256k1 with g + h where h is hash of string "Provisions"
-struct ProvisionsCurve {
+pub struct ProvisionsCurve {
     curve: Secp256k1,
     g: Point,
     h: Point
 }
 
 impl ProvisionsCurve {
-    fn new() -> Self {
+    pub fn new() -> Self {
         let curve = Secp256k1::new();
         let g = curve.g();
         let h = curve.hash_onto_curve(b"PROVISIONS");
@@ -26,11 +26,11 @@ impl ProvisionsCurve {
         self.curve.pubkey(private_key)
     }
 
-    fn g_ref(&self) -> &Point {
+    pub fn g_ref(&self) -> &Point {
         &self.g
     }
 
-    fn h_ref(&self) -> &Point {
+    pub fn h_ref(&self) -> &Point {
         &self.h
     }
 
@@ -39,6 +39,12 @@ impl ProvisionsCurve {
     }
 }
 
+impl Default for ProvisionsCurve {
+    fn default() -> Self {
+        ProvisionsCurve::new()
+    }
+}
+
 // Generate a random number in Z_q for Secp256k1
 fn gen_rand() -> BigInt {
     let mut rng = thread_rng();
@@ -48,14 +54,14 @@ fn gen_rand() -> BigInt {
 }
 
 #[derive(Clone)]
-struct PublicKey {
+pub struct PublicKey {
     private_key: Option<BigInt>,
     public_key: Point,
     balance: BigInt
 }
 
 impl PublicKey {
-    fn new(private_key: BigInt, public_key: Point, balance: BigInt) -> Self {
+    pub fn new(private_key: BigInt, public_key: Point, balance: BigInt) -> Self {
         PublicKey {
             public_key,
             private_key: Some(private_key),
@@ -63,7 +69,7 @@ impl PublicKey {
         }
     }
 
-    fn new_from_pubkey(public_key: Point, balance: BigInt) -> Self {
+    pub fn new_from_pubkey(public_key: Point, balance: BigInt) -> Self {
         PublicKey {
             public_key,
             private_key: None,
@@ -113,14 +119,14 @@ impl PublicKey {
     }
 }
 
-trait PublicKeyProof {
+pub trait PublicKeyProof {
     fn p(&self) -> &Point;
 }
 
 // Representation of the proof that the prover needs as part of the interactive protocol of
 // verifying it.
 #[derive(Clone)]
-struct ProverPublicKeyProof {
+pub struct ProverPublicKeyProof {
     y: Point,
     b: Point,
 
@@ -141,7 +147,7 @@ impl PublicKeyProof for ProverPublicKeyProof {
 // Representation of the proof that the verifier has from the prover publisishing it. Basically,
 // this excludes blinding factors and the private keys.
 #[derive(Clone)]
-struct VerifierPublicKeyProof {
+pub struct VerifierPublicKeyProof {
     y: Point,
     b: Point,
     p: Point,
@@ -158,13 +164,13 @@ impl PublicKeyProof for VerifierPublicKeyProof {
 //     s in 0..1 and (v, t, x_hat) for i..n
 // Verifier input:
 //     (p, l) for i..n
-struct ProofOfAssets {
+pub struct ProofOfAssets {
     curve: ProvisionsCurve,
     pks: Vec<PublicKey>
 }
 
 impl ProofOfAssets {
-    fn new() -> Self {
+    pub fn new() -> Self {
         ProofOfAssets {
             curve: ProvisionsCurve::new(),
             pks: vec![]
@@ -172,8 +178,14 @@ impl ProofOfAssets {
     }
 }
 
+impl Default for ProofOfAssets {
+    fn default() -> Self {
+        ProofOfAssets::new()
+    }
+}
+
 impl ProofOfAssets {
-    fn gen_pk_proof(&self, pk: &PublicKey) -> (ProverPublicKeyProof, VerifierPublicKeyProof) {
+    pub fn gen_pk_proof(&self, pk: &PublicKey) -> (ProverPublicKeyProof, VerifierPublicKeyProof) {
         let y = pk.y();
         let b = pk.b(&self.curve);
         let (p, v) = pk.commitment(&self.curve);
@@ -209,7 +221,7 @@ impl ProofOfAssets {
     //     b^r_s     * h^r_v = p^c * a_1
     //     y^r_s     * h^r_t = l^c * a_2
     //     g^r_x_hat * h^r_t = l^c * a_3
-    fn verify_pk_proof(&self, prover_proof: &ProverPublicKeyProof, verifier_proof: &VerifierPublicKeyProof) -> Result<(), &str> {
+    pub fn verify_pk_proof(&self, prover_proof: &ProverPublicKeyProof, verifier_proof: &VerifierPublicKeyProof) -> Result<(), &str> {
         let curve = &self.curve;
 
         // Prover
@@ -258,7 +270,6 @@ impl ProofOfAssets {
         let p1 = bh == pa1;
         let p2 = yh == la2;
         let p3 = gh == la3;
-        println!("p1: {}, p2: {}, p3: {}", p1, p2, p3);
 
         if p1 {
             if p2 {
@@ -276,7 +287,7 @@ impl ProofOfAssets {
     }
 
     // Product of p for each proof
-    fn gen_z_assets(&self, proofs: &[impl PublicKeyProof]) -> Point {
+    pub fn gen_z_assets(&self, proofs: &[impl PublicKeyProof]) -> Point {
         proofs.iter().fold(Point::infinity(), |acc, proof| acc + proof.p())
     }
 }
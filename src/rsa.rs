@@ -0,0 +1,436 @@
+use num_bigint::{BigInt, RandBigInt, Sign};
+use num_integer::{Integer};
+use num_traits::*;
+use rand::{thread_rng, Rng};
+use finite_field::{mod_inverse};
+use util::{sha256};
+
+// RSA following RFC 8017 (PKCS #1 v2.2).
+//
+// Raw RSA is deterministic and malleable: (m_1 m_2)^e = m_1^e m_2^e. Messages should be padded
+// before encryption, preferably with OAEP. PKCS#1 v1.5 padding is here because so much still uses
+// it, and because its padding checks are what Bleichenbacher's attack exploits.
+//
+// https://tools.ietf.org/html/rfc8017
+
+// Primes below this are tried by division before running Miller-Rabin
+const SMALL_PRIMES_BOUND: u32 = 1000;
+
+// Miller-Rabin rounds for generated primes. Each round lets a composite through with probability at
+// most 1/4, and far less for random candidates.
+const MILLER_RABIN_ROUNDS: usize = 40;
+
+// SHA-256 output length, used for OAEP and MGF1
+const HASH_LEN: usize = 32;
+
+/// Miller-Rabin probabilistic primality test with the given number of random bases. Composites pass
+/// with probability at most 4^-rounds and primes always pass.
+pub fn is_probable_prime(n: &BigInt, rounds: usize) -> bool {
+    let two = BigInt::from(2);
+    if n < &two {
+        return false;
+    }
+    for p in small_primes() {
+        let p = BigInt::from(p);
+        if n == &p {
+            return true;
+        }
+        if (n % &p).is_zero() {
+            return false;
+        }
+    }
+
+    // n - 1 = d * 2^s with d odd
+    let n_minus_one = n - BigInt::one();
+    let mut d = n_minus_one.clone();
+    let mut s = 0;
+    while d.is_even() {
+        d >>= 1;
+        s += 1;
+    }
+
+    let mut rng = thread_rng();
+    'witness: for _ in 0..rounds {
+        let a = rng.gen_bigint_range(&two, &n_minus_one);
+        let mut x = a.modpow(&d, n);
+        if x.is_one() || x == n_minus_one {
+            continue;
+        }
+
+        for _ in 1..s {
+            x = x.modpow(&two, n);
+            if x == n_minus_one {
+                continue 'witness;
+            }
+        }
+
+        // a is a witness that n is composite
+        return false;
+    }
+
+    true
+}
+
+/// Generate a random prime of exactly bits bits. The top two bits are set so that the product of
+/// two such primes has exactly twice as many bits.
+pub fn gen_prime(bits: usize) -> BigInt {
+    assert!(bits >= 3, "primes need at least 3 bits");
+
+    let mut rng = thread_rng();
+    let low = BigInt::from(3) << (bits - 2);
+    let high = BigInt::one() << bits;
+    loop {
+        let candidate = rng.gen_bigint_range(&low, &high) | BigInt::one();
+        if is_probable_prime(&candidate, MILLER_RABIN_ROUNDS) {
+            return candidate;
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublicKey {
+    pub n: BigInt,
+    pub e: BigInt,
+}
+
+/// Private key along with the CRT values that make decryption about four times faster.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrivateKey {
+    pub n: BigInt,
+    pub e: BigInt,
+    pub d: BigInt,
+    p: BigInt,
+    q: BigInt,
+    d_p: BigInt,
+    d_q: BigInt,
+    q_inv: BigInt,
+}
+
+impl PublicKey {
+    pub fn new(n: BigInt, e: BigInt) -> Self {
+        PublicKey { n, e }
+    }
+
+    /// Length of the modulus in bytes
+    pub fn size(&self) -> usize {
+        self.n.bits().div_ceil(8)
+    }
+
+    /// Raw RSA encryption: m^e mod n
+    pub fn encrypt(&self, m: &BigInt) -> Result<BigInt, String> {
+        if m.is_negative() || m >= &self.n {
+            return Err(String::from("Message representative out of range"));
+        }
+
+        Ok(m.modpow(&self.e, &self.n))
+    }
+
+    /// Encrypt message with PKCS#1 v1.5 padding. Messages can be at most size() - 11 bytes.
+    pub fn encrypt_pkcs1v15(&self, message: &[u8]) -> Result<Vec<u8>, String> {
+        let padded = pkcs1v15_pad(message, self.size())?;
+        self.encrypt_bytes(&padded)
+    }
+
+    /// Encrypt message with OAEP using SHA-256 and MGF1. Messages can be at most size() - 66 bytes.
+    pub fn encrypt_oaep(&self, message: &[u8], label: &[u8]) -> Result<Vec<u8>, String> {
+        let padded = oaep_pad(message, label, self.size())?;
+        self.encrypt_bytes(&padded)
+    }
+
+    fn encrypt_bytes(&self, padded: &[u8]) -> Result<Vec<u8>, String> {
+        let c = self.encrypt(&os2ip(padded))?;
+        i2osp(&c, self.size())
+    }
+}
+
+impl PrivateKey {
+    /// Generate a key with a modulus of bits bits and public exponent e. Primes are regenerated
+    /// until e is invertible mod p - 1 and q - 1, which matters for small exponents like 3.
+    pub fn generate(bits: usize, e: &BigInt) -> Result<PrivateKey, String> {
+        if bits < 16 || bits % 2 != 0 {
+            return Err(format!("Unsupported modulus size: {}", bits));
+        }
+        if e < &BigInt::from(3) || e.is_even() {
+            return Err(format!("Invalid public exponent: {}", e));
+        }
+
+        loop {
+            let p = gen_prime(bits / 2);
+            let q = gen_prime(bits / 2);
+            if p == q {
+                continue;
+            }
+            if let Ok(key) = PrivateKey::from_primes(p, q, e.clone()) {
+                return Ok(key);
+            }
+        }
+    }
+
+    /// Build a key from its primes. Errors if e has no inverse mod (p - 1)(q - 1).
+    pub fn from_primes(p: BigInt, q: BigInt, e: BigInt) -> Result<PrivateKey, String> {
+        let one = BigInt::one();
+        let phi = (&p - &one) * (&q - &one);
+        let d = mod_inverse(&e, &phi).ok_or_else(|| format!("{} isn't invertible mod phi(n)", e))?;
+        let q_inv = mod_inverse(&q, &p).ok_or_else(|| String::from("p and q aren't coprime"))?;
+
+        Ok(PrivateKey {
+            n: &p * &q,
+            d_p: d.mod_floor(&(&p - &one)),
+            d_q: d.mod_floor(&(&q - &one)),
+            e, d, p, q, q_inv,
+        })
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey::new(self.n.clone(), self.e.clone())
+    }
+
+    pub fn size(&self) -> usize {
+        self.public_key().size()
+    }
+
+    /// Raw RSA decryption: c^d mod n, computed mod p and mod q and recombined with Garner's formula
+    pub fn decrypt(&self, c: &BigInt) -> Result<BigInt, String> {
+        if c.is_negative() || c >= &self.n {
+            return Err(String::from("Ciphertext representative out of range"));
+        }
+
+        let m_1 = c.modpow(&self.d_p, &self.p);
+        let m_2 = c.modpow(&self.d_q, &self.q);
+        let h = (&self.q_inv * (m_1 - &m_2)).mod_floor(&self.p);
+        Ok(m_2 + h * &self.q)
+    }
+
+    pub fn decrypt_pkcs1v15(&self, ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+        let padded = self.decrypt_bytes(ciphertext)?;
+        pkcs1v15_unpad(&padded)
+    }
+
+    pub fn decrypt_oaep(&self, ciphertext: &[u8], label: &[u8]) -> Result<Vec<u8>, String> {
+        let padded = self.decrypt_bytes(ciphertext)?;
+        oaep_unpad(&padded, label)
+    }
+
+    fn decrypt_bytes(&self, ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+        if ciphertext.len() != self.size() {
+            return Err(String::from("Ciphertext has the wrong length"));
+        }
+
+        let m = self.decrypt(&os2ip(ciphertext))?;
+        i2osp(&m, self.size())
+    }
+}
+
+/// Integer to octet string: x as exactly len big-endian bytes
+pub fn i2osp(x: &BigInt, len: usize) -> Result<Vec<u8>, String> {
+    let (sign, bytes) = x.to_bytes_be();
+    if sign == Sign::Minus {
+        return Err(String::from("Can't encode a negative integer"));
+    }
+
+    let bytes: Vec<u8> = bytes.into_iter().skip_while(|b| *b == 0).collect();
+    if bytes.len() > len {
+        return Err(format!("Integer too large for {} bytes", len));
+    }
+
+    let mut res = vec![0; len - bytes.len()];
+    res.extend(bytes);
+    Ok(res)
+}
+
+/// Octet string to integer, big-endian
+pub fn os2ip(bytes: &[u8]) -> BigInt {
+    BigInt::from_bytes_be(Sign::Plus, bytes)
+}
+
+/// EM = 0x00 || 0x02 || PS || 0x00 || M where PS is at least 8 random non-zero bytes
+pub fn pkcs1v15_pad(message: &[u8], k: usize) -> Result<Vec<u8>, String> {
+    if message.len() + 11 > k {
+        return Err(String::from("Message too long"));
+    }
+
+    let mut rng = thread_rng();
+    let mut padded = vec![0x00, 0x02];
+    for _ in 0..k - message.len() - 3 {
+        padded.push(rng.gen_range(1, 256) as u8);
+    }
+    padded.push(0x00);
+    padded.extend_from_slice(message);
+    Ok(padded)
+}
+
+/// Undo pkcs1v15_pad. The same error is returned whatever was wrong with the padding.
+pub fn pkcs1v15_unpad(padded: &[u8]) -> Result<Vec<u8>, String> {
+    let error = || String::from("Decryption error");
+    if padded.len() < 11 || padded[0] != 0x00 || padded[1] != 0x02 {
+        return Err(error());
+    }
+
+    let separator = padded[2..].iter().position(|b| *b == 0x00).ok_or_else(error)? + 2;
+    if separator < 10 {
+        return Err(error());
+    }
+
+    Ok(padded[separator + 1..].to_vec())
+}
+
+/// EM = 0x00 || maskedSeed || maskedDB where DB = lHash || PS || 0x01 || M, using SHA-256
+pub fn oaep_pad(message: &[u8], label: &[u8], k: usize) -> Result<Vec<u8>, String> {
+    if message.len() + 2 * HASH_LEN + 2 > k {
+        return Err(String::from("Message too long"));
+    }
+
+    let mut db = sha256(label);
+    db.extend(vec![0; k - message.len() - 2 * HASH_LEN - 2]);
+    db.push(0x01);
+    db.extend_from_slice(message);
+
+    let mut seed = vec![0; HASH_LEN];
+    thread_rng().fill(&mut seed[..]);
+
+    let masked_db = xor(&db, &mgf1(&seed, k - HASH_LEN - 1));
+    let masked_seed = xor(&seed, &mgf1(&masked_db, HASH_LEN));
+
+    let mut padded = vec![0x00];
+    padded.extend(masked_seed);
+    padded.extend(masked_db);
+    Ok(padded)
+}
+
+/// Undo oaep_pad. The same error is returned whatever was wrong with the padding, since telling
+/// them apart is Manger's attack.
+pub fn oaep_unpad(padded: &[u8], label: &[u8]) -> Result<Vec<u8>, String> {
+    let error = || String::from("Decryption error");
+    if padded.len() < 2 * HASH_LEN + 2 {
+        return Err(error());
+    }
+
+    let (masked_seed, masked_db) = padded[1..].split_at(HASH_LEN);
+    let seed = xor(masked_seed, &mgf1(masked_db, HASH_LEN));
+    let db = xor(masked_db, &mgf1(&seed, masked_db.len()));
+
+    let (l_hash, rest) = db.split_at(HASH_LEN);
+    let separator = rest.iter().position(|b| *b != 0x00);
+    match separator {
+        Some(i) if padded[0] == 0x00 && l_hash == &sha256(label)[..] && rest[i] == 0x01 => Ok(rest[i + 1..].to_vec()),
+        _ => Err(error()),
+    }
+}
+
+/// Mask generation function MGF1 with SHA-256: hashes of seed || counter concatenated to len bytes
+pub fn mgf1(seed: &[u8], len: usize) -> Vec<u8> {
+    let mut mask = vec![];
+    let mut counter: u32 = 0;
+    while mask.len() < len {
+        let mut data = seed.to_vec();
+        data.extend_from_slice(&counter.to_be_bytes());
+        mask.extend(sha256(&data));
+        counter += 1;
+    }
+
+    mask.truncate(len);
+    mask
+}
+
+fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
+    a.iter().zip(b).map(|(x, y)| x ^ y).collect()
+}
+
+fn small_primes() -> Vec<u32> {
+    (2..SMALL_PRIMES_BOUND).filter(|n| (2..*n).take_while(|d| d * d <= *n).all(|d| n % d != 0)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rsa::*;
+    use set1::{hex_decode};
+
+    fn test_key() -> PrivateKey {
+        // Generated with python's cryptography package
+        let hex = |s: &str| BigInt::parse_bytes(s.as_bytes(), 16).unwrap();
+        let p = hex("dc4dcccca5cf9e0c4ce3961b0b68d8db545b07f1de0bdde4b99f8f6da97d38122713a59107981d5787c432e51499f43fbcb52d3bfb91fbfba4648c21e16f5b45");
+        let q = hex("c8253794243bf036cb10a1e018fe3899aeddcc235a9f75175c70b990f76737cc6f3d9ea6ca9615031bb32575f7604a21c11b7c2102f4fb4c3dd352935afb7ae1");
+        PrivateKey::from_primes(p, q, BigInt::from(65537)).unwrap()
+    }
+
+    #[test]
+    fn rsa_miller_rabin() {
+        for p in &[2, 3, 5, 7919, 104729] {
+            assert!(is_probable_prime(&BigInt::from(*p), 20), "{} is prime", p);
+        }
+        // 561 and 41041 are Carmichael numbers, which fool the Fermat test
+        for c in &[0, 1, 4, 561, 41041, 104730] {
+            assert!(!is_probable_prime(&BigInt::from(*c), 20), "{} is composite", c);
+        }
+
+        let m127 = (BigInt::one() << 127) - BigInt::one();
+        assert!(is_probable_prime(&m127, 20));
+        assert!(!is_probable_prime(&(&m127 * BigInt::from(1009)), 20));
+
+        let p = gen_prime(128);
+        assert_eq!(p.bits(), 128);
+        assert!(is_probable_prime(&p, 20));
+    }
+
+    #[test]
+    fn rsa_generate_encrypt_decrypt() {
+        let key = PrivateKey::generate(512, &BigInt::from(3)).unwrap();
+        assert_eq!(key.n.bits(), 512);
+        assert_eq!(key.size(), 64);
+
+        let public = key.public_key();
+        let m = BigInt::from(42);
+        let c = public.encrypt(&m).unwrap();
+        assert_eq!(c, BigInt::from(42 * 42 * 42));
+        assert_eq!(key.decrypt(&c), Ok(m));
+
+        let m = BigInt::parse_bytes(b"123456789abcdef0123456789abcdef", 16).unwrap();
+        assert_eq!(key.decrypt(&public.encrypt(&m).unwrap()), Ok(m));
+        assert!(public.encrypt(&key.n).is_err());
+
+        assert!(PrivateKey::generate(512, &BigInt::from(4)).is_err());
+    }
+
+    #[test]
+    fn rsa_pkcs1v15() {
+        let key = test_key();
+        let ciphertext = hex_decode("562f0af59ae7118cd7288f0303ce5025afce790d27148a9785e0e6c23e9130b1da782b2ceca1285b9d0f599040a267c28d13bcf210e99e86cf0998bc73dd023442c99e8d42c0b0939736516bd8532ef153fb1bdbd90545d184855b7f8668506d047078ed90bb586825d82a9fd795ba6c7ad087f1beca817ba1b5580388fd292c");
+        assert_eq!(key.decrypt_pkcs1v15(&ciphertext), Ok(b"Attack at dawn".to_vec()));
+
+        let public = key.public_key();
+        let ciphertext = public.encrypt_pkcs1v15(b"Attack at dusk").unwrap();
+        assert_eq!(ciphertext.len(), 128);
+        assert_eq!(key.decrypt_pkcs1v15(&ciphertext), Ok(b"Attack at dusk".to_vec()));
+        assert!(public.encrypt_pkcs1v15(&[0; 118]).is_err());
+
+        assert!(pkcs1v15_unpad(&[0, 2, 1, 1, 1, 1, 1, 1, 1, 0, 9, 9]).is_err(), "padding string too short");
+        assert!(pkcs1v15_unpad(&[0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 9]).is_err(), "wrong block type");
+        assert_eq!(pkcs1v15_unpad(&[0, 2, 1, 1, 1, 1, 1, 1, 1, 1, 0, 9]), Ok(vec![9]));
+    }
+
+    #[test]
+    fn rsa_oaep() {
+        let key = test_key();
+        let ciphertext = hex_decode("483fe8924b7ea2734535b9f8a9632a27f0f4141861c9d871e0bae23f86cdcd115095c6749d33c6ca70f7570539b8ab17d0b26a05317aa42f6fc885ea02ea955b6dd33f3f71f9b86729919489c506194becf204ab139cbf782fa9f16dc8c21800c710397b4395f1351e2a26d968edeba25409b4e21a6bc9e4c7c978d5df1ff890");
+        assert_eq!(key.decrypt_oaep(&ciphertext, b""), Ok(b"Attack at dawn".to_vec()));
+
+        let labeled = hex_decode("539c876214965038e798b0e638340d4683cbaa483f2c225f330eff28ed7d9861734104f56446cc235764f593f218922f9fa69dabec47028f1fea172ca58284d9b8e457f72ab62ea500f86d2c043455c80f3bcaa3b02e6d4b855328a938b05e51755f9b4d57bd73e45927e3aee14ac9ec2c00ad03fb6e81db2a975a28d268dd45");
+        assert_eq!(key.decrypt_oaep(&labeled, b"label"), Ok(b"Attack at dawn".to_vec()));
+        assert!(key.decrypt_oaep(&labeled, b"other label").is_err());
+
+        let public = key.public_key();
+        let first = public.encrypt_oaep(b"Attack at dusk", b"").unwrap();
+        let second = public.encrypt_oaep(b"Attack at dusk", b"").unwrap();
+        assert_ne!(first, second, "OAEP is randomized");
+        assert_eq!(key.decrypt_oaep(&first, b""), Ok(b"Attack at dusk".to_vec()));
+        assert!(public.encrypt_oaep(&[0; 63], b"").is_err());
+    }
+
+    #[test]
+    fn rsa_i2osp() {
+        assert_eq!(i2osp(&BigInt::zero(), 2), Ok(vec![0, 0]));
+        assert_eq!(i2osp(&BigInt::from(0x0102), 3), Ok(vec![0, 1, 2]));
+        assert!(i2osp(&BigInt::from(0x010203), 2).is_err());
+        assert_eq!(os2ip(&[0, 1, 2]), BigInt::from(0x0102));
+    }
+}
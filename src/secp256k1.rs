@@ -3,8 +3,8 @@ use std::rc::{Rc};
 use std::ops::{Add, Sub, Mul};
 use num_bigint::{BigInt};
 use finite_field::{Field, FieldElement};
-use elliptic_curve::{FiniteCurve, FiniteCurvy, Point as ECPoint, Sec};
-use util::{sha256_bigint};
+use elliptic_curve::{CurveGroup, FiniteCurve, FiniteCurvy, Point as ECPoint, Sec};
+use util::{bigint_to_bytes_be, hkdf_sha256, sha256_bigint};
 
 #[derive(Debug, Clone)]
 pub struct Secp256k1 {
@@ -65,18 +65,28 @@ impl Secp256k1 {
         self.curve.is_valid_point(point.point_ref())
     }
 
-    /// Intended to hash arbitrary content onto the curve using SHA-256. This works the first time
-    /// for some content but not all so it's not safe to call with arbitrary strings since it
-    /// doesn't retry to find.
+    /// Hash arbitrary content onto the curve using SHA-256 with try-and-increment. The hash is
+    /// used as the x coordinate and incremented until it lands on the curve, which takes two tries
+    /// on average. See hash_to_curve for the RFC 9380 method.
     pub fn hash_onto_curve(&self, content: &[u8]) -> Point {
-        let x = self.field_elem(sha256_bigint(content));
-        // RHS calculation here stolen from: https://github.com/bbuenz/provisions/blob/b51530db630bc5bddf30bbae0f3d5c99a755649a/src/main/java/edu/stanford/crypto/ECConstants.java#L29-L31
-        let rhs = x.pow(&BigInt::from(2)) * (self.a_ref() + x.clone()) + self.b_ref();
-        let y = rhs.sqrt();
-        let ec_point = self.curve.point(x.value.clone(), y.value);
-        let point = Point::new(ec_point, self.clone());
+        let one = self.field_elem(1);
+        let mut x = self.field_elem(sha256_bigint(content));
+        loop {
+            // RHS calculation here stolen from: https://github.com/bbuenz/provisions/blob/b51530db630bc5bddf30bbae0f3d5c99a755649a/src/main/java/edu/stanford/crypto/ECConstants.java#L29-L31
+            let rhs = x.pow(&BigInt::from(2)) * (self.a_ref() + x.clone()) + self.b_ref();
+            if let Some(y) = rhs.sqrt() {
+                let ec_point = self.curve.point(x.value.clone(), y.value);
+                return Point::new(ec_point, self.clone());
+            }
+
+            x = x + &one;
+        }
+    }
+}
 
-        point
+impl Default for Secp256k1 {
+    fn default() -> Self {
+        Secp256k1::new()
     }
 }
 
@@ -94,40 +104,74 @@ impl FiniteCurvy for Secp256k1 {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct Point {
-    point: ECPoint,
-    curve: Rc<Secp256k1>
-}
+impl CurveGroup for Secp256k1 {
+    fn curve_ref(&self) -> &FiniteCurve {
+        &self.curve
+    }
 
-impl Point {
-    fn new(point: ECPoint, curve: Secp256k1) -> Point {
-        let point = Point { point, curve: Rc::new(curve.clone()) };
-        assert!(curve.is_valid_point(&point), "point not on curve");
-        point
+    fn g_ref(&self) -> &ECPoint {
+        &self.g
     }
 
-    /// Helper method to create a new point cloning the existing curve over
-    pub fn new_from_ec_point(&self, point: ECPoint) -> Point {
-        let next_point = Point { point, curve: self.curve.clone() };
-        assert!(self.curve.is_valid_point(&next_point), "point not on curve");
-        next_point
+    fn n_ref(&self) -> &BigInt {
+        self.subgroup_field.p_ref()
     }
+}
+
+/// Point on a curve which carries the curve around with it. Defaults to Secp256k1 but works with
+/// any curve implementing CurveGroup.
+#[derive(Debug, Clone)]
+pub struct Point<C = Secp256k1> {
+    point: ECPoint,
+    curve: Rc<C>
+}
 
+impl Point<Secp256k1> {
     /// Return a new instance of the point at infinity
     pub fn infinity() -> Point {
         let curve = Secp256k1::new();
         Point { point: ECPoint::Infinity, curve: Rc::new(curve) }
     }
+}
+
+impl<C: CurveGroup> Point<C> {
+    /// Create a point on the provided curve. Panics if the point is not on the curve.
+    pub fn new(point: ECPoint, curve: C) -> Point<C> {
+        assert!(curve.curve_ref().is_valid_point(&point), "point not on curve");
+        Point { point, curve: Rc::new(curve) }
+    }
+
+    /// Helper method to create a new point cloning the existing curve over
+    pub fn new_from_ec_point(&self, point: ECPoint) -> Point<C> {
+        assert!(self.curve.curve_ref().is_valid_point(&point), "point not on curve");
+        Point { point, curve: self.curve.clone() }
+    }
+
+    pub fn curve_ref(&self) -> &C {
+        self.curve.as_ref()
+    }
 
     pub fn point_ref(&self) -> &ECPoint {
         &self.point
     }
 
-    pub fn inverse(&self) -> Point {
+    pub fn inverse(&self) -> Point<C> {
         self.new_from_ec_point(self.point.inverse())
     }
 
+    /// Elliptic Curve Diffie-Hellman. Multiply this public key by our private key and return the
+    /// x coordinate of the result as big-endian bytes the size of the field.
+    pub fn shared_secret(&self, private_key: &BigInt) -> Result<Vec<u8>, String> {
+        let shared = self * private_key.clone();
+        let (x, _) = shared.as_coord().ok_or("Shared secret is the point at infinity")?;
+        Ok(bigint_to_bytes_be(&x.value, x.p_ref().bits().div_ceil(8), true))
+    }
+
+    /// ECDH with the shared secret run through HKDF-SHA-256 to derive len bytes of key material.
+    pub fn ecdh(&self, private_key: &BigInt, salt: &[u8], info: &[u8], len: usize) -> Result<Vec<u8>, String> {
+        Ok(hkdf_sha256(salt, &self.shared_secret(private_key)?, info, len))
+    }
+
     /// Return the coordinates of the point iff it is not the point at infinity.
     pub fn as_coord(&self) -> Option<(&FieldElement, &FieldElement)> {
         match &self.point {
@@ -137,89 +181,89 @@ impl Point {
     }
 }
 
-impl Sec<Point, Secp256k1> for Point {
+impl<C: CurveGroup + Clone> Sec<Point<C>, C> for Point<C> {
     fn as_sec(&self) -> Vec<u8> { self.point.as_sec() }
     fn as_sec_compressed(&self) -> Vec<u8> { self.point.as_sec_compressed() }
 
-    fn from_sec<'a>(bytes: &'a [u8], curve: &'a Secp256k1) -> Result<Point, String> {
-        let point = ECPoint::from_sec(bytes, &curve.curve)?;
+    fn from_sec<'a>(bytes: &'a [u8], curve: &'a C) -> Result<Point<C>, String> {
+        let point = ECPoint::from_sec(bytes, curve.curve_ref())?;
         Ok(Point::new(point, curve.clone()))
     }
 }
 
-impl PartialEq<Point> for Point {
-    fn eq(&self, rhs: &Point) -> bool {
-        &self.point == &rhs.point
+impl<C> PartialEq<Point<C>> for Point<C> {
+    fn eq(&self, rhs: &Point<C>) -> bool {
+        self.point == rhs.point
     }
 }
 
-impl PartialEq<ECPoint> for Point {
+impl<C> PartialEq<ECPoint> for Point<C> {
     fn eq(&self, rhs: &ECPoint) -> bool {
         &self.point == rhs
     }
 }
 
-impl Add for Point {
-    type Output = Point;
+impl<C: CurveGroup> Add for Point<C> {
+    type Output = Point<C>;
 
-    fn add(self, rhs: Point) -> Point {
+    fn add(self, rhs: Point<C>) -> Point<C> {
         self + &rhs
     }
 }
 
-impl<'a> Add<&'a Point> for Point {
-    type Output = Point;
+impl<'a, C: CurveGroup> Add<&'a Point<C>> for Point<C> {
+    type Output = Point<C>;
 
-    fn add(self, rhs: &'a Point) -> Point {
+    fn add(self, rhs: &'a Point<C>) -> Point<C> {
         &self + rhs
     }
 }
 
-impl<'a, 'b> Add<&'b Point> for &'a Point {
-    type Output = Point;
+impl<'a, 'b, C: CurveGroup> Add<&'b Point<C>> for &'a Point<C> {
+    type Output = Point<C>;
 
-    fn add(self, rhs: &'b Point) -> Point {
+    fn add(self, rhs: &'b Point<C>) -> Point<C> {
         let new_point = self.point.add(&rhs.point, self.curve.as_ref());
         self.new_from_ec_point(new_point)
     }
 }
 
-impl Sub for Point {
-    type Output = Point;
+impl<C: CurveGroup> Sub for Point<C> {
+    type Output = Point<C>;
 
-    fn sub(self, rhs: Point) -> Point {
+    fn sub(self, rhs: Point<C>) -> Point<C> {
         self - &rhs
     }
 }
 
-impl<'a> Sub<&'a Point> for Point {
-    type Output = Point;
+impl<'a, C: CurveGroup> Sub<&'a Point<C>> for Point<C> {
+    type Output = Point<C>;
 
-    fn sub(self, rhs: &'a Point) -> Point {
+    fn sub(self, rhs: &'a Point<C>) -> Point<C> {
         // Subtration is defined as adding to the inverse
         let new_point = self.point.add(&rhs.point.inverse(), self.curve.as_ref());
         self.new_from_ec_point(new_point)
     }
 }
 
-impl<T: Into<BigInt> + Clone> Mul<T> for Point {
-    type Output = Point;
+impl<C: CurveGroup, T: Into<BigInt> + Clone> Mul<T> for Point<C> {
+    type Output = Point<C>;
 
-    fn mul(self, n: T) -> Point {
+    fn mul(self, n: T) -> Point<C> {
         &self * n
     }
 }
 
-impl<'a, T: Into<BigInt> + Clone> Mul<T> for &'a Point {
-    type Output = Point;
+impl<'a, C: CurveGroup, T: Into<BigInt> + Clone> Mul<T> for &'a Point<C> {
+    type Output = Point<C>;
 
-    fn mul(self, n: T) -> Point {
+    fn mul(self, n: T) -> Point<C> {
         let new_point = self.point.mul(&n, self.curve.as_ref());
         self.new_from_ec_point(new_point)
     }
 }
 
-impl fmt::Display for Point {
+impl<C> fmt::Display for Point<C> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.point.fmt(f)
     }
@@ -231,6 +275,7 @@ mod tests {
     use num_traits::*;
     use secp256k1::*;
     use elliptic_curve::{Point as ECPoint, Sec};
+    use util::{bigint_to_bytes32_be};
 
     #[test]
     fn secp() {
@@ -426,6 +471,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn secp_sec_decoding_invalid_x() {
+        let curve = Secp256k1::new();
+
+        // Roughly half of x values don't have a point on the curve
+        for x in 1..20u8 {
+            let rhs = curve.field_elem(BigInt::from(x).pow(3u8) + 7);
+            let mut compressed = vec![2u8; 33];
+            compressed[1..].copy_from_slice(&bigint_to_bytes32_be(&BigInt::from(x), true));
+
+            assert_eq!(Point::from_sec(&compressed, &curve).is_ok(), rhs.is_square(), "x = {}", x);
+        }
+    }
+
+    #[test]
+    fn secp256k1_ecdh() {
+        let c = Secp256k1::new();
+        let (alice, bob) = (BigInt::from(0xa11ce), BigInt::from(0xb0b));
+        let (alice_pub, bob_pub) = (c.pubkey(&alice), c.pubkey(&bob));
+
+        let shared = alice_pub.shared_secret(&bob).unwrap();
+        assert_eq!(shared.len(), 32);
+        assert_eq!(shared, bob_pub.shared_secret(&alice).unwrap());
+
+        let alice_key = bob_pub.ecdh(&alice, b"salt", b"info", 48).unwrap();
+        assert_eq!(alice_key.len(), 48);
+        assert_eq!(alice_key, alice_pub.ecdh(&bob, b"salt", b"info", 48).unwrap());
+        assert_ne!(alice_key, alice_pub.ecdh(&bob, b"salt", b"other", 48).unwrap());
+
+        assert!(alice_pub.shared_secret(&Secp256k1::n()).is_err());
+    }
+
     #[test]
     fn secp256k1_point_add() {
         let c = &Secp256k1::new();
@@ -450,5 +527,11 @@ mod tests {
         let point = c.hash_onto_curve(b"PROVISIONS");
 
         assert!(c.is_valid_point(&point), "it generates a valid point");
+
+        // Roughly half of these need more than one try
+        for i in 0..20u8 {
+            let point = c.hash_onto_curve(&[i]);
+            assert!(c.is_valid_point(&point), "it generates a valid point for {}", i);
+        }
     }
 }
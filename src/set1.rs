@@ -1,10 +1,11 @@
 // Helpful resource for testing: https://cryptii.com/pipes/base64-to-hex
 // Resource for bit fiddling: http://www.coranac.com/documents/working-with-bits-and-bitfields/
 use base64::decode as base64decode;
+use std::cmp;
 use std::collections::HashMap;
 use std::str;
-use openssl::symm::{decrypt, Cipher};
-use openssl::error::ErrorStack;
+use block_cipher::{OpenSslAes};
+use modes::{Ecb};
 
 fn hex_to_nibbles(input: &str) -> Vec<u8> {
     // Can also be done with some ascii shifting described in
@@ -124,7 +125,7 @@ pub fn hex_decode(hex: &str) -> Vec<u8> {
     nibs_to_bytes(&nibs)
 }
 
-fn hex_to_base64(input: &str) -> String {
+pub fn hex_to_base64(input: &str) -> String {
     let bytes = hex_decode(input);
     // There be easter egg here:
     // println!("{:?}", str::from_utf8(&bytes).unwrap());
@@ -132,7 +133,7 @@ fn hex_to_base64(input: &str) -> String {
     res
 }
 
-fn xor(left_hex: &str, right_hex: &str) -> String {
+pub fn xor(left_hex: &str, right_hex: &str) -> String {
     let left_nibs = hex_to_nibbles(left_hex);
     let right_nibs = hex_to_nibbles(right_hex);
 
@@ -170,7 +171,7 @@ pub fn decrypt_single_byte_xor_with_score_bytes(bytes: &[u8]) -> Option<(usize,
     ascii_plaintexts_with_scores.max_by_key(|x| x.0)
 }
 
-fn decrypt_single_byte_xor(input: &str) -> String {
+pub fn decrypt_single_byte_xor(input: &str) -> String {
     let decrypted_with_score = decrypt_single_byte_xor_with_score(input);
     let bytes = decrypted_with_score.map(|(_, bytes)| bytes);
     let plaintext = bytes.and_then(|b| String::from_utf8(b).ok());
@@ -178,7 +179,7 @@ fn decrypt_single_byte_xor(input: &str) -> String {
     plaintext.unwrap() // YOLO
 }
 
-fn detect_single_byte_xor(inputs: Vec<&str>) -> Option<String> {
+pub fn detect_single_byte_xor(inputs: Vec<&str>) -> Option<String> {
     inputs
         .iter()
         .filter_map(|input| decrypt_single_byte_xor_with_score(input))
@@ -227,7 +228,7 @@ pub fn hex_encode(bytes: &[u8]) -> String {
     s
 }
 
-fn xor_encrypt_with_key(plaintext: &str, key: &str) -> String {
+pub fn xor_encrypt_with_key(plaintext: &str, key: &str) -> String {
     let encrypted = plaintext
         .as_bytes()
         .iter()
@@ -238,7 +239,7 @@ fn xor_encrypt_with_key(plaintext: &str, key: &str) -> String {
     hex_encode(&encrypted)
 }
 
-fn xor_decrypt_with_key(plaintext: &[u8], key: &[u8]) -> Vec<u8> {
+pub fn xor_decrypt_with_key(plaintext: &[u8], key: &[u8]) -> Vec<u8> {
     plaintext
         .iter()
         .zip(key.iter().cycle())
@@ -246,14 +247,21 @@ fn xor_decrypt_with_key(plaintext: &[u8], key: &[u8]) -> Vec<u8> {
         .collect::<Vec<u8>>()
 }
 
-fn decrypt_repeating_xor(base64: &str) -> String {
+pub fn decrypt_repeating_xor(base64: &str) -> String {
     let bytes = base64decode(base64).unwrap();
+    let (_, plaintext) = break_repeating_xor(&bytes);
+    String::from_utf8(plaintext).unwrap()
+}
 
+/// Recover the key of repeating-key XOR encrypted bytes. Returns (key, plaintext).
+pub fn break_repeating_xor(bytes: &[u8]) -> (Vec<u8>, Vec<u8>) {
     // For each KEYSIZE, 2-40, take first and second keysize of bytes, calculate normalized
-    // distance, and select the lowest as the likely key size.
+    // distance, and select the lowest as the likely key size. Shorter inputs limit the sizes we
+    // can try since we need 4 blocks of each size.
+    let max_key_size = cmp::min(40, bytes.len() / 4);
     let mut lowest_distance = 999999;
     let mut best_sizes: Vec<usize> = vec![];
-    for key_size in 2..41 {
+    for key_size in 2..(max_key_size + 1) {
         let mut chunks = bytes.chunks(key_size);
         // Take 4 blocks and average them for the distance
         let distance1 = hamming_distance(chunks.next().unwrap(), chunks.next().unwrap());
@@ -272,7 +280,7 @@ fn decrypt_repeating_xor(base64: &str) -> String {
     }
 
     let mut best_score = 0;
-    let mut found_key = String::new();
+    let mut found_key: Vec<u8> = vec![];
     for key_size in &best_sizes {
         let blocks: Vec<Vec<u8>> = bytes
             .chunks(*key_size)
@@ -284,14 +292,14 @@ fn decrypt_repeating_xor(base64: &str) -> String {
         //
         // For each block, the single-byte XOR key that produces the best looking histogram is the
         // repeating-key XOR key byte for that block. Put them together and you have the key.
-        let mut key = String::new();
+        let mut key: Vec<u8> = vec![];
         let mut total_score = 0;
         for block in &transposed {
             let res = decrypt_single_byte_xor_with_score_bytes(&block);
             if let Some((score, c, plaintext)) = &res {
                 if score > &0 {
                     total_score += score;
-                    key.push(*c);
+                    key.push(*c as u8);
                 }
             }
         }
@@ -301,8 +309,8 @@ fn decrypt_repeating_xor(base64: &str) -> String {
         }
     }
 
-    let plaintext = xor_decrypt_with_key(&bytes, found_key.as_bytes());
-    String::from_utf8(plaintext).unwrap()
+    let plaintext = xor_decrypt_with_key(bytes, &found_key);
+    (found_key, plaintext)
 }
 
 pub fn transpose(input: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
@@ -345,16 +353,15 @@ fn byte_hamming_distance(left: &u8, right: &u8) -> usize {
     distance
 }
 
-fn hamming_distance(left: &[u8], right: &[u8]) -> usize {
+pub fn hamming_distance(left: &[u8], right: &[u8]) -> usize {
     left.iter()
         .zip(right.iter())
         .map(|(l, r)| byte_hamming_distance(l, r))
         .sum()
 }
 
-fn aes_ecb_decrypt(input: &[u8], key: &[u8]) -> Result<Vec<u8>, ErrorStack> {
-    let cipher = Cipher::aes_128_ecb();
-    decrypt(cipher, key, None, input)
+pub fn aes_ecb_decrypt(input: &[u8], key: &[u8]) -> Result<Vec<u8>, String> {
+    Ecb::new(OpenSslAes::new(key)?).decrypt(input)
 }
 
 pub fn bytes_to_16byte_blocks(bytes: &[u8]) -> Vec<u16> {
@@ -379,7 +386,7 @@ pub fn num_duplicate_blocks(bytes: &[u16]) -> usize {
     max_dup.unwrap_or(&0).clone()
 }
 
-fn detect_aes_ecb_from_hex_lines(input: &str) -> Option<String> {
+pub fn detect_aes_ecb_from_hex_lines(input: &str) -> Option<String> {
     input.lines()
         .map(|line| hex_decode(line))
         // Retain bytes value in u8 while making sure to check duplicate blocks with 16 bit values
@@ -544,6 +551,16 @@ mod tests {
         assert_eq!(result.len(), 2876);
     }
 
+    #[test]
+    fn break_repeating_xor() {
+        let ciphertext = base64decode(&read_file("src/data/challenge6.txt", true)).unwrap();
+
+        let (key, plaintext) = set1::break_repeating_xor(&ciphertext);
+
+        assert_eq!(key, b"Terminator X: Bring the noise".to_vec());
+        assert_eq!(plaintext.len(), 2876);
+    }
+
     #[test]
     fn hamming_distance() {
         let left = "this is a test";
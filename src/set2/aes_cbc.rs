@@ -1,9 +1,19 @@
 use openssl::symm::{Cipher, Crypter, Mode};
 use openssl::error::ErrorStack;
-use set2::pkcs_7_pad;
+use block_cipher::{OpenSslAes};
+use modes::{Cbc};
+
+// AES-128, AES-192 or AES-256 by key length
+fn ecb_cipher(key: &[u8]) -> Cipher {
+    match key.len() {
+        24 => Cipher::aes_192_ecb(),
+        32 => Cipher::aes_256_ecb(),
+        _ => Cipher::aes_128_ecb(),
+    }
+}
 
 pub fn openssl_ecb_encrypt_block(data: &[u8], key: &[u8]) -> Result<Vec<u8>, ErrorStack> {
-    let cipher = Cipher::aes_128_ecb();
+    let cipher = ecb_cipher(key);
 
     // Must use this more complicated scheme to disable padding since we handle adding padding
     // ourselves.
@@ -17,8 +27,8 @@ pub fn openssl_ecb_encrypt_block(data: &[u8], key: &[u8]) -> Result<Vec<u8>, Err
     Ok(ciphertext)
 }
 
-fn openssl_ecb_decrypt_block(data: &[u8], key: &[u8]) -> Result<Vec<u8>, ErrorStack> {
-    let cipher = Cipher::aes_128_ecb();
+pub fn openssl_ecb_decrypt_block(data: &[u8], key: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+    let cipher = ecb_cipher(key);
 
     let mut crypter = Crypter::new(cipher, Mode::Decrypt, key, None).unwrap();
     crypter.pad(false);
@@ -30,81 +40,12 @@ fn openssl_ecb_decrypt_block(data: &[u8], key: &[u8]) -> Result<Vec<u8>, ErrorSt
     Ok(plaintext)
 }
 
-fn xor_slices(left: &[u8], right: &[u8]) -> Vec<u8> {
-    left.iter().zip(right.iter()).map(|(l, r)| l ^ r).collect()
-}
-
-// AES block size is 128 bits (16 bytes)
-const BYTES_IN_BLOCK: usize = 16;
-
-pub fn encrypt(data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>, ErrorStack> {
-    let mut ciphertext: Vec<u8> = vec![];
-
-    let mut prev_block = iv.to_vec();
-    for block in data.chunks(BYTES_IN_BLOCK) {
-        // I think there's still something slightly wrong here if the block is exactly 16 bytes.
-        // We should be padding it with an extra block but we aren't.
-        let padded_block: Vec<u8> = if block.len() < BYTES_IN_BLOCK {
-            pkcs_7_pad(&block, BYTES_IN_BLOCK)
-        } else {
-            block.to_vec()
-        };
-        let next_block = xor_slices(&prev_block, &padded_block);
-        let encrypted_next_block = openssl_ecb_encrypt_block(&next_block, &key)?;
-        ciphertext.append(&mut encrypted_next_block.clone());
-
-        prev_block = encrypted_next_block;
-    }
-
-    Ok(ciphertext)
-}
-
-pub fn decrypt(data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>, PaddingError> {
-    let mut offset_data = iv.to_vec();
-    offset_data.append(&mut data.to_vec());
-
-    let plaintext: Vec<u8> = data.chunks(BYTES_IN_BLOCK)
-        .zip(offset_data.chunks(BYTES_IN_BLOCK))
-        .map(|(block, prev_block)| {
-            // This is unsafe to unwrap. In a real impl we'd want to handle and return an err.
-            let decrypted_block = openssl_ecb_decrypt_block(&block, &key).unwrap();
-            xor_slices(&decrypted_block, &prev_block)
-        }).flatten().collect();
-
-    remove_padding(&plaintext)
+pub fn encrypt(data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>, String> {
+    Cbc::new(OpenSslAes::new(key)?).encrypt(data, iv)
 }
 
-#[derive(Debug, PartialEq)]
-pub struct PaddingError(u8, u8); // expected, actual
-
-fn remove_padding(data: &[u8]) -> Result<Vec<u8>, PaddingError> {
-    if data.len() == 0 {
-        return Ok(data.to_vec());
-    }
-
-    let mut mdata = data.to_vec();
-    let mut ctr = 1u8;
-    let padding = mdata.pop().unwrap();
-
-    // Walk backwards from the end and built up the counter of how many bytes of padding we find
-    // that match what's expected.
-    while let Some(byte) = mdata.pop() {
-        // Increment counter iff it's the same, and we haven't already met the count needed
-        if byte == padding && ctr != padding {
-            ctr += 1;
-        } else {
-            // Since we pop it off in the let, but it was not padding, we need to add it back in
-            // here.
-            mdata.push(byte);
-            break;
-        }
-    }
-
-    if ctr == padding {
-        Ok(mdata)
-    } else {
-        Err(PaddingError(padding, ctr))
-    }
+pub fn decrypt(data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>, String> {
+    Cbc::new(OpenSslAes::new(key)?).decrypt(data, iv)
 }
 
 #[cfg(test)]
@@ -131,36 +72,24 @@ mod tests {
     }
 
     #[test]
-    fn remove_padding() {
-        // Valid padding of 7
-        let plaintext = b"Hello\x07\x07\x07\x07\x07\x07\x07";
-        let plaintext_no_pad = aes_cbc::remove_padding(plaintext).unwrap();
-        assert_eq!(plaintext_no_pad, b"Hello");
-
-        // Padding is 2, but there's 3 bytes of it. Should only remove two.
-        let plaintext = b"Hello\x02\x02\x02";
-        let plaintext_no_pad = aes_cbc::remove_padding(plaintext).unwrap();
-        assert_eq!(plaintext_no_pad, b"Hello\x02");
-
-        // Not enough padding
-        let plaintext = b"Hello\x07\x07\x07\x07";
-        let err = aes_cbc::remove_padding(plaintext).expect_err("expect padding error");
-        assert_eq!(err, aes_cbc::PaddingError(7, 4));
+    fn aes_cbc_always_pads() {
+        let key = b"YELLOW SUBMARINE";
+        for data in &[&b""[..], b"YELLOW SUBMARINE"] {
+            let ciphertext = aes_cbc::encrypt(data, key, key).unwrap();
+            assert_eq!(ciphertext.len(), data.len() + 16);
+            assert_eq!(aes_cbc::decrypt(&ciphertext, key, key), Ok(data.to_vec()));
+        }
     }
 
     #[test]
-    fn remove_padding_with_challenge_input() {
-        let plaintext = b"ICE ICE BABY\x04\x04\x04\x04";
-        let plaintext_no_pad = aes_cbc::remove_padding(plaintext).unwrap();
-        assert_eq!(plaintext_no_pad, b"ICE ICE BABY");
-
-        let plaintext = b"ICE ICE BABY\x05\x05\x05\x05";
-        let err = aes_cbc::remove_padding(plaintext).expect_err("expect padding error");
-        assert_eq!(err, aes_cbc::PaddingError(5, 4));
-
-        let plaintext = b"ICE ICE BABY\x01\x02\x03\x04";
-        let err = aes_cbc::remove_padding(plaintext).expect_err("expect padding error");
-        assert_eq!(err, aes_cbc::PaddingError(4, 1));
+    fn aes_cbc_decrypt_rejects_bad_input() {
+        let key = b"YELLOW SUBMARINE";
+        let ciphertext = aes_cbc::encrypt(b"Hellooooo", key, key).unwrap();
+
+        assert!(aes_cbc::decrypt(&ciphertext, key, &key[..8]).is_err());
+        assert!(aes_cbc::decrypt(&ciphertext, &key[..8], key).is_err());
+        assert!(aes_cbc::decrypt(&ciphertext[..8], key, key).is_err());
+        assert!(aes_cbc::decrypt(&ciphertext, key, &[0; 16]).is_err());
     }
 
     #[test]
@@ -1,7 +1,4 @@
 use set2::aes_cbc;
-use set2::{pkcs_7_pad};
-
-const BLOCK_SIZE: usize = 16;
 
 fn escape_input(input: &[u8]) -> Vec<u8> {
     // This is a _very_ inefficient way to do this, but should work.
@@ -11,27 +8,23 @@ fn escape_input(input: &[u8]) -> Vec<u8> {
     input.as_bytes().to_vec()
 }
 
-// Pad input to 16 byte then encrypt
-fn encrypt_with_extra(input: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
+pub fn encrypt_with_extra(input: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
     let prefix = b"comment1=cooking%20MCs;userdata=";
     let suffix = b";comment2=%20like%20a%20pound%20of%20bacon";
     let mut plaintext: Vec<u8> = vec![];
     plaintext.append(&mut prefix.to_vec());
-    let mut input = if input.len() < 16 { pkcs_7_pad(input, BLOCK_SIZE) } else { input.to_vec() };
-    input = escape_input(&input);
-    plaintext.append(&mut input);
+    plaintext.append(&mut escape_input(input));
     plaintext.append(&mut suffix.to_vec());
 
     aes_cbc::encrypt(&plaintext, key, iv).expect("encryption works")
 }
 
-fn is_admin(ciphertext: &[u8], key: &[u8], iv: &[u8]) -> bool {
+pub fn is_admin(ciphertext: &[u8], key: &[u8], iv: &[u8]) -> bool {
     let pt_bytes = aes_cbc::decrypt(&ciphertext, &key, &iv).expect("works");
     let mut pt_str = String::new();
     for byte in pt_bytes {
         pt_str.push(byte as char);
     }
-    println!("PT: {:?}", pt_str);
 
     pt_str.contains(";admin=true;")
 }
@@ -39,7 +32,7 @@ fn is_admin(ciphertext: &[u8], key: &[u8], iv: &[u8]) -> bool {
 #[cfg(test)]
 mod tests {
     use set2::cbc_bitflip;
-    use set2::mode_detection::{rand_bytes};
+    use util::{rand_bytes};
     use set2::aes_cbc;
 
     #[test]
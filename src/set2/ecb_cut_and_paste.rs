@@ -1,20 +1,12 @@
-use openssl;
-use openssl::symm;
-
-fn rand_bytes(bytes: usize) -> Vec<u8> {
-    let mut buf = vec![0; bytes];
-    openssl::rand::rand_bytes(&mut buf).unwrap();
-    buf.to_vec()
-}
+use block_cipher::{OpenSslAes};
+use modes::{Ecb};
 
 fn ecb_encrypt(input: &[u8], key: &[u8]) -> Vec<u8> {
-    let cipher = symm::Cipher::aes_128_ecb();
-    symm::encrypt(cipher, key, None, input).expect("should work")
+    Ecb::new(OpenSslAes::new(key).expect("should work")).encrypt(input)
 }
 
 fn ecb_decrypt(input: &[u8], key: &[u8]) -> Vec<u8> {
-    let cipher = symm::Cipher::aes_128_ecb();
-    symm::decrypt(cipher, key, None, input).expect("should work")
+    Ecb::new(OpenSslAes::new(key).expect("should work")).decrypt(input).expect("should work")
 }
 
 #[derive(Default)]
@@ -62,7 +54,7 @@ fn encode_cookie(profile: Profile) -> String {
     encoded
 }
 
-fn profile_for(email: &str) -> String {
+pub fn profile_for(email: &str) -> String {
     let profile = Profile {
         email: email.to_string(),
         uid: "10".to_string(),
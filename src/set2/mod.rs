@@ -1,15 +1,15 @@
 pub mod aes_cbc;
-mod mode_detection;
-mod byte_decryption;
-mod ecb_cut_and_paste;
-mod cbc_bitflip;
-mod padding_oracle;
+pub mod mode_detection;
+pub mod byte_decryption;
+pub mod ecb_cut_and_paste;
+pub mod cbc_bitflip;
+pub mod padding_oracle;
 
 // Description from RFC-2315
 //
 // The method shall be to pad the input at the trailing end with k - (l mod k) octets all having
 // value k - (l mod k), where l is the length of the input.
-fn pkcs_7_pad(input: &[u8], block_size: usize) -> Vec<u8> {
+pub fn pkcs_7_pad(input: &[u8], block_size: usize) -> Vec<u8> {
     let padding = (block_size - (input.len() % block_size)) as u8;
 
     let mut padded = input.to_vec();
@@ -19,6 +19,23 @@ fn pkcs_7_pad(input: &[u8], block_size: usize) -> Vec<u8> {
     padded
 }
 
+#[derive(Debug, PartialEq)]
+pub struct PaddingError(u8, u8); // expected, actual
+
+// Strip padding added by pkcs_7_pad. Every padding byte is checked, and there's never more than a
+// block of it.
+pub fn pkcs_7_unpad(input: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+    let padding = *input.last().ok_or(PaddingError(0, 0))?;
+
+    // Walk backwards from the end counting how many bytes match the padding we expect
+    let found = input.iter().rev().take(padding as usize).take_while(|&&byte| byte == padding).count() as u8;
+
+    if padding == 0 || padding as usize > block_size || found != padding {
+        return Err(PaddingError(padding, found));
+    }
+    Ok(input[..input.len() - padding as usize].to_vec())
+}
+
 #[cfg(test)]
 mod tests {
     use set2;
@@ -36,4 +53,37 @@ mod tests {
         expected_output.push(4);
         assert_eq!(output, expected_output);
     }
+
+    #[test]
+    fn pkcs_7_unpad() {
+        // Valid padding of 7
+        let plaintext = b"Hello\x07\x07\x07\x07\x07\x07\x07";
+        assert_eq!(set2::pkcs_7_unpad(plaintext, 16).unwrap(), b"Hello");
+
+        // Padding is 2, but there's 3 bytes of it. Should only remove two.
+        let plaintext = b"Hello\x02\x02\x02";
+        assert_eq!(set2::pkcs_7_unpad(plaintext, 16).unwrap(), b"Hello\x02");
+
+        // Not enough padding
+        let plaintext = b"Hello\x07\x07\x07\x07";
+        assert_eq!(set2::pkcs_7_unpad(plaintext, 16), Err(set2::PaddingError(7, 4)));
+
+        // Never empty, never zero and never more than a block
+        assert_eq!(set2::pkcs_7_unpad(b"", 16), Err(set2::PaddingError(0, 0)));
+        assert_eq!(set2::pkcs_7_unpad(b"Hello\x00", 16), Err(set2::PaddingError(0, 0)));
+        assert_eq!(set2::pkcs_7_unpad(&[17; 17], 16), Err(set2::PaddingError(17, 17)));
+        assert_eq!(set2::pkcs_7_unpad(&[16; 16], 16), Ok(vec![]));
+    }
+
+    #[test]
+    fn pkcs_7_unpad_with_challenge_input() {
+        let plaintext = b"ICE ICE BABY\x04\x04\x04\x04";
+        assert_eq!(set2::pkcs_7_unpad(plaintext, 16).unwrap(), b"ICE ICE BABY");
+
+        let plaintext = b"ICE ICE BABY\x05\x05\x05\x05";
+        assert_eq!(set2::pkcs_7_unpad(plaintext, 16), Err(set2::PaddingError(5, 4)));
+
+        let plaintext = b"ICE ICE BABY\x01\x02\x03\x04";
+        assert_eq!(set2::pkcs_7_unpad(plaintext, 16), Err(set2::PaddingError(4, 1)));
+    }
 }
@@ -1,23 +1,12 @@
-use openssl;
-use openssl::symm;
-use rand::prelude::{thread_rng, Rng, random as randbool};
+use rand::prelude::{random as randbool};
+use block_cipher::{OpenSslAes};
+use modes::{Ecb};
 use set2::aes_cbc;
 use set1::{bytes_to_16byte_blocks, num_duplicate_blocks};
-
-pub fn rand_bytes(bytes: usize) -> Vec<u8> {
-    let mut buf = vec![0; bytes];
-    openssl::rand::rand_bytes(&mut buf).unwrap();
-    buf.to_vec()
-}
-
-// Generate number in range from min to max inclusive
-pub fn rand_in_range(min: usize, max: usize) -> usize {
-    let mut rng = thread_rng();
-    rng.gen_range(min, max + 1)
-}
+use util::{rand_bytes, rand_in_range};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
-enum Mode { ECB, CBC }
+pub enum Mode { ECB, CBC }
 
 impl Mode {
     fn random() -> Mode {
@@ -30,20 +19,15 @@ impl Mode {
     fn encrypt(&self, input: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
         match self {
             Mode::CBC => aes_cbc::encrypt(input, key, iv).expect("should work"),
-            Mode::ECB => {
-                let cipher = symm::Cipher::aes_128_ecb();
-                symm::encrypt(cipher, key, None, input).expect("should work")
-            }
+            Mode::ECB => Ecb::new(OpenSslAes::new(key).expect("should work")).encrypt(input),
         }
     }
 }
 
 
-fn detect_encryption_mode(ciphertext: &[u8]) -> Mode {
+pub fn detect_encryption_mode(ciphertext: &[u8]) -> Mode {
     let dup_blocks = num_duplicate_blocks(&bytes_to_16byte_blocks(&ciphertext));
-    println!("CT: {:?}, {}", ciphertext, ciphertext.len());
     if dup_blocks > 1 {
-        println!("Dups: {:?}", dup_blocks);
         Mode::ECB
     } else {
         Mode::CBC
@@ -53,7 +37,7 @@ fn detect_encryption_mode(ciphertext: &[u8]) -> Mode {
 // Randomly encrypt input using either AES-128-ECB or AES-128-CBC.
 //
 // Returns the mode used so that we can write tests to verify detection.
-fn encryption_oracle(input: &[u8]) -> (Mode, Vec<u8>) {
+pub fn encryption_oracle(input: &[u8]) -> (Mode, Vec<u8>) {
     let mut rand_input = vec![];
     rand_input.append(&mut rand_bytes(rand_in_range(5, 10)));
     rand_input.append(&mut input.to_vec());
@@ -1,17 +1,12 @@
 use set2::aes_cbc;
-use set2::{pkcs_7_pad};
 
 const BLOCK_SIZE: usize = 16;
 
-// Encrypt input that's always padded to at least a block. I think this part is needed so that
-// there's always at least 2 blocks.
-fn encrypt(input: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
-    let pt = if input.len() < 16 { pkcs_7_pad(input, BLOCK_SIZE) } else { input.to_vec() };
-
-    aes_cbc::encrypt(&pt, key, iv).expect("encryption works")
+pub fn encrypt(input: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
+    aes_cbc::encrypt(input, key, iv).expect("encryption works")
 }
 
-fn is_valid_padding(ciphertext: &[u8], key: &[u8], iv: &[u8]) -> bool {
+pub fn is_valid_padding(ciphertext: &[u8], key: &[u8], iv: &[u8]) -> bool {
     let decryption = aes_cbc::decrypt(&ciphertext, &key, &iv);
     match decryption {
         Ok(_) => true,
@@ -33,7 +28,7 @@ fn decrypt_byte(prev_block: &[u8], target_block: &[u8], target_byte: usize, key:
         ct.append(&mut target_block.to_vec());
         let is_valid = is_valid_padding(&ct, key, iv);
 
-        if is_valid {
+        if is_valid && !is_longer_padding(&ct, target_byte, key, iv) {
             //   Once it's valid, we generally know P2'[15] is 1. (This isn't 100% though?)
             // Once we know the C1'[15] that leads to valid padding...
             //   C1'[15] ^ P2'[15] = I2[15]
@@ -50,6 +45,19 @@ fn decrypt_byte(prev_block: &[u8], target_block: &[u8], target_byte: usize, key:
     }
 }
 
+// Valid padding may be longer than the one we're aiming for, e.g. when looking for \x01 the block
+// could happen to end in \x02\x02. Changing the byte before the target rules this out since it
+// would break the longer padding but not ours.
+fn is_longer_padding(ct: &[u8], target_byte: usize, key: &[u8], iv: &[u8]) -> bool {
+    if target_byte == 0 {
+        return false;
+    }
+
+    let mut ct = ct.to_vec();
+    ct[target_byte - 1] ^= 1;
+    !is_valid_padding(&ct, key, iv)
+}
+
 fn decrypt_block(prev_block: &[u8], target_block: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
     let mut pt: Vec<u8> = vec![0; 16];
     let mut ibytes: Vec<u8> = vec![0; 16];
@@ -98,7 +106,7 @@ fn get_block(ciphertext: &[u8], block_num: usize) -> &[u8] {
 // Repeat until block is decrypted.
 //
 // To do more blocks, start at the end and chop blocks off once we know the PT.
-fn attack(ciphertext: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
+pub fn attack(ciphertext: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
     let num_blocks = ciphertext.len() / 16;
 
     let mut pt: Vec<u8> = vec![];
@@ -115,7 +123,7 @@ fn attack(ciphertext: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
 #[cfg(test)]
 mod tests {
     use set2::padding_oracle;
-    use set2::mode_detection::{rand_bytes};
+    use util::{rand_bytes};
     use base64::decode as base64decode;
 
     #[test]
@@ -134,9 +142,6 @@ mod tests {
     }
 
     #[test]
-    // NOTE: This test sometimes fails and I'm not entirely sure why. Weirdly, it seems to be
-    // something with the key/iv since that's the only randomness in this test. The failure
-    // surfaces as an overflow error in the loop decrypting a byte.
     fn padding_oracle_decrypt() {
         let key = rand_bytes(16);
         let iv = rand_bytes(16);
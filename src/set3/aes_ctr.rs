@@ -1,37 +1,16 @@
-use set2::aes_cbc::{openssl_ecb_encrypt_block};
+use block_cipher::{OpenSslAes};
+use modes::{Ctr};
 
-const BYTES_IN_BLOCK: usize = 16;
-
-// Encrypt with AES-CBC. Uses 16 byte blocks with a 64 bit nonce and incrementing counter.
+// Encrypt with AES-CTR. Uses 16 byte blocks with a 64 bit nonce and incrementing counter, both
+// little-endian.
 pub fn encrypt(input: &[u8], key: &[u8], nonce: u64) -> Vec<u8> {
-    let mut output: Vec<u8> = vec![];
-    let nonce_bytes = nonce.to_le_bytes();
-    let mut ctr: u64 = 0;
-
-    for input_block in input.chunks(BYTES_IN_BLOCK) {
-        let mut input_pt: Vec<u8> = vec![];
-        input_pt.append(&mut nonce_bytes.to_vec());
-        input_pt.append(&mut ctr.to_le_bytes().to_vec());
-
-        let keystream = openssl_ecb_encrypt_block(&input_pt, key).expect("encrypted");
-
-        let mut ct_block: Vec<u8> = input_block
-            .to_vec()
-            .iter()
-            .zip(keystream.iter())
-            .map(|(left, right)| left ^ right)
-            .collect();
-
-        output.append(&mut ct_block);
-        ctr += 1;
-    }
-
-    output
+    let ctr = Ctr::new(OpenSslAes::new(key).expect("valid key"));
+    ctr.encrypt(input, &nonce.to_le_bytes()).expect("nonce is half a block")
 }
 
-// Decrypt a ciphertext encrypted with AES-CBC. This is equivalent to calling encrypt with the same
+// Decrypt a ciphertext encrypted with AES-CTR. This is equivalent to calling encrypt with the same
 // arguments but is provided for ease of readability.
-fn decrypt(ciphertext: &[u8], key: &[u8], nonce: u64) -> Vec<u8> {
+pub fn decrypt(ciphertext: &[u8], key: &[u8], nonce: u64) -> Vec<u8> {
     encrypt(ciphertext, key, nonce)
 }
 
@@ -1,10 +1,33 @@
+use set1::{transpose, decrypt_single_byte_xor_with_score_bytes};
+
+// Recover the keystream shared by ciphertexts encrypted under the same key and nonce. Since every
+// ciphertext is XOR'd with the same keystream, the bytes at each index are effectively single-byte
+// XOR encrypted and can be solved with frequency analysis. Only as much keystream as the shortest
+// ciphertext can be recovered. Errors if any index can't be solved, rather than leaving a gap that
+// would shift the rest of the keystream.
+pub fn recover_keystream(ciphertexts: &[Vec<u8>]) -> Result<Vec<u8>, String> {
+    let min_len = ciphertexts.iter().map(|ct| ct.len()).min().unwrap_or(0);
+    let truncated: Vec<Vec<u8>> = ciphertexts.iter().map(|ct| ct[0..min_len].to_vec()).collect();
+    let transposed = transpose(truncated);
+
+    let mut keystream: Vec<u8> = vec![];
+    for (i, block) in transposed.iter().take(min_len).enumerate() {
+        match decrypt_single_byte_xor_with_score_bytes(block) {
+            Some((_, c, _)) => keystream.push(c as u8),
+            None => return Err(format!("Unable to recover keystream byte {}", i)),
+        }
+    }
+
+    Ok(keystream)
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs::File;
     use std::io::Read;
     use base64::decode as base64decode;
     use set3::aes_ctr::{encrypt};
-    use set1::{transpose, decrypt_single_byte_xor_with_score_bytes};
+    use set3::aes_ctr_nonce_reuse::{recover_keystream};
 
     #[test]
     fn ctr_nonce_reuse_19() {
@@ -51,31 +74,20 @@ mod tests {
             print_string(&pt);
             encrypt(&pt, key, nonce)
         }).collect();
-        let mut min_len = 9999999;
-        for content in ciphertexts.clone() {
-            if content.len() < min_len {
-                min_len = content.len();
-            }
-        }
-        let ciphertexts: Vec<Vec<u8>> = ciphertexts.iter().map(|ct| ct[0..min_len].to_vec()).collect();
-        // NOTE: transpose doesn't currently work if len is longer than the block size
-        let transposed = transpose(ciphertexts[0..min_len].to_vec());
+        let key = recover_keystream(&ciphertexts).unwrap();
+        let min_len = ciphertexts.iter().map(|ct| ct.len()).min().unwrap();
+        assert_eq!(key.len(), min_len);
 
-        let mut key: Vec<u8> = vec![];
-        for block in &transposed {
-            let res = decrypt_single_byte_xor_with_score_bytes(&block);
-            if let Some((score, c, plaintext)) = &res {
-                key.push(*c as u8);
-            }
-        }
-
-        for ct in ciphertexts {
-            let content = xor(&ct, &key);
-            print_chars(&content);
+        // Every line starts with a capital letter, and scoring that column on its own letter
+        // frequencies picks a key byte that turns them into other capitals, so the first byte isn't
+        // checked. Every other byte of every line has to decrypt exactly.
+        for (line, ct) in file.lines().zip(ciphertexts.iter()) {
+            let pt = base64decode(line).unwrap();
+            let recovered = xor(ct, &key);
+            print_chars(&recovered);
+            assert_eq!(recovered.len(), key.len());
+            assert_eq!(&recovered[1..], &pt[1..key.len()]);
         }
-
-        // We just mark this as passed since we manually verify it works.
-        assert!(true);
     }
 
     // Helper to read a file from disk unsafely and strip newlines
@@ -1,2 +1,2 @@
-mod aes_ctr;
-mod aes_ctr_nonce_reuse;
+pub mod aes_ctr;
+pub mod aes_ctr_nonce_reuse;
@@ -0,0 +1,258 @@
+use std::str;
+use num_bigint::{BigInt};
+use num_traits::*;
+use finite_field::{Field};
+use block_cipher::{OpenSslAes};
+use modes::{Cbc};
+use util::{rand_bytes};
+use set5::diffie_hellman::{session_key, DiffieHellman};
+
+// Cryptopals challenges 34 and 35: man-in-the-middle attacks on unauthenticated Diffie-Hellman.
+//
+// The protocol runs over a simulated channel: Alice proposes a group, sends her public key, gets
+// Bob's back and then sends a message encrypted under the session key, which Bob echoes. Nothing
+// is authenticated, so Mallory sitting in the middle can rewrite any of it:
+//
+// - Parameter injection: replacing both public keys with p makes both secrets p^x mod p = 0.
+// - Malicious g: handing Bob g = 1, p or p - 1 makes his public key 1, 0 or +-1, which fixes
+//   Alice's secret to 1, 0 or +-1 in turn.
+//
+// Either way Mallory knows the secrets and can read and relay every message.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Negotiate { p: BigInt, g: BigInt },
+    Ack,
+    PublicKey(BigInt),
+    Encrypted { ciphertext: Vec<u8>, iv: Vec<u8> },
+}
+
+/// One side of the channel, which replies to each message it's sent
+pub trait Endpoint {
+    fn handle(&mut self, message: Message) -> Result<Message, String>;
+}
+
+/// Accepts whatever group he's offered and echoes back messages
+#[derive(Default)]
+pub struct Bob {
+    dh: Option<DiffieHellman>,
+    key: Option<Vec<u8>>,
+    pub received: Vec<Vec<u8>>,
+}
+
+impl Bob {
+    pub fn new() -> Self {
+        Bob::default()
+    }
+}
+
+impl Endpoint for Bob {
+    fn handle(&mut self, message: Message) -> Result<Message, String> {
+        match message {
+            Message::Negotiate { p, g } => {
+                self.dh = Some(DiffieHellman::new(p, g));
+                Ok(Message::Ack)
+            }
+            Message::PublicKey(a) => {
+                let dh = self.dh.as_ref().ok_or_else(|| String::from("No group negotiated"))?;
+                let (b, big_b) = dh.keypair();
+                self.key = Some(session_key(&dh.shared_secret(&b, &a)));
+                Ok(Message::PublicKey(big_b.value))
+            }
+            Message::Encrypted { ciphertext, iv } => {
+                let key = self.key.as_ref().ok_or_else(|| String::from("No session key"))?;
+                let message = decrypt(&ciphertext, key, &iv)?;
+                self.received.push(message.clone());
+                encrypt(&message, key)
+            }
+            Message::Ack => Err(String::from("Unexpected message")),
+        }
+    }
+}
+
+/// Run the protocol as Alice with the given group, returning the echo of message she gets back
+pub fn alice<E: Endpoint>(dh: &DiffieHellman, message: &[u8], bob: &mut E) -> Result<Vec<u8>, String> {
+    let negotiate = Message::Negotiate { p: dh.p_ref().clone(), g: dh.g_ref().value.clone() };
+    if bob.handle(negotiate)? != Message::Ack {
+        return Err(String::from("Group wasn't accepted"));
+    }
+
+    let (a, big_a) = dh.keypair();
+    let key = match bob.handle(Message::PublicKey(big_a.value))? {
+        Message::PublicKey(b) => session_key(&dh.shared_secret(&a, &b)),
+        _ => return Err(String::from("Expected a public key")),
+    };
+
+    match bob.handle(encrypt(message, &key)?)? {
+        Message::Encrypted { ciphertext, iv } => decrypt(&ciphertext, &key, &iv),
+        _ => Err(String::from("Expected an encrypted message")),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Attack {
+    ParameterInjection,
+    GEqualsOne,
+    GEqualsP,
+    GEqualsPMinusOne,
+}
+
+/// Sits between Alice and Bob, rewriting the key exchange and relaying every message after reading
+/// it
+pub struct Mallory<E: Endpoint> {
+    bob: E,
+    attack: Attack,
+    field: Option<Field>,
+    alice_keys: Vec<Vec<u8>>,
+    bob_key: Vec<u8>,
+    pub intercepted: Vec<Vec<u8>>,
+}
+
+impl<E: Endpoint> Mallory<E> {
+    pub fn new(bob: E, attack: Attack) -> Self {
+        Mallory { bob, attack, field: None, alice_keys: vec![], bob_key: vec![], intercepted: vec![] }
+    }
+
+    pub fn bob_ref(&self) -> &E {
+        &self.bob
+    }
+}
+
+impl<E: Endpoint> Endpoint for Mallory<E> {
+    fn handle(&mut self, message: Message) -> Result<Message, String> {
+        match message {
+            Message::Negotiate { p, g } => {
+                let g = match self.attack {
+                    Attack::ParameterInjection => g,
+                    Attack::GEqualsOne => BigInt::one(),
+                    Attack::GEqualsP => p.clone(),
+                    Attack::GEqualsPMinusOne => &p - BigInt::one(),
+                };
+                self.field = Some(Field::new(p.clone()));
+                self.bob.handle(Message::Negotiate { p, g })
+            }
+            Message::PublicKey(_) => {
+                let field = self.field.clone().ok_or_else(|| String::from("No group negotiated"))?;
+                let p = field.p_ref().clone();
+
+                // Send Bob a public key that fixes his secret to 0 or 1
+                let (fake_a, bob_secret) = match self.attack {
+                    Attack::ParameterInjection | Attack::GEqualsP => (p.clone(), BigInt::zero()),
+                    Attack::GEqualsOne | Attack::GEqualsPMinusOne => (BigInt::one(), BigInt::one()),
+                };
+                self.bob_key = session_key(&field.elem(bob_secret));
+
+                let b = match self.bob.handle(Message::PublicKey(fake_a))? {
+                    Message::PublicKey(b) => b,
+                    _ => return Err(String::from("Expected a public key")),
+                };
+
+                // Bob's public key is now 1, 0 or +-1 so Alice's secret B^a is too. With p - 1 it
+                // depends on the parity of a, so both are kept and Alice's first message picks one.
+                let (b, alice_secrets) = match self.attack {
+                    Attack::ParameterInjection => (p.clone(), vec![BigInt::zero()]),
+                    Attack::GEqualsOne => (b, vec![BigInt::one()]),
+                    Attack::GEqualsP => (b, vec![BigInt::zero()]),
+                    Attack::GEqualsPMinusOne if b.is_one() => (b, vec![BigInt::one()]),
+                    Attack::GEqualsPMinusOne => (b, vec![BigInt::one(), &p - BigInt::one()]),
+                };
+                self.alice_keys = alice_secrets.into_iter().map(|s| session_key(&field.elem(s))).collect();
+
+                Ok(Message::PublicKey(b))
+            }
+            Message::Encrypted { ciphertext, iv } => {
+                let (alice_key, message) = decrypt_with_any(&ciphertext, &self.alice_keys, &iv)?;
+                self.intercepted.push(message.clone());
+
+                let reply = match self.bob.handle(encrypt(&message, &self.bob_key)?)? {
+                    Message::Encrypted { ciphertext, iv } => decrypt(&ciphertext, &self.bob_key, &iv)?,
+                    _ => return Err(String::from("Expected an encrypted message")),
+                };
+                self.intercepted.push(reply.clone());
+                encrypt(&reply, &alice_key)
+            }
+            Message::Ack => Err(String::from("Unexpected message")),
+        }
+    }
+}
+
+// Decrypt with whichever of keys is right. A wrong key still gives valid padding about one time in
+// 256, so when more than one works the one that gives text wins.
+fn decrypt_with_any(ciphertext: &[u8], keys: &[Vec<u8>], iv: &[u8]) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let candidates: Vec<(Vec<u8>, Vec<u8>)> = keys.iter()
+        .filter_map(|key| decrypt(ciphertext, key, iv).ok().map(|m| (key.clone(), m)))
+        .collect();
+
+    candidates.iter()
+        .find(|(_, message)| str::from_utf8(message).is_ok())
+        .or_else(|| candidates.first())
+        .cloned()
+        .ok_or_else(|| String::from("None of the predicted keys decrypt the message"))
+}
+
+fn encrypt(message: &[u8], key: &[u8]) -> Result<Message, String> {
+    let iv = rand_bytes(16);
+    let ciphertext = Cbc::new(OpenSslAes::new(key)?).encrypt(message, &iv)?;
+    Ok(Message::Encrypted { ciphertext, iv })
+}
+
+fn decrypt(ciphertext: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>, String> {
+    Cbc::new(OpenSslAes::new(key)?).decrypt(ciphertext, iv)
+}
+
+#[cfg(test)]
+mod tests {
+    use set5::dh_mitm::*;
+
+    const MESSAGE: &[u8] = b"Ice ice baby, too cold";
+
+    #[test]
+    fn dh_mitm_honest_channel() {
+        let mut bob = Bob::new();
+        assert_eq!(alice(&DiffieHellman::nist(), MESSAGE, &mut bob), Ok(MESSAGE.to_vec()));
+        assert_eq!(bob.received, vec![MESSAGE.to_vec()]);
+
+        // Whole blocks and empty messages are padded too
+        for message in &[&b"YELLOW SUBMARINE"[..], b""] {
+            assert_eq!(alice(&DiffieHellman::nist(), message, &mut Bob::new()), Ok(message.to_vec()));
+        }
+    }
+
+    #[test]
+    fn dh_mitm_parameter_injection_34() {
+        let mut mallory = Mallory::new(Bob::new(), Attack::ParameterInjection);
+        assert_eq!(alice(&DiffieHellman::nist(), MESSAGE, &mut mallory), Ok(MESSAGE.to_vec()));
+        assert_eq!(mallory.intercepted, vec![MESSAGE.to_vec(), MESSAGE.to_vec()]);
+        assert_eq!(mallory.bob_ref().received, vec![MESSAGE.to_vec()]);
+    }
+
+    #[test]
+    fn dh_mitm_malicious_g_35() {
+        for attack in &[Attack::GEqualsOne, Attack::GEqualsP, Attack::GEqualsPMinusOne] {
+            let mut mallory = Mallory::new(Bob::new(), *attack);
+            assert_eq!(alice(&DiffieHellman::nist(), MESSAGE, &mut mallory), Ok(MESSAGE.to_vec()), "{:?}", attack);
+            assert_eq!(mallory.intercepted[0], MESSAGE.to_vec(), "{:?}", attack);
+        }
+    }
+
+    #[test]
+    fn dh_mitm_wrong_key_with_valid_padding() {
+        let field = Field::new(DiffieHellman::nist().p_ref().clone());
+        let p = field.p_ref().clone();
+        let (wrong, right) = (session_key(&field.elem(BigInt::one())), session_key(&field.elem(&p - BigInt::one())));
+
+        // Find a ciphertext that also has valid padding under the wrong key, and check the key
+        // that gives text is still picked whichever order they're tried in
+        let (ciphertext, iv) = loop {
+            let iv = rand_bytes(16);
+            let ciphertext = Cbc::new(OpenSslAes::new(&right).unwrap()).encrypt(MESSAGE, &iv).unwrap();
+            if decrypt(&ciphertext, &wrong, &iv).is_ok() {
+                break (ciphertext, iv);
+            }
+        };
+        let keys = vec![wrong.clone(), right.clone()];
+        assert_eq!(decrypt_with_any(&ciphertext, &keys, &iv), Ok((right.clone(), MESSAGE.to_vec())));
+        let keys = vec![right.clone(), wrong];
+        assert_eq!(decrypt_with_any(&ciphertext, &keys, &iv), Ok((right, MESSAGE.to_vec())));
+    }
+}
@@ -0,0 +1,85 @@
+use num_bigint::{BigInt, RandBigInt};
+use num_traits::*;
+use rand::{thread_rng};
+use finite_field::{Field, FieldElement};
+use util::{sha256};
+
+// Cryptopals challenge 33: Diffie-Hellman over the multiplicative group of F_p.
+//
+// Alice and Bob pick secrets a and b and exchange A = g^a and B = g^b. Both can compute
+// s = B^a = A^b = g^ab but someone who only saw A and B has to solve a discrete log to get it.
+
+/// The 1536-bit MODP group from RFC 3526 that the challenges use, with g = 2
+pub const NIST_P: &str = "ffffffffffffffffc90fdaa22168c234c4c6628b80dc1cd129024e088a67cc74020bbea63b139b22514a08798e3404ddef9519b3cd3a431b302b0a6df25f14374fe1356d6d51c245e485b576625e7ec6f44c42e9a637ed6b0bff5cb6f406b7edee386bfb5a899fa5ae9f24117c4b1fe649286651ece45b3dc2007cb8a163bf0598da48361c55d39a69163fa8fd24cf5f83655d23dca3ad961c62f356208552bb9ed529077096966d670c354e4abc9804f1746c08ca237327ffffffffffffffff";
+pub const NIST_G: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffieHellman {
+    field: Field,
+    g: FieldElement,
+}
+
+impl DiffieHellman {
+    pub fn new<T: Into<BigInt>>(p: T, g: T) -> Self {
+        let field = Field::new(p);
+        DiffieHellman { g: field.elem(g), field }
+    }
+
+    pub fn nist() -> Self {
+        let p = BigInt::parse_bytes(NIST_P.as_bytes(), 16).unwrap();
+        DiffieHellman::new(p, BigInt::from(NIST_G))
+    }
+
+    pub fn p_ref(&self) -> &BigInt {
+        self.field.p_ref()
+    }
+
+    pub fn g_ref(&self) -> &FieldElement {
+        &self.g
+    }
+
+    /// Random private key in [1, p - 1) along with its public key
+    pub fn keypair(&self) -> (BigInt, FieldElement) {
+        let private_key = thread_rng().gen_bigint_range(&BigInt::one(), &(self.p_ref() - BigInt::one()));
+        let public_key = self.public_key(&private_key);
+        (private_key, public_key)
+    }
+
+    pub fn public_key(&self, private_key: &BigInt) -> FieldElement {
+        self.g.pow(private_key)
+    }
+
+    /// s = (their public key)^(our private key)
+    pub fn shared_secret(&self, private_key: &BigInt, public_key: &BigInt) -> FieldElement {
+        self.field.elem(public_key.clone()).pow(private_key)
+    }
+}
+
+/// 128-bit key derived from a shared secret: the first 16 bytes of its SHA-256
+pub fn session_key(secret: &FieldElement) -> Vec<u8> {
+    let (_, bytes) = secret.value.to_bytes_be();
+    sha256(&bytes)[..16].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use set5::diffie_hellman::*;
+
+    #[test]
+    fn diffie_hellman_33() {
+        let dh = DiffieHellman::new(37, 5);
+        let (a, b) = (BigInt::from(6), BigInt::from(15));
+        let (big_a, big_b) = (dh.public_key(&a), dh.public_key(&b));
+        assert_eq!(big_a.value, BigInt::from(11));
+        assert_eq!(big_b.value, BigInt::from(29));
+        assert_eq!(dh.shared_secret(&a, &big_b.value), dh.shared_secret(&b, &big_a.value));
+        assert_eq!(dh.shared_secret(&a, &big_b.value).value, BigInt::from(36));
+
+        let dh = DiffieHellman::nist();
+        let (a, big_a) = dh.keypair();
+        let (b, big_b) = dh.keypair();
+        let s = dh.shared_secret(&a, &big_b.value);
+        assert_eq!(s, dh.shared_secret(&b, &big_a.value));
+        assert_eq!(session_key(&s).len(), 16);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod rsa_broadcast;
+pub mod diffie_hellman;
+pub mod dh_mitm;
+pub mod srp;
+pub mod simple_srp;
@@ -0,0 +1,51 @@
+use num_bigint::{BigInt};
+use num_traits::*;
+use discrete_log::{crt};
+use rsa::{PublicKey};
+
+// Cryptopals challenge 40: e=3 RSA broadcast attack (Håstad).
+//
+// Encrypting the same unpadded message to three recipients with e = 3 gives m^3 mod n_i for three
+// coprime moduli. The CRT combines them into m^3 mod n_1 n_2 n_3, and since m < n_i that's just m^3
+// over the integers, so an integer cube root recovers m without factoring anything.
+
+/// Recover m from three encryptions of it under different e = 3 keys
+pub fn broadcast_attack(ciphertexts: &[(BigInt, PublicKey)]) -> Result<BigInt, String> {
+    let e = BigInt::from(3);
+    if ciphertexts.len() != 3 {
+        return Err(format!("Need 3 ciphertexts, got {}", ciphertexts.len()));
+    }
+    if ciphertexts.iter().any(|(_, key)| key.e != e) {
+        return Err(String::from("Every key must use e = 3"));
+    }
+
+    let residues: Vec<(BigInt, BigInt)> = ciphertexts.iter().map(|(c, key)| (c.clone(), key.n.clone())).collect();
+    let (cubed, _) = crt(&residues).ok_or_else(|| String::from("Moduli aren't coprime"))?;
+
+    let m = cubed.cbrt();
+    if m.pow(3usize) != cubed {
+        return Err(String::from("Combined ciphertext isn't a perfect cube"));
+    }
+
+    Ok(m)
+}
+
+#[cfg(test)]
+mod tests {
+    use set5::rsa_broadcast::*;
+    use rsa::{os2ip, PrivateKey};
+
+    #[test]
+    fn rsa_broadcast_40() {
+        let m = os2ip(b"Cooking MC's like a pound of bacon");
+        let e = BigInt::from(3);
+
+        let ciphertexts: Vec<(BigInt, PublicKey)> = (0..3).map(|_| {
+            let key = PrivateKey::generate(512, &e).unwrap().public_key();
+            (key.encrypt(&m).unwrap(), key)
+        }).collect();
+
+        assert_eq!(broadcast_attack(&ciphertexts), Ok(m));
+        assert!(broadcast_attack(&ciphertexts[..2]).is_err());
+    }
+}
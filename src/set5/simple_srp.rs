@@ -0,0 +1,119 @@
+use num_bigint::{BigInt, RandBigInt};
+use num_integer::{Integer};
+use num_traits::*;
+use rand::{thread_rng};
+use util::{rand_bytes};
+use set5::diffie_hellman::{DiffieHellman};
+use set5::srp::{private_key, proof, session_key};
+use util::{constant_time_eq};
+
+// Cryptopals challenge 38: offline dictionary attack on simplified SRP.
+//
+// Simplified SRP drops the kv blinding and sends a random u:
+//
+//   C -> S  I, A = g^a
+//   S -> C  salt, B = g^b, u
+//   C       S = B^(a + ux)
+//   S       S = (Av^u)^b
+//   C -> S  HMAC(H(S), salt)
+//
+// Without the verifier mixed into B, a fake server can pick b = 1 and u = 1. The client's proof is
+// then keyed by S = g^(a + x) = A g^x, and every password guess can be checked against it offline
+// with one exponentiation and no further contact with the client.
+
+pub struct Server {
+    group: DiffieHellman,
+    salt: Vec<u8>,
+    verifier: BigInt,
+    session_proof: Option<Vec<u8>>,
+}
+
+impl Server {
+    pub fn new(password: &[u8]) -> Self {
+        let group = DiffieHellman::nist();
+        let salt = rand_bytes(16);
+        let verifier = group.public_key(&private_key(&salt, password)).value;
+        Server { group, salt, verifier, session_proof: None }
+    }
+
+    /// Handle the client's A, replying with (salt, B, u)
+    pub fn start(&mut self, a: &BigInt) -> (Vec<u8>, BigInt, BigInt) {
+        let n = self.group.p_ref();
+        let (b, big_b) = self.group.keypair();
+        let u = thread_rng().gen_bigint_range(&BigInt::zero(), &(BigInt::one() << 128));
+
+        // S = (A v^u)^b
+        let base = (a * self.group.shared_secret(&u, &self.verifier).value).mod_floor(n);
+        let key = session_key(&self.group.shared_secret(&b, &base).value);
+        self.session_proof = Some(proof(&key, &self.salt));
+
+        (self.salt.clone(), big_b.value, u)
+    }
+
+    pub fn verify(&self, client_proof: &[u8]) -> bool {
+        self.session_proof.as_ref().is_some_and(|p| constant_time_eq(p, client_proof))
+    }
+}
+
+pub struct Client {
+    group: DiffieHellman,
+    password: Vec<u8>,
+    a: BigInt,
+    public_key: BigInt,
+}
+
+impl Client {
+    pub fn new(password: &[u8]) -> Self {
+        let group = DiffieHellman::nist();
+        let (a, public_key) = group.keypair();
+        Client { group, password: password.to_vec(), a, public_key: public_key.value }
+    }
+
+    pub fn public_key(&self) -> &BigInt {
+        &self.public_key
+    }
+
+    /// Handle the server's (salt, B, u), returning the proof to send back
+    pub fn finish(&self, salt: &[u8], big_b: &BigInt, u: &BigInt) -> Vec<u8> {
+        // S = B^(a + ux)
+        let x = private_key(salt, &self.password);
+        let secret = self.group.shared_secret(&(&self.a + u * x), big_b);
+        proof(&session_key(&secret.value), salt)
+    }
+}
+
+pub fn login(client: &Client, server: &mut Server) -> bool {
+    let (salt, big_b, u) = server.start(client.public_key());
+    server.verify(&client.finish(&salt, &big_b, &u))
+}
+
+/// Pose as the server with b = 1 and u = 1, then find the client's password in dictionary offline
+pub fn crack_password(client: &Client, dictionary: &[&str]) -> Option<String> {
+    let group = DiffieHellman::nist();
+    let salt = rand_bytes(16);
+    let a = client.public_key();
+    let client_proof = client.finish(&salt, &group.g_ref().value, &BigInt::one());
+
+    // S = g^(a + x) = A g^x
+    dictionary.iter().find(|password| {
+        let x = private_key(&salt, password.as_bytes());
+        let secret = (a * group.public_key(&x).value).mod_floor(group.p_ref());
+        proof(&session_key(&secret), &salt) == client_proof
+    }).map(|password| password.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use set5::simple_srp::*;
+
+    #[test]
+    fn simple_srp_38() {
+        let mut server = Server::new(b"sunshine");
+        assert!(login(&Client::new(b"sunshine"), &mut server));
+        assert!(!login(&Client::new(b"moonshine"), &mut server));
+
+        let dictionary = vec!["123456", "password", "qwerty", "letmein", "dragon", "sunshine", "monkey", "football"];
+        assert_eq!(crack_password(&Client::new(b"sunshine"), &dictionary), Some(String::from("sunshine")));
+        assert_eq!(crack_password(&Client::new(b"Tr0ub4dor&3"), &dictionary), None);
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::{HashMap};
+use num_bigint::{BigInt};
+use num_integer::{Integer};
+use num_traits::*;
+use util::{rand_bytes};
+use set5::diffie_hellman::{DiffieHellman};
+use util::{constant_time_eq, hmac_sha256, sha256, sha256_bigint};
+
+// Cryptopals challenges 36 and 37: Secure Remote Password.
+//
+// The server stores a verifier v = g^x where x = H(salt || password) instead of the password. A
+// login is a Diffie-Hellman exchange where the server's public key is blinded by v, so both sides
+// only get the same secret if the client knows x:
+//
+//   C -> S  I, A = g^a
+//   S -> C  salt, B = kv + g^b
+//   both    u = H(A || B)
+//   C       S = (B - kg^x)^(a + ux)
+//   S       S = (Av^u)^b
+//   C -> S  HMAC(H(S), salt)
+//
+// A client that sends A = 0 (or any multiple of N) forces the server's secret to 0, so it can log in
+// without the password. Servers have to reject those.
+
+// Multiplier k from SRP-6a, fixed as in the challenge
+const K: u32 = 3;
+
+/// x = H(salt || password)
+pub fn private_key(salt: &[u8], password: &[u8]) -> BigInt {
+    let mut data = salt.to_vec();
+    data.extend_from_slice(password);
+    sha256_bigint(&data)
+}
+
+/// Key from the shared secret: H(S)
+pub fn session_key(secret: &BigInt) -> Vec<u8> {
+    sha256(&int_bytes(secret))
+}
+
+/// Proof of the session key sent from client to server: HMAC(K, salt)
+pub fn proof(key: &[u8], salt: &[u8]) -> Vec<u8> {
+    hmac_sha256(key, salt)
+}
+
+pub struct Server {
+    group: DiffieHellman,
+    validate: bool,
+    users: HashMap<String, (Vec<u8>, BigInt)>, // email -> (salt, verifier)
+    session_proof: Option<Vec<u8>>,
+}
+
+impl Server {
+    /// A server that doesn't check the client's public key, which the zero key attack needs
+    pub fn new() -> Self {
+        Server { group: DiffieHellman::nist(), validate: false, users: HashMap::new(), session_proof: None }
+    }
+
+    /// A server that rejects client public keys that are 0 mod N
+    pub fn validating() -> Self {
+        Server { validate: true, ..Server::new() }
+    }
+
+    pub fn register(&mut self, email: &str, password: &[u8]) {
+        let salt = rand_bytes(16);
+        let x = private_key(&salt, password);
+        let v = self.group.public_key(&x).value;
+        self.users.insert(email.to_string(), (salt, v));
+    }
+
+    /// Handle the client's first message, replying with (salt, B)
+    pub fn start(&mut self, email: &str, a: &BigInt) -> Result<(Vec<u8>, BigInt), String> {
+        let n = self.group.p_ref().clone();
+        let (salt, v) = self.users.get(email).cloned().ok_or_else(|| format!("Unknown user: {}", email))?;
+        if self.validate && a.mod_floor(&n).is_zero() {
+            return Err(String::from("Invalid client public key"));
+        }
+
+        let (b, g_b) = self.group.keypair();
+        let big_b = (BigInt::from(K) * &v + g_b.value).mod_floor(&n);
+        let u = scrambler(a, &big_b);
+
+        // S = (A v^u)^b
+        let base = (a * self.group.shared_secret(&u, &v).value).mod_floor(&n);
+        let key = session_key(&self.group.shared_secret(&b, &base).value);
+        self.session_proof = Some(proof(&key, &salt));
+
+        Ok((salt, big_b))
+    }
+
+    /// Check the client's proof for the session started last
+    pub fn verify(&self, client_proof: &[u8]) -> bool {
+        self.session_proof.as_ref().is_some_and(|p| constant_time_eq(p, client_proof))
+    }
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Server::new()
+    }
+}
+
+pub struct Client {
+    group: DiffieHellman,
+    email: String,
+    password: Vec<u8>,
+    a: BigInt,
+    public_key: BigInt,
+}
+
+impl Client {
+    pub fn new(email: &str, password: &[u8]) -> Self {
+        let group = DiffieHellman::nist();
+        let (a, public_key) = group.keypair();
+        Client { group, email: email.to_string(), password: password.to_vec(), a, public_key: public_key.value }
+    }
+
+    pub fn email(&self) -> &str {
+        &self.email
+    }
+
+    /// A = g^a
+    pub fn public_key(&self) -> &BigInt {
+        &self.public_key
+    }
+
+    /// Handle the server's (salt, B), returning the proof to send back
+    pub fn finish(&self, salt: &[u8], big_b: &BigInt) -> Result<Vec<u8>, String> {
+        let n = self.group.p_ref();
+        if big_b.mod_floor(n).is_zero() {
+            return Err(String::from("Invalid server public key"));
+        }
+
+        let u = scrambler(&self.public_key, big_b);
+        let x = private_key(salt, &self.password);
+
+        // S = (B - kg^x)^(a + ux)
+        let base = (big_b - BigInt::from(K) * self.group.public_key(&x).value).mod_floor(n);
+        let secret = self.group.shared_secret(&(&self.a + u * x), &base);
+        Ok(proof(&session_key(&secret.value), salt))
+    }
+}
+
+/// Run a login between client and server in process
+pub fn login(client: &Client, server: &mut Server) -> Result<bool, String> {
+    let (salt, big_b) = server.start(client.email(), client.public_key())?;
+    let client_proof = client.finish(&salt, &big_b)?;
+    Ok(server.verify(&client_proof))
+}
+
+/// Log in as email without the password by sending a public key that's a multiple of N. The server's
+/// secret is then 0 whatever its own key is.
+pub fn zero_key_login(server: &mut Server, email: &str, a: &BigInt) -> Result<bool, String> {
+    let (salt, _) = server.start(email, a)?;
+    let key = session_key(&BigInt::zero());
+    Ok(server.verify(&proof(&key, &salt)))
+}
+
+// u = H(A || B)
+fn scrambler(a: &BigInt, b: &BigInt) -> BigInt {
+    let mut data = int_bytes(a);
+    data.extend(int_bytes(b));
+    sha256_bigint(&data)
+}
+
+fn int_bytes(n: &BigInt) -> Vec<u8> {
+    n.to_bytes_be().1
+}
+
+#[cfg(test)]
+mod tests {
+    use set5::srp::*;
+
+    const EMAIL: &str = "alice@example.com";
+
+    #[test]
+    fn srp_36() {
+        let mut server = Server::new();
+        server.register(EMAIL, b"correct horse battery staple");
+
+        let client = Client::new(EMAIL, b"correct horse battery staple");
+        assert_eq!(login(&client, &mut server), Ok(true));
+
+        let impostor = Client::new(EMAIL, b"hunter2");
+        assert_eq!(login(&impostor, &mut server), Ok(false));
+
+        let stranger = Client::new("mallory@example.com", b"hunter2");
+        assert!(login(&stranger, &mut server).is_err());
+    }
+
+    #[test]
+    fn srp_zero_key_37() {
+        let mut server = Server::new();
+        server.register(EMAIL, b"correct horse battery staple");
+
+        let n = DiffieHellman::nist().p_ref().clone();
+        for multiple in 0..3 {
+            let a = &n * BigInt::from(multiple);
+            assert_eq!(zero_key_login(&mut server, EMAIL, &a), Ok(true), "A = {}N", multiple);
+        }
+
+        let mut server = Server::validating();
+        server.register(EMAIL, b"correct horse battery staple");
+        assert!(zero_key_login(&mut server, EMAIL, &n).is_err());
+
+        let client = Client::new(EMAIL, b"correct horse battery staple");
+        assert_eq!(login(&client, &mut server), Ok(true));
+    }
+}
@@ -0,0 +1,175 @@
+use std::cmp::{max, min};
+use num_bigint::{BigInt, RandBigInt};
+use num_integer::{Integer};
+use num_traits::*;
+use rand::{thread_rng};
+use finite_field::{mod_inverse};
+use rsa::{i2osp, pkcs1v15_unpad, PrivateKey, PublicKey};
+
+// Cryptopals challenges 47 and 48: Bleichenbacher's PKCS#1 v1.5 padding oracle attack.
+//
+// A conforming plaintext starts with 00 02, so it lies in [2B, 3B) where B = 2^(8(k - 2)). If
+// c s^e is conforming then ms mod n is in that range too, which bounds m to a set of intervals.
+// Searching for more such s and intersecting the bounds shrinks the intervals until only m is
+// left. Each new s is found by trying values until the oracle accepts, so this takes thousands of
+// queries, far fewer than the message space.
+//
+// http://archiv.infsec.ethz.ch/education/fs08/secsem/bleichenbacher98.pdf
+
+/// Tells us whether the plaintext of a ciphertext starts with 00 02
+pub trait PaddingOracle {
+    fn public_key(&self) -> PublicKey;
+    fn is_conforming(&self, c: &BigInt) -> bool;
+}
+
+pub struct Server {
+    key: PrivateKey,
+}
+
+impl Server {
+    pub fn new(key: PrivateKey) -> Self {
+        Server { key }
+    }
+}
+
+impl PaddingOracle for Server {
+    fn public_key(&self) -> PublicKey {
+        self.key.public_key()
+    }
+
+    // Starting with 00 02 is the same as being in [2B, 3B)
+    fn is_conforming(&self, c: &BigInt) -> bool {
+        let b = BigInt::one() << (8 * (self.key.size() - 2));
+        match self.key.decrypt(c) {
+            Ok(m) => m >= &b * BigInt::from(2) && m < &b * BigInt::from(3),
+            Err(_) => false,
+        }
+    }
+}
+
+/// Decrypt a PKCS#1 v1.5 ciphertext using only the padding oracle, returning the unpadded message
+pub fn attack<O: PaddingOracle>(c: &BigInt, oracle: &O) -> Result<Vec<u8>, String> {
+    let key = oracle.public_key();
+    let n = &key.n;
+    let b = BigInt::one() << (8 * (key.size() - 2));
+    let (b2, b3) = (&b * BigInt::from(2), &b * BigInt::from(3));
+    let query = |s: &BigInt, c_0: &BigInt| oracle.is_conforming(&(c_0 * s.modpow(&key.e, n)).mod_floor(n));
+
+    // Step 1: blinding. Only needed when c isn't already conforming.
+    let mut rng = thread_rng();
+    let mut s_0 = BigInt::one();
+    while !query(&s_0, c) {
+        s_0 = rng.gen_bigint_range(&BigInt::from(2), n);
+    }
+    let c_0 = (c * s_0.modpow(&key.e, n)).mod_floor(n);
+
+    let mut intervals = vec![(b2.clone(), &b3 - BigInt::one())];
+    let mut s = ceil_div(n, &b3);
+    let mut i = 1;
+    loop {
+        if i == 1 {
+            // Step 2a: smallest s >= n / 3B that's conforming
+            while !query(&s, &c_0) {
+                s += 1;
+            }
+        } else if intervals.len() > 1 {
+            // Step 2b: next conforming s
+            s += 1;
+            while !query(&s, &c_0) {
+                s += 1;
+            }
+        } else {
+            // Step 2c: with a single interval [a, b], search s for increasing r where
+            // (2B + rn) / b <= s < (3B + rn) / a, which roughly halves the interval each time
+            let (lo, hi) = intervals[0].clone();
+            let mut r = ceil_div(&(BigInt::from(2) * (&hi * &s - &b2)), n);
+            s = 'search: loop {
+                let mut candidate = ceil_div(&(&b2 + &r * n), &hi);
+                let end = ceil_div(&(&b3 + &r * n), &lo);
+                while candidate < end {
+                    if query(&candidate, &c_0) {
+                        break 'search candidate;
+                    }
+                    candidate += 1;
+                }
+                r += 1;
+            };
+        }
+
+        // Step 3: narrow each interval to the values consistent with ms - rn in [2B, 3B)
+        let mut narrowed: Vec<(BigInt, BigInt)> = vec![];
+        for (lo, hi) in &intervals {
+            let mut r = ceil_div(&(lo * &s - &b3 + BigInt::one()), n);
+            let r_end = (hi * &s - &b2).div_floor(n);
+            while r <= r_end {
+                let new_lo = max(lo.clone(), ceil_div(&(&b2 + &r * n), &s));
+                let new_hi = min(hi.clone(), (&b3 - BigInt::one() + &r * n).div_floor(&s));
+                if new_lo <= new_hi {
+                    narrowed.push((new_lo, new_hi));
+                }
+                r += 1;
+            }
+        }
+        intervals = merge(narrowed);
+        if intervals.is_empty() {
+            return Err(String::from("No intervals left, the oracle isn't consistent"));
+        }
+
+        // Step 4: done once a single value is left
+        if intervals.len() == 1 && intervals[0].0 == intervals[0].1 {
+            let s_0_inv = mod_inverse(&s_0, n).ok_or_else(|| String::from("s_0 shares a factor with n"))?;
+            let m = (&intervals[0].0 * s_0_inv).mod_floor(n);
+            return pkcs1v15_unpad(&i2osp(&m, key.size())?);
+        }
+
+        i += 1;
+    }
+}
+
+// Union of overlapping intervals, sorted
+fn merge(mut intervals: Vec<(BigInt, BigInt)>) -> Vec<(BigInt, BigInt)> {
+    intervals.sort();
+    let mut merged: Vec<(BigInt, BigInt)> = vec![];
+    for (lo, hi) in intervals {
+        if let Some(last) = merged.last_mut() {
+            if lo <= last.1 {
+                last.1 = max(last.1.clone(), hi);
+                continue;
+            }
+        }
+        merged.push((lo, hi));
+    }
+
+    merged
+}
+
+fn ceil_div(a: &BigInt, b: &BigInt) -> BigInt {
+    -((-a).div_floor(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use set6::bleichenbacher::*;
+    use rsa::{os2ip};
+
+    #[test]
+    fn bleichenbacher_47() {
+        // Challenge 48 is the same with a 768 bit modulus, which needs the same number of queries
+        // but each one costs more
+        let server = Server::new(PrivateKey::generate(256, &BigInt::from(3)).unwrap());
+        let key = server.public_key();
+        let message = b"kick it, CC";
+        let c = os2ip(&key.encrypt_pkcs1v15(message).unwrap());
+
+        assert!(server.is_conforming(&c));
+        assert_eq!(attack(&c, &server), Ok(message.to_vec()));
+    }
+
+    #[test]
+    fn bleichenbacher_merge() {
+        let intervals = vec![(5, 9), (1, 3), (2, 4), (10, 12)];
+        let intervals = intervals.into_iter().map(|(a, b)| (BigInt::from(a), BigInt::from(b))).collect();
+        let expected: Vec<(BigInt, BigInt)> = vec![(1, 4), (5, 9), (10, 12)].into_iter().map(|(a, b)| (BigInt::from(a), BigInt::from(b))).collect();
+        assert_eq!(merge(intervals), expected);
+    }
+}
@@ -0,0 +1,142 @@
+use num_bigint::{BigInt};
+use num_integer::{Integer};
+use num_traits::*;
+use dsa::{Params, Sig, Signer};
+use finite_field::{Field};
+
+// Cryptopals challenges 43, 44 and 45: DSA key recovery and parameter tampering.
+//
+// s = k^-1 (z + xr) mod q, so anyone who knows k for one signature gets x = (sk - z) / r:
+//
+// - 43: k drawn from a tiny range can be found by stepping through g^k until it matches r.
+// - 44: two signatures with the same k share r, and subtracting them gives
+//   k = (z1 - z2) / (s1 - s2).
+// - 45: a verifier that takes g from the attacker accepts anything. With g = 0 every r is 0, and
+//   with g = p + 1 any y^t works as r for every message.
+
+/// x = (sk - z) / r for a signature made with nonce k
+pub fn recover_x_from_k(signer: &Signer, sig: &Sig, k: &BigInt) -> BigInt {
+    let k = signer.elem(k);
+    ((sig.s.clone() * k - &sig.z) / sig.r.clone()).value
+}
+
+/// Find x for a signature whose nonce is in [0, bound) by trying every k, checking against pubkey
+pub fn recover_x_small_k(signer: &Signer, sig: &Sig, pubkey: &BigInt, bound: u64) -> Option<BigInt> {
+    let Params { p, g, .. } = signer.params_ref();
+
+    // g^k one multiplication at a time rather than an exponentiation per guess
+    let mut g_k = BigInt::one();
+    for k in 0..bound {
+        if signer.elem(&g_k) == sig.r {
+            let k = BigInt::from(k);
+            let x = recover_x_from_k(signer, sig, &k);
+            if &signer.pubkey(&x) == pubkey {
+                return Some(x);
+            }
+        }
+        g_k = (g_k * g).mod_floor(p);
+    }
+    None
+}
+
+/// Find x from any two signatures in sigs that reused a nonce, checking against pubkey
+pub fn recover_x_repeated_k(signer: &Signer, sigs: &[Sig], pubkey: &BigInt) -> Option<BigInt> {
+    for (i, a) in sigs.iter().enumerate() {
+        for b in &sigs[i + 1..] {
+            if a.r != b.r || a.s == b.s {
+                continue;
+            }
+
+            // k = (z1 - z2) / (s1 - s2)
+            let k = ((&a.z - &b.z) / (&a.s - &b.s)).value;
+            let x = recover_x_from_k(signer, a, &k);
+            if &signer.pubkey(&x) == pubkey {
+                return Some(x);
+            }
+        }
+    }
+    None
+}
+
+/// Signature that verifies for z under pubkey when the verifier uses g = p + 1: with
+/// r = (y^t mod p) mod q and s = r / t, the verifier computes g^u1 y^(r/s) = y^t.
+pub fn forge_signature(params: &Params, pubkey: &BigInt, z: &BigInt, t: &BigInt) -> Sig {
+    let field = Field::new(params.q.clone());
+    let r = field.elem(pubkey.modpow(t, &params.p));
+    let s = r.clone() / field.elem(t.clone());
+    Sig::new(r, s, field.elem(z.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use set6::dsa_attacks::*;
+    use set1::{hex_encode};
+    use util::{sha1, sha1_bigint};
+
+    const MESSAGE: &[u8] = b"For those that envy a MC it can be hazardous to your health\nSo be friendly, a matter of life and death, just like a etch-a-sketch\n";
+
+    fn hex(s: &str) -> BigInt {
+        BigInt::parse_bytes(s.as_bytes(), 16).unwrap()
+    }
+
+    #[test]
+    fn dsa_known_k() {
+        let signer = Signer::new();
+        let (x, _) = signer.keypair();
+        let k = BigInt::from(0xdeadbeefu32);
+        let sig = signer.sign_message(b"Programming Bitcoin!", &k, &x);
+        assert_eq!(recover_x_from_k(&signer, &sig, &k), x);
+    }
+
+    #[test]
+    fn dsa_small_k_43() {
+        let signer = Signer::new();
+        let y = hex("84ad4719d044495496a3201c8ff484feb45b962e7302e56a392aee4abab3e4bdebf2955b4736012f21a08084056b19bcd7fee56048e004e44984e2f411788efdc837a0d2e5abb7b555039fd243ac01f0fb2ed1dec568280ce678e931868d23eb095fde9d3779191b8c0299d6e07bbb283e6633451e535c45513b2d33c99ea17");
+        let z = sha1_bigint(MESSAGE);
+        assert_eq!(z, hex("d2d0714f014a9784047eaeccf956520045c45265"));
+
+        let r = BigInt::parse_bytes(b"548099063082341131477253921760299949438196259240", 10).unwrap();
+        let s = BigInt::parse_bytes(b"857042759984254168557880549501802188789837994940", 10).unwrap();
+        let sig = Sig::new(signer.elem(&r), signer.elem(&s), signer.elem(&z));
+        assert!(signer.verify(&sig, &y));
+
+        let x = recover_x_small_k(&signer, &sig, &y, 1 << 16).unwrap();
+        assert_eq!(hex_encode(&sha1(x.to_str_radix(16).as_bytes())), "0954edd5e0afe5542a4adf012611a91912a3ec16");
+    }
+
+    #[test]
+    fn dsa_repeated_k_44() {
+        let signer = Signer::new();
+        let (x, y) = signer.keypair();
+        let (k1, k2) = (BigInt::from(1234567u32), BigInt::from(7654321u32));
+        let sigs = vec![
+            signer.sign_message(b"Listen for me, you better listen for me now. ", &k1, &x),
+            signer.sign_message(b"Pure black people mon is all I mon know. ", &k2, &x),
+            signer.sign_message(b"Yeah me shoes a an tear up an' now me toes is a show a ", &k1, &x),
+        ];
+        assert_eq!(recover_x_repeated_k(&signer, &sigs, &y), Some(x));
+        assert_eq!(recover_x_repeated_k(&signer, &sigs[..2], &y), None);
+    }
+
+    #[test]
+    fn dsa_parameter_tampering_45() {
+        let params = Params::cryptopals();
+        let (x, y) = Signer::new().keypair();
+
+        // g = 0: r = 0 for every signature, which only verifies when 0 < r isn't checked
+        let zero = Signer::with_params(Params { g: BigInt::zero(), ..params.clone() });
+        let sig = Sig::new(zero.elem(&BigInt::zero()), zero.elem(&BigInt::from(12345)), zero.elem(&sha1_bigint(b"Hello, world")));
+        assert!(zero.verify_without_range_check(&sig, &y));
+        assert!(zero.verify_without_range_check(&Sig { z: zero.elem(&sha1_bigint(b"Goodbye, world")), ..sig.clone() }, &y));
+        assert!(!zero.verify(&sig, &y));
+
+        // g = p + 1: the magic signature verifies for anything
+        let one = Signer::with_params(Params { g: &params.p + BigInt::one(), ..params.clone() });
+        for message in &[&b"Hello, world"[..], &b"Goodbye, world"[..]] {
+            let sig = forge_signature(&params, &y, &sha1_bigint(message), &BigInt::from(42));
+            assert!(one.verify(&sig, &y));
+            assert!(!Signer::new().verify(&sig, &y));
+        }
+        assert!(Signer::new().verify(&Signer::new().sign_message(b"Hello, world", &BigInt::from(42), &x), &y));
+    }
+}
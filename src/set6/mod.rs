@@ -0,0 +1,4 @@
+pub mod unpadded_oracle;
+pub mod parity_oracle;
+pub mod bleichenbacher;
+pub mod dsa_attacks;
@@ -0,0 +1,82 @@
+use num_bigint::{BigInt};
+use num_integer::{Integer};
+use num_traits::*;
+use rsa::{PrivateKey, PublicKey};
+
+// Cryptopals challenge 46: RSA parity oracle.
+//
+// Multiplying c by 2^e doubles the plaintext. 2m mod n is even exactly when it didn't wrap around
+// n, i.e. when m < n/2, because n is odd. Doubling again and asking for the parity splits the
+// remaining range in half again, so log2(n) queries pin m down completely.
+
+/// Tells us whether the plaintext of a ciphertext is even
+pub trait ParityOracle {
+    fn public_key(&self) -> PublicKey;
+    fn is_even(&self, c: &BigInt) -> bool;
+}
+
+pub struct Server {
+    key: PrivateKey,
+}
+
+impl Server {
+    pub fn new(key: PrivateKey) -> Self {
+        Server { key }
+    }
+}
+
+impl ParityOracle for Server {
+    fn public_key(&self) -> PublicKey {
+        self.key.public_key()
+    }
+
+    fn is_even(&self, c: &BigInt) -> bool {
+        self.key.decrypt(c).map(|m| m.is_even()).unwrap_or(false)
+    }
+}
+
+/// Decrypt c using only the parity oracle
+pub fn decrypt<O: ParityOracle>(c: &BigInt, oracle: &O) -> BigInt {
+    let key = oracle.public_key();
+    let double = BigInt::from(2).modpow(&key.e, &key.n);
+
+    // After k queries m is in [lo * n / 2^k, hi * n / 2^k)
+    let (mut lo, mut hi) = (BigInt::zero(), BigInt::one());
+    let mut c = c.clone();
+    let mut k = 0;
+    while (BigInt::one() << k) <= key.n {
+        c = (&c * &double).mod_floor(&key.n);
+        lo <<= 1;
+        hi <<= 1;
+        k += 1;
+
+        let mid = &lo + BigInt::one();
+        if oracle.is_even(&c) {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+
+    // The range is now narrower than 1 so its lower bound rounded up is m
+    let scale = BigInt::one() << k;
+    (lo * &key.n + &scale - BigInt::one()) / scale
+}
+
+#[cfg(test)]
+mod tests {
+    use set6::parity_oracle::*;
+    use base64::decode as base64decode;
+    use rsa::{i2osp, os2ip};
+
+    #[test]
+    fn parity_oracle_46() {
+        let server = Server::new(PrivateKey::generate(1024, &BigInt::from(65537)).unwrap());
+        let key = server.public_key();
+        let message = base64decode("VGhhdCdzIHdoeSBJIGZvdW5kIHlvdSBkb24ndCBwbGF5IGFyb3VuZCB3aXRoIHRoZSBGdW5reSBDb2xkIE1lZGluYQ==").unwrap();
+        let c = key.encrypt(&os2ip(&message)).unwrap();
+
+        let m = decrypt(&c, &server);
+        assert_eq!(i2osp(&m, message.len()), Ok(message));
+    }
+}
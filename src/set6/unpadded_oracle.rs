@@ -0,0 +1,73 @@
+use std::cell::{RefCell};
+use std::collections::{HashSet};
+use num_bigint::{BigInt, RandBigInt};
+use num_integer::{Integer};
+use rand::{thread_rng};
+use finite_field::{mod_inverse};
+use rsa::{PrivateKey, PublicKey};
+
+// Cryptopals challenge 41: unpadded message recovery oracle.
+//
+// A server that decrypts anything except ciphertexts it has already seen still decrypts
+// c' = s^e c, which is a new ciphertext. Since raw RSA is multiplicative it decrypts to sm, and
+// dividing by s gives back m.
+
+/// Something that decrypts raw RSA ciphertexts for us, within limits
+pub trait DecryptionOracle {
+    fn public_key(&self) -> PublicKey;
+    fn decrypt(&self, c: &BigInt) -> Result<BigInt, String>;
+}
+
+/// Decrypts each ciphertext at most once
+pub struct Server {
+    key: PrivateKey,
+    seen: RefCell<HashSet<BigInt>>,
+}
+
+impl Server {
+    pub fn new(key: PrivateKey) -> Self {
+        Server { key, seen: RefCell::new(HashSet::new()) }
+    }
+}
+
+impl DecryptionOracle for Server {
+    fn public_key(&self) -> PublicKey {
+        self.key.public_key()
+    }
+
+    fn decrypt(&self, c: &BigInt) -> Result<BigInt, String> {
+        if !self.seen.borrow_mut().insert(c.clone()) {
+            return Err(String::from("Ciphertext has already been decrypted"));
+        }
+
+        self.key.decrypt(c)
+    }
+}
+
+/// Recover the plaintext of c from an oracle that won't decrypt c itself
+pub fn recover_message<O: DecryptionOracle>(c: &BigInt, oracle: &O) -> Result<BigInt, String> {
+    let key = oracle.public_key();
+    let s = thread_rng().gen_bigint_range(&BigInt::from(2), &key.n);
+    let s_inv = mod_inverse(&s, &key.n).ok_or_else(|| String::from("s shares a factor with n"))?;
+
+    let c_prime = (s.modpow(&key.e, &key.n) * c).mod_floor(&key.n);
+    let p_prime = oracle.decrypt(&c_prime)?;
+    Ok((p_prime * s_inv).mod_floor(&key.n))
+}
+
+#[cfg(test)]
+mod tests {
+    use set6::unpadded_oracle::*;
+    use rsa::{os2ip};
+
+    #[test]
+    fn unpadded_oracle_41() {
+        let server = Server::new(PrivateKey::generate(512, &BigInt::from(65537)).unwrap());
+        let m = os2ip(b"{time: 1356304276, social: '555-55-5555'}");
+        let c = server.public_key().encrypt(&m).unwrap();
+
+        assert_eq!(server.decrypt(&c), Ok(m.clone()));
+        assert!(server.decrypt(&c).is_err());
+        assert_eq!(recover_message(&c, &server), Ok(m));
+    }
+}
@@ -0,0 +1,170 @@
+use num_bigint::{BigInt};
+use num_integer::{Integer};
+use num_traits::*;
+use finite_field::{mod_inverse};
+use ecdsa::{Sig};
+use lattice::{lll_reduce};
+
+// Private key recovery from ECDSA nonces that aren't uniformly random.
+//
+// A signature is s = k^-1 (z + rd) mod n, so a single nonce k gives away the private key d. When
+// the same nonce signs two messages it cancels out and both fall out directly. When nonces are
+// merely short, or some of their bits leak, each signature gives an approximate linear equation in
+// d. That's the hidden number problem, and with enough signatures the unknown parts of the nonces
+// form an unusually short vector in a lattice that LLL can find.
+
+/// Recover the private key from two signatures over different messages that used the same nonce,
+/// which shows up as both having the same r.
+///
+///   s_1 - s_2 = k^-1 (z_1 - z_2)  =>  k = (z_1 - z_2) / (s_1 - s_2)  =>  d = (s_1 k - z_1) / r
+pub fn recover_from_nonce_reuse(sig_1: &Sig, sig_2: &Sig) -> Result<BigInt, String> {
+    if sig_1.r != sig_2.r {
+        return Err(String::from("Signatures don't share a nonce"));
+    }
+    if sig_1.s == sig_2.s {
+        return Err(String::from("Signatures are over the same message"));
+    }
+
+    let k = (&sig_1.z - &sig_2.z) / (&sig_1.s - &sig_2.s);
+    let d = (&(&sig_1.s * &k) - &sig_1.z) / sig_1.r.clone();
+    Ok(d.value)
+}
+
+/// Recover the private key from signatures whose nonces are short: k < 2^nonce_bits for each. n is
+/// the order of the curve's subgroup.
+pub fn recover_from_biased_nonces(sigs: &[Sig], nonce_bits: usize, n: &BigInt) -> Option<BigInt> {
+    let known = vec![BigInt::zero(); sigs.len()];
+    recover_from_partial_nonces(sigs, &known, nonce_bits, n)
+}
+
+/// Recover the private key from signatures where part of each nonce is known, so that
+/// k_i = known_i + b_i with 0 <= b_i < 2^nonce_bits. Leaked high bits of a nonce are known_i with
+/// the low bits zeroed.
+///
+/// Each signature gives b_i = t_i d + u_i mod n with t_i = r_i / s_i and u_i = z_i / s_i - known_i.
+/// Eliminating d with the first one leaves b_i = A_i b_0 + C_i mod n, and the vector
+/// (b_1, ..., b_m, b_0, B) is a short vector in the lattice spanned by the rows of
+///
+///   [ n              ]
+///   [    ...         ]
+///   [         n      ]
+///   [ A_1 ... A_m 1  ]
+///   [ C_1 ... C_m 0 B]
+///
+/// which LLL finds once the signatures leak a bit more than log2(n) bits between them.
+pub fn recover_from_partial_nonces(sigs: &[Sig], known: &[BigInt], nonce_bits: usize, n: &BigInt) -> Option<BigInt> {
+    if sigs.len() < 2 || sigs.len() != known.len() {
+        return None;
+    }
+
+    let mut t = vec![];
+    let mut u = vec![];
+    for (sig, known) in sigs.iter().zip(known) {
+        let s_inv = mod_inverse(&sig.s.value, n)?;
+        t.push((&sig.r.value * &s_inv).mod_floor(n));
+        u.push((&sig.z.value * &s_inv - known).mod_floor(n));
+    }
+
+    let m = sigs.len() - 1;
+    let bound = BigInt::one() << nonce_bits;
+    let t_0_inv = mod_inverse(&t[0], n)?;
+
+    let mut basis = vec![vec![BigInt::zero(); m + 2]; m + 2];
+    for i in 0..m {
+        let a_i = (&t[i + 1] * &t_0_inv).mod_floor(n);
+        let c_i = (&u[i + 1] - &a_i * &u[0]).mod_floor(n);
+        basis[i][i] = n.clone();
+        basis[m][i] = a_i;
+        basis[m + 1][i] = c_i;
+    }
+    basis[m][m] = BigInt::one();
+    basis[m + 1][m + 1] = bound.clone();
+
+    for row in lll_reduce(&basis).ok()? {
+        let sign = if row[m + 1] == bound {
+            BigInt::one()
+        } else if row[m + 1] == -&bound {
+            -BigInt::one()
+        } else {
+            continue;
+        };
+
+        let b_0 = &row[m] * sign;
+        let d = ((b_0 - &u[0]) * &t_0_inv).mod_floor(n);
+
+        // The right key makes every nonce land in range
+        if t.iter().zip(&u).all(|(t_i, u_i)| (t_i * &d + u_i).mod_floor(n) < bound) {
+            return Some(d);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use set8::ecdsa_nonce::*;
+    use num_bigint::{RandBigInt};
+    use rand::{thread_rng};
+    use ecdsa::{Signer};
+    use elliptic_curve::{CurveGroup};
+    use secp256k1::{Secp256k1};
+    use util::{sha256_bigint};
+
+    #[test]
+    fn ecdsa_nonce_reuse() {
+        let curve = Secp256k1::new();
+        let mut rng = thread_rng();
+        let privkey = rng.gen_bigint_range(&BigInt::one(), curve.n_ref());
+        let k = rng.gen_bigint_range(&BigInt::one(), curve.n_ref());
+
+        let signer = Signer::new();
+        let sig_1 = signer.sign(&sha256_bigint(b"first message"), &k, &privkey);
+        let sig_2 = signer.sign(&sha256_bigint(b"second message"), &k, &privkey);
+        assert_eq!(recover_from_nonce_reuse(&sig_1, &sig_2), Ok(privkey.clone()));
+
+        let other_k = rng.gen_bigint_range(&BigInt::one(), curve.n_ref());
+        let sig_3 = signer.sign(&sha256_bigint(b"third message"), &other_k, &privkey);
+        assert!(recover_from_nonce_reuse(&sig_1, &sig_3).is_err());
+        assert!(recover_from_nonce_reuse(&sig_1, &sig_1).is_err());
+    }
+
+    #[test]
+    fn ecdsa_biased_nonces() {
+        let curve = Secp256k1::new();
+        let n = curve.n_ref();
+        let mut rng = thread_rng();
+        let privkey = rng.gen_bigint_range(&BigInt::one(), n);
+        let signer = Signer::new();
+
+        // The top 64 bits of every nonce are zero
+        let nonce_bits = 192;
+        let sigs: Vec<Sig> = (0..8u8).map(|i| {
+            let k = rng.gen_bigint_range(&BigInt::one(), &(BigInt::one() << nonce_bits));
+            signer.sign(&sha256_bigint(&[i]), &k, &privkey)
+        }).collect();
+
+        assert_eq!(recover_from_biased_nonces(&sigs, nonce_bits, n), Some(privkey));
+    }
+
+    #[test]
+    fn ecdsa_partially_known_nonces() {
+        let curve = Secp256k1::new();
+        let n = curve.n_ref();
+        let mut rng = thread_rng();
+        let privkey = rng.gen_bigint_range(&BigInt::one(), n);
+        let signer = Signer::new();
+
+        // The top 56 bits of every nonce leak
+        let nonce_bits = 200;
+        let mut sigs = vec![];
+        let mut known = vec![];
+        for i in 0..8u8 {
+            let k = rng.gen_bigint_range(&BigInt::one(), n);
+            sigs.push(signer.sign(&sha256_bigint(&[i]), &k, &privkey));
+            known.push((&k >> nonce_bits) << nonce_bits);
+        }
+
+        assert_eq!(recover_from_partial_nonces(&sigs, &known, nonce_bits, n), Some(privkey));
+    }
+}
@@ -0,0 +1,236 @@
+use rand::{thread_rng, Rng};
+use gcm::{ghash, ghash_input, Gf128};
+
+// Cryptopals challenge 63: the forbidden attack on GCM with a repeated nonce.
+//
+// A GCM tag is t = GHASH_H(A, C) + E(J0), and GHASH is a polynomial in H whose coefficients are
+// the blocks of A and C. Two messages under the same key and nonce share E(J0), so adding their
+// tags cancels it and leaves
+//
+//   0 = sum_i (b1_i + b2_i) H^i + (t1 + t2)
+//
+// which is known apart from H. Its roots over GF(2^128) are a handful of candidates for H, and a
+// third message with the same nonce almost always narrows them down to one. With H and any one
+// tag the mask E(J0) is known too, so valid tags can be forged for any ciphertext under that nonce.
+
+/// A ciphertext as seen on the wire
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub ad: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+    pub tag: Vec<u8>,
+}
+
+impl Message {
+    pub fn new(ad: &[u8], ciphertext: &[u8], tag: &[u8]) -> Self {
+        Message { ad: ad.to_vec(), ciphertext: ciphertext.to_vec(), tag: tag.to_vec() }
+    }
+}
+
+/// Possible hash keys for two messages sealed with the same key and nonce
+pub fn auth_key_candidates(first: &Message, second: &Message) -> Vec<Gf128> {
+    let diff = poly_add(&tag_poly(first), &tag_poly(second));
+    if diff.len() < 2 {
+        return vec![];
+    }
+
+    let mut candidates = roots(&monic(&diff));
+    candidates.sort_by_key(|h| h.0);
+    candidates
+}
+
+/// Recover the hash key from three or more messages that share a key and nonce
+pub fn recover_auth_key(messages: &[Message]) -> Option<Gf128> {
+    if messages.len() < 2 {
+        return None;
+    }
+
+    let candidates: Vec<Gf128> = auth_key_candidates(&messages[0], &messages[1]).into_iter()
+        .filter(|&h| {
+            let mask = mask(h, &messages[0]);
+            messages[2..].iter().all(|m| Gf128::from_bytes(&m.tag) == ghash(h, &m.ad, &m.ciphertext) + mask)
+        })
+        .collect();
+
+    match candidates.as_slice() {
+        [h] => Some(*h),
+        _ => None,
+    }
+}
+
+/// Tag for ciphertext and ad under the nonce that known was sealed with
+pub fn forge_tag(h: Gf128, known: &Message, ad: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    (ghash(h, ad, ciphertext) + mask(h, known)).to_bytes().to_vec()
+}
+
+// E(J0) = t + GHASH_H(A, C)
+fn mask(h: Gf128, message: &Message) -> Gf128 {
+    Gf128::from_bytes(&message.tag) + ghash(h, &message.ad, &message.ciphertext)
+}
+
+// GHASH plus the tag as a polynomial in H, lowest degree first: t + b_n H + ... + b_1 H^n
+fn tag_poly(message: &Message) -> Vec<Gf128> {
+    let mut poly = vec![Gf128::from_bytes(&message.tag)];
+    poly.extend(ghash_input(&message.ad, &message.ciphertext).into_iter().rev());
+    poly
+}
+
+// Polynomials over GF(2^128) as coefficient vectors, lowest degree first, with no trailing zeros.
+// Subtraction is the same as addition.
+
+fn trim(poly: &mut Vec<Gf128>) {
+    while poly.last().is_some_and(|c| c.is_zero()) {
+        poly.pop();
+    }
+}
+
+fn monic(poly: &[Gf128]) -> Vec<Gf128> {
+    let lead_inv = poly.last().expect("nonzero polynomial").inverse();
+    poly.iter().map(|&c| c * lead_inv).collect()
+}
+
+fn poly_add(a: &[Gf128], b: &[Gf128]) -> Vec<Gf128> {
+    let mut sum: Vec<Gf128> = (0..a.len().max(b.len()))
+        .map(|i| *a.get(i).unwrap_or(&Gf128::zero()) + *b.get(i).unwrap_or(&Gf128::zero()))
+        .collect();
+    trim(&mut sum);
+    sum
+}
+
+fn poly_mul(a: &[Gf128], b: &[Gf128]) -> Vec<Gf128> {
+    if a.is_empty() || b.is_empty() {
+        return vec![];
+    }
+    let mut product = vec![Gf128::zero(); a.len() + b.len() - 1];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate() {
+            product[i + j] = product[i + j] + x * y;
+        }
+    }
+    trim(&mut product);
+    product
+}
+
+// Quotient and remainder of a divided by b, for b != 0
+fn poly_divmod(a: &[Gf128], b: &[Gf128]) -> (Vec<Gf128>, Vec<Gf128>) {
+    let mut rem = a.to_vec();
+    let mut quotient = vec![Gf128::zero(); a.len().saturating_sub(b.len()) + 1];
+    let lead_inv = b.last().expect("nonzero divisor").inverse();
+    while rem.len() >= b.len() {
+        let coeff = *rem.last().unwrap() * lead_inv;
+        let shift = rem.len() - b.len();
+        quotient[shift] = coeff;
+        for (i, &c) in b.iter().enumerate() {
+            rem[shift + i] = rem[shift + i] + coeff * c;
+        }
+        trim(&mut rem);
+    }
+    trim(&mut quotient);
+    (quotient, rem)
+}
+
+fn poly_rem(a: &[Gf128], b: &[Gf128]) -> Vec<Gf128> {
+    poly_divmod(a, b).1
+}
+
+fn poly_gcd(a: &[Gf128], b: &[Gf128]) -> Vec<Gf128> {
+    let (mut a, mut b) = (a.to_vec(), b.to_vec());
+    while !b.is_empty() {
+        let rem = poly_rem(&a, &b);
+        a = b;
+        b = rem;
+    }
+    if a.is_empty() { a } else { monic(&a) }
+}
+
+// p^(2^128) mod f by squaring 128 times
+fn frobenius(p: &[Gf128], f: &[Gf128]) -> Vec<Gf128> {
+    (0..128).fold(poly_rem(p, f), |q, _| poly_rem(&poly_mul(&q, &q), f))
+}
+
+// Distinct roots of a monic polynomial
+fn roots(f: &[Gf128]) -> Vec<Gf128> {
+    // Every element is a root of x^(2^128) - x, so the gcd with it is the product of (x - r) over
+    // the roots r of f
+    let x = [Gf128::zero(), Gf128::one()];
+    let linear = poly_gcd(f, &poly_add(&frobenius(&x, f), &x));
+    split_linear(&linear)
+}
+
+// Equal-degree factorization of a product of distinct linear factors. The trace a + a^2 + ... +
+// a^(2^127) of a random a is 0 or 1 at each root with equal chance, so its gcd with f splits the
+// roots in two.
+fn split_linear(f: &[Gf128]) -> Vec<Gf128> {
+    match f.len() {
+        0 | 1 => return vec![],
+        2 => return vec![f[0]],
+        _ => {}
+    }
+
+    let mut rng = thread_rng();
+    loop {
+        let a: Vec<Gf128> = (0..f.len() - 1)
+            .map(|_| Gf128((u128::from(rng.gen::<u64>()) << 64) | u128::from(rng.gen::<u64>())))
+            .collect();
+        let mut trace = poly_rem(&a, f);
+        let mut power = trace.clone();
+        for _ in 1..128 {
+            power = poly_rem(&poly_mul(&power, &power), f);
+            trace = poly_add(&trace, &power);
+        }
+
+        let factor = poly_gcd(f, &trace);
+        if factor.len() > 1 && factor.len() < f.len() {
+            let mut found = split_linear(&factor);
+            found.extend(split_linear(&poly_divmod(f, &factor).0));
+            return found;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use set8::gcm_nonce_reuse::*;
+    use aes::{Aes};
+    use gcm::{Gcm};
+    use util::{rand_bytes};
+
+    #[test]
+    fn gcm_polynomial_roots() {
+        let rs = [Gf128(3), Gf128(0xdead_beef << 64), Gf128(u128::MAX)];
+        let f = rs.iter().fold(vec![Gf128::one()], |f, &r| poly_mul(&f, &[r, Gf128::one()]));
+        // A repeated root is only returned once
+        let f = poly_mul(&f, &[rs[0], Gf128::one()]);
+
+        let mut found = roots(&f);
+        found.sort_by_key(|r| r.0);
+        assert_eq!(found, rs.to_vec());
+    }
+
+    #[test]
+    fn gcm_nonce_reuse_63() {
+        let gcm = Gcm::new(Aes::new(&rand_bytes(16)).unwrap()).unwrap();
+        let nonce = rand_bytes(12);
+        let seal = |plaintext: &[u8], ad: &[u8]| {
+            let (ciphertext, tag) = gcm.encrypt(plaintext, ad, &nonce).unwrap();
+            Message::new(ad, &ciphertext, &tag)
+        };
+
+        let messages = vec![
+            seal(b"Transfer $100 to Bob, reference 7", b"account 1"),
+            seal(b"Transfer $250 to Carol for the rent, reference 8", b"account 1"),
+            seal(b"Balance enquiry", b"account 2"),
+        ];
+        assert!(auth_key_candidates(&messages[0], &messages[1]).contains(&gcm.hash_key()));
+        let h = recover_auth_key(&messages).unwrap();
+        assert_eq!(h, gcm.hash_key());
+
+        // Knowing the first plaintext, rewrite it and forge a tag for the result
+        let mut ciphertext = messages[0].ciphertext.clone();
+        for (c, (old, new)) in ciphertext.iter_mut().zip(b"Transfer $100 to Bob".iter().zip(b"Transfer $999 to Eve".iter())) {
+            *c ^= old ^ new;
+        }
+        let tag = forge_tag(h, &messages[0], b"account 1", &ciphertext);
+        assert_eq!(gcm.decrypt(&ciphertext, b"account 1", &nonce, &tag), Ok(b"Transfer $999 to Eve, reference 7".to_vec()));
+    }
+}
@@ -0,0 +1,251 @@
+use num_bigint::{BigInt, RandBigInt};
+use num_traits::*;
+use rand::{thread_rng};
+use elliptic_curve::{FiniteCurve, Point};
+use discrete_log::{baby_step_giant_step, crt};
+use util::{bigint_to_bytes_be, hmac_sha256, sha256};
+
+// Cryptopals challenge 59: ECDH invalid-curve attacks.
+//
+// The addition formulas for y^2 = x^3 + ax + b never use b. If Bob multiplies whatever point he's
+// sent by his private key without checking it's on the curve, Eve can send points from a curve
+// with a different b whose group order has small factors. A point h of small order r forces the
+// shared secret into a set of r values, so the MAC Bob sends back reveals his key mod r. Doing this
+// for enough small primes and combining the results with the CRT recovers the whole key.
+//
+// https://toadstyle.org/cryptopals/59.txt
+
+const P: &str = "233970423115425145524320034830162017933";
+const A: i64 = -95051;
+const B: i64 = 11279326;
+const G: (&str, &str) = ("182", "85518893674295321206118380980485522083");
+const N: &str = "29246302889428143187362802287225875743";
+
+/// Curves y^2 = x^3 - 95051x + b with a different b and their orders, from the challenge
+pub const INVALID_CURVES: &[(i64, &str)] = &[
+    (210, "233970423115425145550826547352470124412"),
+    (504, "233970423115425145544350131142039591210"),
+    (727, "233970423115425145545378039958152057148"),
+];
+
+// Small prime factors are only searched for up to this bound
+const FACTOR_BOUND: u32 = 1 << 16;
+
+// Stop querying Bob once the key is narrowed down to this many candidates and finish with BSGS
+const BSGS_BOUND: u32 = 1 << 20;
+
+const MESSAGE: &[u8] = b"crazy flamboyant for the rap enjoyment";
+
+/// The curve from the challenge, its base point and the order of the base point
+pub fn challenge_curve() -> (FiniteCurve, Point, BigInt) {
+    let curve = FiniteCurve::new(BigInt::from(A), BigInt::from(B), parse(P));
+    let g = curve.point(parse(G.0), parse(G.1));
+    (curve, g, parse(N))
+}
+
+/// Bob does ECDH with anyone who sends him a public key and replies with a MAC under the shared key.
+pub struct Bob {
+    curve: FiniteCurve,
+    private_key: BigInt,
+}
+
+impl Bob {
+    pub fn new() -> Self {
+        let (_, _, n) = challenge_curve();
+        Bob::with_private_key(thread_rng().gen_bigint_range(&BigInt::one(), &n))
+    }
+
+    pub fn with_private_key(private_key: BigInt) -> Self {
+        let (curve, _, _) = challenge_curve();
+        Bob { curve, private_key }
+    }
+
+    pub fn public_key(&self) -> Point {
+        let (_, g, _) = challenge_curve();
+        g.mul(&self.private_key, &self.curve)
+    }
+
+    /// Reply to a public key without checking it's on the curve
+    pub fn respond(&self, public_key: &Point) -> Vec<u8> {
+        let shared = public_key.mul(&self.private_key, &self.curve);
+        mac(&shared, MESSAGE)
+    }
+
+    /// Reply to a public key only after validating it, which stops the attack
+    pub fn respond_validated(&self, public_key: &Point) -> Result<Vec<u8>, String> {
+        if public_key.is_infinity() || !self.curve.is_valid_point(public_key) {
+            return Err(String::from("Public key is not on the curve"));
+        }
+
+        Ok(self.respond(public_key))
+    }
+}
+
+impl Default for Bob {
+    fn default() -> Self {
+        Bob::new()
+    }
+}
+
+/// MAC over message keyed by SHA-256 of the shared point
+pub fn mac(shared: &Point, message: &[u8]) -> Vec<u8> {
+    let key = match shared {
+        Point::Infinity => sha256(b""),
+        Point::Coordinate { x, y } => {
+            let mut bytes = bigint_to_bytes_be(&x.value, 16, true);
+            bytes.extend(bigint_to_bytes_be(&y.value, 16, true));
+            sha256(&bytes)
+        }
+    };
+
+    hmac_sha256(&key, message)
+}
+
+/// Recover Bob's private key by sending him points of small order from invalid curves. Residues
+/// are collected from the smallest primes up until the key is pinned down to a range small enough
+/// for baby-step giant-step against his real public key.
+pub fn recover_private_key<F>(respond: F, bob_public_key: &Point) -> Option<BigInt> where F: Fn(&Point) -> Vec<u8> {
+    let (curve, g, n) = challenge_curve();
+
+    // Each small prime only needs to be used once, even if it divides several orders
+    let mut primes: Vec<(BigInt, i64, BigInt)> = vec![];
+    for (b, order) in INVALID_CURVES {
+        let order = parse(order);
+        for r in small_prime_factors(&order) {
+            if !primes.iter().any(|(q, _, _)| q == &r) {
+                primes.push((r, *b, order.clone()));
+            }
+        }
+    }
+    primes.sort();
+
+    let mut residues = vec![];
+    let mut modulus = BigInt::one();
+    for (r, b, order) in primes {
+        if &n / &modulus < BigInt::from(BSGS_BOUND) {
+            break;
+        }
+
+        let invalid_curve = FiniteCurve::new(BigInt::from(A), BigInt::from(b), parse(P));
+        let h = point_of_order(&invalid_curve, &order, &r);
+        let tag = respond(&h);
+
+        // Bob's shared secret is (k mod r)h, so find which multiple gives the same MAC
+        let mut candidate = Point::Infinity;
+        let mut residue = None;
+        let mut i = BigInt::zero();
+        while i < r {
+            if mac(&candidate, MESSAGE) == tag {
+                residue = Some(i.clone());
+                break;
+            }
+            candidate = candidate.add(&h, &invalid_curve);
+            i += 1;
+        }
+
+        modulus *= &r;
+        residues.push((residue?, r));
+    }
+
+    // k = x + mM where x = k mod M, so solve (k - x)G = m(MG) for m < n / M
+    let (x, modulus) = crt(&residues)?;
+    let target = bob_public_key.add(&g.mul(&x, &curve).inverse(), &curve);
+    let m = baby_step_giant_step(&g.mul(&modulus, &curve), &target, &(&n / &modulus + 1), &curve)?;
+
+    Some(x + m * modulus)
+}
+
+// Find a point of order exactly r. Clearing every factor but r from a random point leaves one
+// whose order is a power of r, which is then multiplied by r until the next step is infinity. The
+// r-torsion isn't always cyclic, so just dividing the order by r can send every point to infinity.
+fn point_of_order(curve: &FiniteCurve, order: &BigInt, r: &BigInt) -> Point {
+    let mut cofactor = order.clone();
+    while (&cofactor % r).is_zero() {
+        cofactor /= r;
+    }
+
+    let mut rng = thread_rng();
+    let p = parse(P);
+    loop {
+        let x = rng.gen_bigint_range(&BigInt::zero(), &p);
+        if let Ok(y) = curve.solve_y(&x, true) {
+            let mut h = Point::coord(curve.field_elem(x), y).mul(&cofactor, curve);
+            if h.is_infinity() {
+                continue;
+            }
+
+            let mut next = h.mul(r, curve);
+            while !next.is_infinity() {
+                h = next;
+                next = h.mul(r, curve);
+            }
+            return h;
+        }
+    }
+}
+
+// Prime factors of n below FACTOR_BOUND
+fn small_prime_factors(n: &BigInt) -> Vec<BigInt> {
+    let mut n = n.clone();
+    let mut factors = vec![];
+    for d in 2..FACTOR_BOUND {
+        let d = BigInt::from(d);
+        if (&n % &d).is_zero() {
+            factors.push(d.clone());
+            while (&n % &d).is_zero() {
+                n /= &d;
+            }
+        }
+    }
+
+    factors
+}
+
+fn parse(decimal: &str) -> BigInt {
+    BigInt::parse_bytes(decimal.as_bytes(), 10).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use set8::invalid_curve::*;
+
+    #[test]
+    fn invalid_curve_small_factors() {
+        let factors = small_prime_factors(&parse(INVALID_CURVES[0].1));
+        let expected: Vec<BigInt> = vec![2, 3, 11, 23, 31, 89, 4999, 28411, 45361].into_iter().map(BigInt::from).collect();
+        assert_eq!(factors, expected);
+    }
+
+    #[test]
+    fn invalid_curve_order_two_point() {
+        // Points of order 2 have y = 0, which still has to be encoded for the MAC
+        let (b, order) = INVALID_CURVES[0];
+        let invalid_curve = FiniteCurve::new(BigInt::from(A), BigInt::from(b), parse(P));
+        let h = point_of_order(&invalid_curve, &parse(order), &BigInt::from(2));
+        match h {
+            Point::Coordinate { ref y, .. } => assert_eq!(*y, 0),
+            Point::Infinity => panic!("expected a point of order 2"),
+        }
+
+        let odd = Bob::with_private_key(BigInt::from(12345));
+        assert_eq!(odd.respond(&h), mac(&h, MESSAGE));
+        let even = Bob::with_private_key(BigInt::from(12346));
+        assert_eq!(even.respond(&h), mac(&Point::Infinity, MESSAGE));
+    }
+
+    #[test]
+    fn invalid_curve_59() {
+        let bob = Bob::new();
+        let bob_public_key = bob.public_key();
+
+        let recovered = recover_private_key(|point| bob.respond(point), &bob_public_key);
+        assert_eq!(recovered, Some(bob.private_key.clone()));
+
+        // Bob refuses to touch the attacker's points once he validates them
+        let (b, order) = INVALID_CURVES[1];
+        let invalid_curve = FiniteCurve::new(BigInt::from(A), BigInt::from(b), parse(P));
+        let h = point_of_order(&invalid_curve, &parse(order), &BigInt::from(61));
+        assert!(bob.respond_validated(&h).is_err());
+        assert!(bob.respond_validated(&bob_public_key).is_ok());
+    }
+}
@@ -0,0 +1,3 @@
+pub mod ecdsa_nonce;
+pub mod invalid_curve;
+pub mod gcm_nonce_reuse;
@@ -1,6 +1,22 @@
 use num_bigint::{BigInt, Sign};
-use sha2::{Digest as Sha2Digest, Sha256};
+use sha2::{Digest as Sha2Digest, Sha256, Sha512};
 use ripemd160::{Ripemd160, Digest as RipemdDigest};
+use openssl;
+use openssl::sha;
+use rand::{thread_rng, Rng};
+
+/// Random bytes from OpenSSL's CSPRNG
+pub fn rand_bytes(len: usize) -> Vec<u8> {
+    let mut buf = vec![0; len];
+    openssl::rand::rand_bytes(&mut buf).unwrap();
+    buf
+}
+
+// Generate number in range from min to max inclusive
+pub fn rand_in_range(min: usize, max: usize) -> usize {
+    let mut rng = thread_rng();
+    rng.gen_range(min, max + 1)
+}
 
 pub fn sha256(data: &[u8]) -> Vec<u8> {
     Sha256::digest(data).to_vec()
@@ -11,6 +27,19 @@ pub fn sha256_bigint(data: &[u8]) -> BigInt {
     BigInt::from_bytes_be(Sign::Plus, &h)
 }
 
+/// SHA-1, which is broken for collisions but still what classic DSA pairs with
+pub fn sha1(data: &[u8]) -> Vec<u8> {
+    sha::sha1(data).to_vec()
+}
+
+pub fn sha1_bigint(data: &[u8]) -> BigInt {
+    BigInt::from_bytes_be(Sign::Plus, &sha1(data))
+}
+
+pub fn sha512(data: &[u8]) -> Vec<u8> {
+    Sha512::digest(data).to_vec()
+}
+
 /// Implements the Hash256 algorithm.
 ///
 /// Hash256(x) = SHA256(SHA256(x)) - two rounds of SHA-256 on data.
@@ -31,18 +60,72 @@ pub fn hash160(data: &[u8]) -> Vec<u8> {
     hasher.result().to_vec()
 }
 
+// SHA-256 works on 64 byte blocks
+const SHA256_BLOCK_SIZE: usize = 64;
+
+/// HMAC using SHA-256 as described in RFC 2104.
+///
+/// HMAC(K, m) = H((K' ^ opad) || H((K' ^ ipad) || m)) where K' is the key padded to a block.
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut block_key = if key.len() > SHA256_BLOCK_SIZE { sha256(key) } else { key.to_vec() };
+    block_key.resize(SHA256_BLOCK_SIZE, 0);
+
+    let mut inner: Vec<u8> = block_key.iter().map(|b| b ^ 0x36).collect();
+    inner.extend_from_slice(data);
+
+    let mut outer: Vec<u8> = block_key.iter().map(|b| b ^ 0x5c).collect();
+    outer.append(&mut sha256(&inner));
+    sha256(&outer)
+}
+
+/// HKDF using HMAC-SHA-256 as described in RFC 5869. Extracts a pseudorandom key from the input
+/// keying material and expands it into len bytes bound to info.
+pub fn hkdf_sha256(salt: &[u8], ikm: &[u8], info: &[u8], len: usize) -> Vec<u8> {
+    assert!(len <= 255 * 32, "HKDF output is limited to 255 blocks");
+
+    // Extract
+    let prk = hmac_sha256(salt, ikm);
+
+    // Expand: T(i) = HMAC(PRK, T(i - 1) || info || i)
+    let mut okm = vec![];
+    let mut t: Vec<u8> = vec![];
+    for i in 1..=255u8 {
+        if okm.len() >= len {
+            break;
+        }
+        t.extend_from_slice(info);
+        t.push(i);
+        t = hmac_sha256(&prk, &t);
+        okm.extend_from_slice(&t);
+    }
+
+    okm.truncate(len);
+    okm
+}
+
+/// Compare two byte slices in time that doesn't depend on where they first differ.
+pub fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len() && left.iter().zip(right.iter()).fold(0, |acc, (l, r)| acc | (l ^ r)) == 0
+}
+
 /// Convert a bigint into a 32 byte big-endian representation.
 /// We assume it's positive and not > 32 bytes and panic if those are not met.
 pub fn bigint_to_bytes32_be(num: &BigInt, padded: bool) -> Vec<u8> {
-    // We ignore the sign here and assume these are all positive values. This is true
-    // on curves over F_p which is really all we care about for now.
+    bigint_to_bytes_be(num, 32, padded)
+}
+
+/// Convert a bigint into a big-endian representation of at most len bytes.
+/// We assume it's non-negative and not > len bytes and panic if those are not met.
+pub fn bigint_to_bytes_be(num: &BigInt, len: usize, padded: bool) -> Vec<u8> {
+    // We ignore the sign here and assume these are all non-negative values. This is true
+    // on curves over F_p which is really all we care about for now. Zero has no sign.
     let (sign, mut bytes) = num.to_bytes_be();
-    if sign != Sign::Plus { panic!("BigInt is negative which is not currently allowed") }
-    if bytes.len() > 32 { panic!("BigInt is too large to fit within 32 bytes.") }
+    if sign == Sign::Minus { panic!("BigInt is negative which is not currently allowed") }
+    if bytes.len() > len { panic!("BigInt is too large to fit within {} bytes.", len) }
 
     if padded {
-        let mut res = Vec::with_capacity(32);
-        let num_padding_bytes = 32 - bytes.len();
+        let mut res = Vec::with_capacity(len);
+        let num_padding_bytes = len - bytes.len();
         for i in 0..num_padding_bytes { res.push(0); }
         res.append(&mut bytes);
         res
@@ -65,6 +148,12 @@ mod tests {
         assert_eq!(hash, "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9")
     }
 
+    #[test]
+    fn sha1_test() {
+        let hash = hex_encode(&sha1(b"hello world"));
+        assert_eq!(hash, "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed")
+    }
+
     #[test]
     fn hash256_test() {
         let s = b"hello world";
@@ -82,4 +171,32 @@ mod tests {
 
         assert_eq!(hash, "bc62d4b80d9e36da29c16c5d4d9f11731f36052c72401a76c23c0fb5a9b74423")
     }
+
+    #[test]
+    fn hmac_sha256_test() {
+        // RFC 4231 test cases 1, 2 and 6
+        let mac = hex_encode(&hmac_sha256(&[0x0b; 20], b"Hi There"));
+        assert_eq!(mac, "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7");
+
+        let mac = hex_encode(&hmac_sha256(b"Jefe", b"what do ya want for nothing?"));
+        assert_eq!(mac, "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+
+        let mac = hex_encode(&hmac_sha256(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First"));
+        assert_eq!(mac, "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54");
+    }
+
+    #[test]
+    fn hkdf_sha256_test() {
+        // RFC 5869 test case 1
+        let salt: Vec<u8> = (0x00..0x0d).collect();
+        let info: Vec<u8> = (0xf0..0xfa).collect();
+
+        let okm = hex_encode(&hkdf_sha256(&salt, &[0x0b; 22], &info, 42));
+        assert_eq!(okm, "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865");
+
+        // The largest output uses every counter value up to 255
+        let longest = hkdf_sha256(&salt, &[0x0b; 22], &info, 255 * 32);
+        assert_eq!(longest.len(), 255 * 32);
+        assert_eq!(hex_encode(&longest[..42]), okm);
+    }
 }
@@ -0,0 +1,128 @@
+use num_bigint::{BigInt, Sign};
+use montgomery::{MontgomeryCurve};
+
+// X25519 Diffie-Hellman as described in RFC 7748.
+//
+// Private keys are 32 random bytes which get clamped into a scalar, and public keys are the 32
+// byte little-endian u coordinate of kG where G has u = 9. The shared secret is the u coordinate
+// of a(bG) = b(aG). Any 32 byte string is accepted as a u coordinate, including points on the
+// twist, since clamping keeps the small subgroups from leaking more than a few bits.
+//
+// https://www.rfc-editor.org/rfc/rfc7748.html
+
+pub const KEY_LEN: usize = 32;
+
+/// u coordinate of the Curve25519 base point
+pub const BASE_POINT: [u8; 32] = [
+    9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// Multiply the point with encoded u coordinate u by the clamped scalar k.
+pub fn x25519(k: &[u8], u: &[u8]) -> Result<Vec<u8>, String> {
+    if k.len() != KEY_LEN || u.len() != KEY_LEN {
+        return Err(format!("Scalar and u coordinate must be {} bytes", KEY_LEN));
+    }
+
+    let curve = MontgomeryCurve::curve25519();
+    let u = curve.field_elem(decode_u_coordinate(u));
+    let result = curve.ladder(&decode_scalar(k), &u, 255);
+
+    let mut bytes = result.value.to_bytes_le().1;
+    bytes.resize(KEY_LEN, 0);
+    Ok(bytes)
+}
+
+/// Derive the public key for a private key
+pub fn pubkey(private_key: &[u8]) -> Result<Vec<u8>, String> {
+    x25519(private_key, &BASE_POINT)
+}
+
+/// Compute the shared secret with another party's public key. An all zero result means their key
+/// was a low order point and is rejected, as RFC 7748 section 6.1 suggests.
+pub fn shared_secret(private_key: &[u8], public_key: &[u8]) -> Result<Vec<u8>, String> {
+    let secret = x25519(private_key, public_key)?;
+    if secret.iter().all(|b| *b == 0) {
+        return Err(String::from("Public key is a low order point"));
+    }
+
+    Ok(secret)
+}
+
+// Clear the low 3 bits so the scalar is a multiple of the cofactor 8, clear bit 255 and set bit
+// 254 so every scalar has the same bit length.
+fn decode_scalar(k: &[u8]) -> BigInt {
+    let mut k = k.to_vec();
+    k[0] &= 0xf8;
+    k[31] &= 0x7f;
+    k[31] |= 0x40;
+    BigInt::from_bytes_le(Sign::Plus, &k)
+}
+
+// The top bit is ignored. Values of p and above aren't rejected and just get reduced mod p.
+fn decode_u_coordinate(u: &[u8]) -> BigInt {
+    let mut u = u.to_vec();
+    u[31] &= 0x7f;
+    BigInt::from_bytes_le(Sign::Plus, &u)
+}
+
+#[cfg(test)]
+mod tests {
+    use x25519::*;
+    use set1::{hex_decode, hex_encode};
+
+    #[test]
+    fn x25519_rfc7748_vectors() {
+        // RFC 7748 5.2
+        let output = x25519(
+            &hex_decode("a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4"),
+            &hex_decode("e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c"),
+        ).unwrap();
+        assert_eq!(hex_encode(&output), "c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552");
+
+        let output = x25519(
+            &hex_decode("4b66e9d4d1b4673c5ad22691957d6af5c11b6421e0ea01d42ca4169e7918ba0d"),
+            &hex_decode("e5210f12786811d3f4b7959d0538ae2c31dbe7106fc03c3efc4cd549c715a493"),
+        ).unwrap();
+        assert_eq!(hex_encode(&output), "95cbde9476e8907d7aade45cb4b873f88b595a68799fa152e6f8f7647aac7957");
+    }
+
+    #[test]
+    fn x25519_rfc7748_iterated() {
+        // Start with k = u = 9 and set u = k, k = x25519(k, u) each round
+        let mut k = BASE_POINT.to_vec();
+        let mut u = BASE_POINT.to_vec();
+        for i in 1..=1000 {
+            let output = x25519(&k, &u).unwrap();
+            u = k;
+            k = output;
+
+            if i == 1 {
+                assert_eq!(hex_encode(&k), "422c8e7a6227d7bca1350b3e2bb7279f7897b87bb6854b783c60e80311ae3079");
+            }
+        }
+        assert_eq!(hex_encode(&k), "684cf59ba83309552800ef566f2f4d3c1c3887c49360e3875f2eb94d99532c51");
+    }
+
+    #[test]
+    fn x25519_diffie_hellman() {
+        // RFC 7748 6.1
+        let alice = hex_decode("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let bob = hex_decode("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb");
+
+        let alice_public = pubkey(&alice).unwrap();
+        let bob_public = pubkey(&bob).unwrap();
+        assert_eq!(hex_encode(&alice_public), "8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a");
+        assert_eq!(hex_encode(&bob_public), "de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f");
+
+        let secret = shared_secret(&alice, &bob_public).unwrap();
+        assert_eq!(hex_encode(&secret), "4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742");
+        assert_eq!(shared_secret(&bob, &alice_public).unwrap(), secret);
+
+        // u = 0 and u = 1 are low order points
+        assert!(shared_secret(&alice, &[0; 32]).is_err());
+        let mut one = [0; 32];
+        one[0] = 1;
+        assert!(shared_secret(&alice, &one).is_err());
+    }
+}